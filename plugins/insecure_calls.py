@@ -0,0 +1,84 @@
+#!/usr/bin/env python3
+"""reference sec_engine plugin, reports insecure calls in python and javascript sources
+
+speaks the `sec-engine-plugin/1` protocol documented in src/parser/plugin.rs: one json document on
+stdout, progress on stderr, exit code 0 when the scan ran (findings or not).
+
+    python3 plugins/insecure_calls.py <dir>
+
+directories named in INSECURE_CALLS_EXCLUDE (comma separated) are skipped.
+"""
+
+import hashlib
+import json
+import os
+import re
+import sys
+
+PROTOCOL = "sec-engine-plugin/1"
+VERSION = "1.0.0"
+SKIP_DIRS = {".git", "node_modules", "target", "venv", ".venv", "__pycache__"}
+
+# (id, extensions, pattern, severity, name, cwe)
+RULES = [
+    ("eval", (".py", ".js", ".ts"), r"\beval\s*\(", "high", "eval of dynamic code", "CWE-95"),
+    ("exec", (".py",), r"\bexec\s*\(", "high", "exec of dynamic code", "CWE-95"),
+    ("pickle", (".py",), r"\bpickle\.loads?\s*\(", "high", "deserialization of untrusted data with pickle", "CWE-502"),
+    ("yaml-load", (".py",), r"\byaml\.load\s*\((?![^)]*Loader)", "medium", "yaml.load without a safe loader", "CWE-502"),
+    ("shell", (".py",), r"subprocess\.\w+\([^)]*shell\s*=\s*True", "high", "subprocess started through the shell", "CWE-78"),
+    ("tls-verify", (".py",), r"verify\s*=\s*False", "medium", "tls certificate verification disabled", "CWE-295"),
+    ("md5", (".py", ".js", ".ts"), r"\b(?:hashlib\.md5|createHash\(\s*['\"]md5)", "low", "md5 used as hash", "CWE-328"),
+    ("inner-html", (".js", ".ts"), r"\.innerHTML\s*=", "medium", "assignment to innerHTML", "CWE-79"),
+]
+
+
+def scan_file(path, rel):
+    findings = []
+    try:
+        with open(path, encoding="utf-8", errors="replace") as f:
+            lines = f.readlines()
+    except OSError as e:
+        print(f"skip {rel}: {e}", file=sys.stderr)
+        return findings
+    for rule_id, exts, pattern, severity, name, cwe in RULES:
+        if not rel.endswith(exts):
+            continue
+        for no, line in enumerate(lines, 1):
+            if re.search(pattern, line):
+                findings.append({
+                    "name": name,
+                    "severity": severity,
+                    "description": f"`{line.strip()}` matches rule `{rule_id}`",
+                    "identifiers": [cwe, f"insecure-calls.{rule_id}"],
+                    "file": rel,
+                    "line": no,
+                    "end_line": no,
+                    "fingerprint": hashlib.sha1(f"{rule_id}:{rel}:{line.strip()}".encode()).hexdigest(),
+                })
+    return findings
+
+
+def main():
+    root = sys.argv[1] if len(sys.argv) > 1 else os.environ.get("SEC_ENGINE_TARGET", ".")
+    excluded = {d for d in os.environ.get("INSECURE_CALLS_EXCLUDE", "").split(",") if d}
+    findings = []
+    scanned = 0
+    for dirpath, dirnames, filenames in os.walk(root):
+        dirnames[:] = sorted(d for d in dirnames if d not in SKIP_DIRS and d not in excluded)
+        for filename in sorted(filenames):
+            path = os.path.join(dirpath, filename)
+            rel = os.path.relpath(path, root).replace(os.sep, "/")
+            if rel.endswith((".py", ".js", ".ts")):
+                scanned += 1
+                findings.extend(scan_file(path, rel))
+    print(f"scanned {scanned} files, {len(findings)} findings", file=sys.stderr)
+    json.dump({
+        "protocol": PROTOCOL,
+        "tool": {"name": "insecure-calls", "version": VERSION},
+        "findings": findings,
+    }, sys.stdout, indent=2)
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,178 @@
+//! command line of the engines, `sec_engine <engine|auto|remediate> [--lang zh] [files, dirs or globs]`
+//!
+//! the external command plugins of `PLUGIN_CONFIG` run before the engines, see [`crate::parser::plugin`]
+
+use std::{cmp, path::Path};
+
+use crate::{
+    conf::setting::{GRYPE_REPORT, PLUGIN_CONFIG, REMEDIATE_DRY_RUN, TARGET, TRIVY_REPORT},
+    parser::{
+        base::{BaseParser, BaseReport},
+        container, dast,
+        detect::{self, Engine, Format, Input},
+        iac, license, plugin,
+        registry::ParserRegistry,
+        sast, sca, secret,
+    },
+    utils::{gate, i18n, remediate},
+};
+
+/// run the command of `args` (program name first), formats of `registry` are detected before the
+/// built-in ones. returns the process exit code of the gate
+pub async fn run(mut args: Vec<String>, registry: &ParserRegistry) -> i32 {
+    if let Some(pos) = args.iter().position(|arg| arg == "--lang") {
+        if pos + 1 < args.len() {
+            i18n::set_lang(args[pos + 1].as_str());
+        }
+        args.drain(pos..cmp::min(pos + 2, args.len()));
+    }
+    if let 1 = args.len() {
+        log::error!("not enough args");
+        // println!("not enough args");ca
+        return 0;
+    }
+    let target = TARGET.to_string();
+    // files, directories or globs after the command, the report files under TARGET by default
+    let mut specs: Vec<String> = args[2..].iter().filter(|arg| !arg.starts_with("--")).cloned().collect();
+    if specs.is_empty() {
+        specs.push(target.to_string());
+        if !target.is_empty() {
+            for report in [TRIVY_REPORT.as_str(), GRYPE_REPORT.as_str()] {
+                specs.push(Path::new(target.as_str()).join(report).to_string_lossy().to_string());
+            }
+        }
+    }
+    let mut inputs = detect::inputs(&specs, registry);
+    // external command plugins of the engines about to run add their outputs as inputs
+    let engines: Vec<Engine> = match args[1].as_str() {
+        "auto" => Engine::ALL.to_vec(),
+        "remediate" => vec![Engine::Sca],
+        name => Engine::from_name(name).into_iter().collect(),
+    };
+    let plugins = plugin::load(PLUGIN_CONFIG.as_str());
+    inputs.extend(plugin::inputs(&plugins, &engines, target.as_str()).await);
+
+    match args[1].as_str() {
+        "auto" => {
+            for engine in Engine::ALL {
+                run_engine(engine, &inputs, registry).await;
+            }
+        }
+        "remediate" => match load_sca(&inputs, registry) {
+            Some(mut report) => {
+                report.filter().await;
+                report.enrich();
+                let dry_run = *REMEDIATE_DRY_RUN || args.iter().any(|arg| arg == "--dry-run");
+                remediate::run(target.as_str(), &report.upgrades(), dry_run).await;
+            }
+            None => log::error!("no sca report in {specs:?}"),
+        },
+        name => match Engine::from_name(name) {
+            Some(engine) => {
+                let mut found = run_engine(engine, &inputs, registry).await;
+                // the iac scanning job writes gl-sast-report.json too
+                if engine == Engine::Sast && inputs.iter().any(|i| i.format == Format::Kics) {
+                    found = run_engine(Engine::Iac, &inputs, registry).await || found;
+                }
+                if !found {
+                    log::error!("no {name} report in {specs:?}");
+                }
+            }
+            None => log::error!("not support"),
+        },
+    }
+
+    gate::exit_code()
+}
+
+// parse every input of `engine` into `report`, false when there is none
+fn load<T, R: BaseParser<T>>(report: &mut R, inputs: &[Input], engine: Engine, registry: &ParserRegistry) -> bool {
+    let selected = detect::select(inputs, engine);
+    for input in selected.iter() {
+        log::info!("{}: {} ({})", engine.name(), input.path, input.format);
+        match &input.format {
+            Format::Plugin(name, _) => match registry.get(name) {
+                Some(registration) => {
+                    let mut parser = registration.build();
+                    parser.parse(input.content.as_str());
+                    report.merge(&parser.findings());
+                }
+                None => log::warn!("parser {name} is not registered, skip {}", input.path),
+            },
+            Format::Command(name, _) => match plugin::parse_output(engine, input.content.as_str()) {
+                Ok(findings) => {
+                    report.merge(&findings);
+                }
+                Err(e) => log::warn!("plugin {name} broke the protocol, {e}"),
+            },
+            _ => {
+                report.parse_file(input.content.as_str(), input.path.as_str());
+            }
+        }
+    }
+
+    !selected.is_empty()
+}
+
+/// report of `engine` merged from its inputs, false when no input belongs to it
+async fn run_engine(engine: Engine, inputs: &[Input], registry: &ParserRegistry) -> bool {
+    match engine {
+        Engine::Sast => {
+            let mut report = sast::SASTReport::new();
+            if !load(&mut report, inputs, engine, registry) {
+                return false;
+            }
+            report.get_diffs().await;
+            report.report().await;
+        }
+        Engine::Sca => match load_sca(inputs, registry) {
+            Some(mut report) => report.report().await,
+            None => return false,
+        },
+        Engine::Secret => {
+            let mut report = secret::SecretReport::new();
+            if !load(&mut report, inputs, engine, registry) {
+                return false;
+            }
+            report.report().await;
+        }
+        Engine::Iac => {
+            let mut report = iac::IacReport::new();
+            if !load(&mut report, inputs, engine, registry) {
+                return false;
+            }
+            report.report().await;
+        }
+        Engine::License => {
+            let mut report = license::LicenseReport::new();
+            if !load(&mut report, inputs, engine, registry) {
+                return false;
+            }
+            report.report().await;
+        }
+        Engine::Container => {
+            let mut report = container::ContainerReport::new();
+            if !load(&mut report, inputs, engine, registry) {
+                return false;
+            }
+            report.report().await;
+        }
+        Engine::Dast => {
+            let mut report = dast::DastReport::new();
+            if !load(&mut report, inputs, engine, registry) {
+                return false;
+            }
+            report.report().await;
+        }
+    }
+
+    true
+}
+
+// gitlab report first, the sboms add the dependency graph and their own vulnerabilities,
+// findings of other scanners already reported by another input are skipped
+fn load_sca(inputs: &[Input], registry: &ParserRegistry) -> Option<sca::SCAReport> {
+    let mut report = sca::SCAReport::new();
+
+    load(&mut report, inputs, Engine::Sca, registry).then_some(report)
+}
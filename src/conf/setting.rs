@@ -1,6 +1,6 @@
 #!setting
 use lazy_static::lazy_static;
-use std::{env, sync::Arc, sync::Mutex};
+use std::{env, sync::Mutex};
 
 // const GITLAB_HOST: &str = "http://10.10.10.167/";
 // const GITLAB_HOST: &str = "https://d8f5-116-228-147-46.ngrok-free.app/";
@@ -9,14 +9,8 @@ use std::{env, sync::Arc, sync::Mutex};
 // 5Za9qoy5SdFWcfXTueug
 
 const EXCLUDED_USER_IDS: [&str; 3] = ["2", "61", "113"];
-const ISSUE_TEMPLATE: &str = "
-<!-- sec title -->
-## {}
-<!-- sec description -->
-{}
-<!-- vuln table -->
-{}
-";
+// default issue templates, see utils::template for the syntax and context
+pub const DEFAULT_ISSUE_TITLE_TEMPLATE: &str = "{{{title}}}";
 
 pub static mut HG_AI_CLIENT: Option<&mut gradio::Client> = None;
 
@@ -31,4 +25,74 @@ lazy_static! {
         env::var("CI_MERGE_REQUEST_IID").unwrap_or(String::new());
     pub static ref AI_TOKEN: String = env::var("AI_TOKEN").unwrap_or(String::new());
     pub static ref AI_HOST: String = env::var("AI_HOST").unwrap_or(String::new());
+    // empty keeps the built-in mix: english title and table headers, chinese text (see utils::i18n)
+    pub static ref LANG: Mutex<String> =
+        Mutex::new(env::var("SEC_LANG").unwrap_or(String::new()));
+    pub static ref TARGET: String = env::var("TARGET").unwrap_or(String::new());
+    // checkout of the scanned sources, iac resources are read from here (TARGET when unset)
+    pub static ref CI_PROJECT_DIR: String = env::var("CI_PROJECT_DIR").unwrap_or(String::new());
+    // native scanner reports under TARGET, mapped into the sca / secret / iac / license engines
+    pub static ref TRIVY_REPORT: String = env::var("TRIVY_REPORT").unwrap_or(String::from("trivy-report.json"));
+    pub static ref GRYPE_REPORT: String = env::var("GRYPE_REPORT").unwrap_or(String::from("grype-report.json"));
+    // external command plugins, see parser::plugin for the config and the output protocol.
+    // opt-in and never read from TARGET by default, the checkout is controlled by whoever pushes
+    pub static ref PLUGIN_CONFIG: String = env::var("PLUGIN_CONFIG").unwrap_or(String::new());
+    // seconds a plugin may run when its config sets no `timeout`
+    pub static ref PLUGIN_TIMEOUT: u64 = env::var("PLUGIN_TIMEOUT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300);
+    pub static ref CI_PIPELINE_ID: String = env::var("CI_PIPELINE_ID").unwrap_or(String::new());
+    pub static ref CI_COMMIT_SHA: String = env::var("CI_COMMIT_SHA").unwrap_or(String::new());
+    pub static ref CI_COMMIT_REF_NAME: String =
+        env::var("CI_COMMIT_REF_NAME").unwrap_or(String::new());
+    // comma separated export formats, e.g. "csv,jsonl"
+    pub static ref EXPORT_FORMAT: String = env::var("EXPORT_FORMAT").unwrap_or(String::new());
+    pub static ref EXPORT_DIR: String = env::var("EXPORT_DIR").unwrap_or(TARGET.to_string());
+    pub static ref CI_JOB_ID: String = env::var("CI_JOB_ID").unwrap_or(String::new());
+    // gitlab rejects issue descriptions and notes over 1048576 chars
+    pub static ref ISSUE_MAX_LEN: usize = env::var("ISSUE_MAX_LEN")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1_000_000);
+    pub static ref REPORT_ARTIFACT_URL: String = env::var("REPORT_ARTIFACT_URL").unwrap_or(format!(
+        "{}/-/jobs/{}/artifacts/browse",
+        CI_PROJECT_URL.as_str(),
+        CI_JOB_ID.as_str()
+    ));
+    // shorten the aggregated sca descriptions with the llm
+    pub static ref SCA_LLM_DECORATE: bool = env::var("SCA_LLM_DECORATE").map_or(false, |v| v == "true" || v == "1");
+    pub static ref CODE_QUALITY_REPORT: String = env::var("CODE_QUALITY_REPORT")
+        .unwrap_or(format!("{}/gl-code-quality-report.json", TARGET.as_str()));
+    // local osv / nvd json dump for offline cve enrichment, see utils::cvedb
+    pub static ref CVE_DB_PATH: String = env::var("CVE_DB_PATH").unwrap_or(String::new());
+    pub static ref CVE_CACHE_DIR: String = env::var("CVE_CACHE_DIR")
+        .unwrap_or(format!("{}.cache", CVE_DB_PATH.trim_end_matches('/')));
+    // epss scores csv and cisa kev catalog json, see utils::threat
+    pub static ref EPSS_PATH: String = env::var("EPSS_PATH").unwrap_or(String::new());
+    pub static ref KEV_PATH: String = env::var("KEV_PATH").unwrap_or(String::new());
+    // quality gate, see utils::gate
+    pub static ref GATE_SEVERITY: String = env::var("GATE_SEVERITY").unwrap_or(String::new());
+    pub static ref GATE_FAIL_ON_KEV: bool = env::var("GATE_FAIL_ON_KEV").map_or(false, |v| v == "true" || v == "1");
+    // license policy, comma separated spdx ids, `*` suffix for a prefix, see utils::spdx
+    pub static ref LICENSE_ALLOW: String = env::var("LICENSE_ALLOW").unwrap_or(String::new());
+    pub static ref LICENSE_DENY: String = env::var("LICENSE_DENY").unwrap_or(String::new());
+    pub static ref LICENSE_REVIEW: String = env::var("LICENSE_REVIEW").unwrap_or(String::new());
+    // allow / review / deny for unlisted licenses and for packages without license (allow when unset)
+    pub static ref LICENSE_DEFAULT: String = env::var("LICENSE_DEFAULT").unwrap_or(String::new());
+    pub static ref LICENSE_UNKNOWN: String = env::var("LICENSE_UNKNOWN").unwrap_or(String::new());
+    // merged cyclonedx sbom with the sca findings as vex, not written when unset
+    pub static ref SBOM_OUTPUT: String = env::var("SBOM_OUTPUT").unwrap_or(String::new());
+    // spdx 2.3 json export of the sca dependencies, not written when unset
+    pub static ref SPDX_OUTPUT: String = env::var("SPDX_OUTPUT").unwrap_or(String::new());
+    // extra spdx creator, e.g. "Person: Jane (jane@example.com)"
+    pub static ref SPDX_CREATOR: String = env::var("SPDX_CREATOR").unwrap_or(String::new());
+    pub static ref CI_PROJECT_PATH: String = env::var("CI_PROJECT_PATH").unwrap_or(String::new());
+    pub static ref CI_PROJECT_NAMESPACE: String =
+        env::var("CI_PROJECT_NAMESPACE").unwrap_or(String::new());
+    // remediate: print the patch instead of opening a merge request
+    pub static ref REMEDIATE_DRY_RUN: bool = env::var("REMEDIATE_DRY_RUN").map_or(false, |v| v == "true" || v == "1");
+    // remediate: branch name, `sec/remediate-{CI_COMMIT_REF_NAME}` by default so every pipeline
+    // updates the open merge request of the branch instead of opening another one
+    pub static ref REMEDIATE_BRANCH: String = env::var("REMEDIATE_BRANCH").unwrap_or(String::new());
 }
@@ -0,0 +1,6 @@
+//! sec_engine as a library, crates of the workspace add their report formats through
+//! [`parser::registry::ParserRegistry`] and run [`cli::run`] from their own binary
+pub mod cli;
+pub mod conf;
+pub mod parser;
+pub mod utils;
@@ -1,13 +1,20 @@
 //! parser common trait
 
 use crate::{
-    conf::setting::{CI_MERGE_REQUEST_IID, CI_PROJECT_ID},
-    utils::gitlab::{get_commit_diff, get_mr_commit_hash, Issue},
+    conf::setting::{CI_PROJECT_DIR, TARGET},
+    utils::gitlab::Issue,
 };
 pub trait BaseParser<T> {
     fn parse(self: &mut Self, content: &str) -> &Vec<T>;
-    fn export(self: &Self, excel: &str) -> bool;
+    /// parse the report read from `_fpath`, for formats that need the files next to it
+    fn parse_file(self: &mut Self, content: &str, _fpath: &str) -> &Vec<T> {
+        self.parse(content)
+    }
+    fn export(self: &Self, fpath: &str) -> bool;
     fn to_issue(self: &Self) -> Issue;
+    fn findings(self: &Self) -> Vec<Finding>;
+    /// add the findings of an out-of-tree parser, see [`super::registry`]
+    fn merge(self: &mut Self, findings: &[Finding]) -> &Vec<T>;
     async fn filter(self: &mut Self) -> Vec<T>;
     async fn is_in_diff(self: &Self, fpath: &str) -> bool {
         false
@@ -29,3 +36,78 @@ pub struct RiskOwner {
     service: String,
     url: String,
 }
+
+/// engine independent view of a single vul, used by the report writers
+#[derive(Debug, Clone, Default)]
+pub struct Finding {
+    pub engine: String,
+    pub name: String,
+    pub description: String,
+    pub severity: String,
+    pub identifiers: Vec<String>,
+    pub file: String,
+    pub line: u64,
+    // last line of a multi-line finding, 0 when only `line` is known
+    pub end_line: u64,
+    pub dependency: String,
+    pub fingerprint: String,
+    // listed in the cisa known exploited vulnerabilities catalog
+    pub kev: bool,
+    pub priority: Option<f64>,
+}
+
+impl Finding {
+    /// `(name, version)` of `dependency`, maven names keep their `group:artifact` colon
+    pub fn package(self: &Self) -> (String, String) {
+        match self.dependency.rsplit_once(':') {
+            Some((name, version)) => (name.to_string(), version.to_string()),
+            None => (self.dependency.to_string(), String::new()),
+        }
+    }
+}
+
+/// stable fingerprint (fnv-1a 64) of the given parts, same input gives same output across runs
+pub fn fingerprint(parts: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for b in part.as_bytes().iter().chain([0u8].iter()) {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    format!("{:016x}", hash)
+}
+
+/// fingerprint given by the scanner (external plugins), else the stable one of `parts`
+pub fn fingerprint_or(given: &str, parts: &[&str]) -> String {
+    match given {
+        "" => fingerprint(parts),
+        given => given.to_string(),
+    }
+}
+
+/// gitlab style severity name of the upper / lower case levels used by other scanners
+pub fn severity_name(severity: &str) -> String {
+    match severity.to_lowercase().as_str() {
+        "critical" => "Critical",
+        "high" => "High",
+        "medium" | "moderate" => "Medium",
+        "low" => "Low",
+        "info" | "trace" | "negligible" => "Info",
+        _ => "Unknown",
+    }
+    .to_string()
+}
+
+/// path relative to the project, scanners running in the ci job report absolute paths
+pub fn relative_path(path: &str) -> String {
+    let path = path.trim_start_matches("file://");
+    let path = [CI_PROJECT_DIR.as_str(), TARGET.as_str()]
+        .iter()
+        .filter(|dir| !dir.is_empty())
+        .find_map(|dir| path.strip_prefix(format!("{}/", dir.trim_end_matches('/')).as_str()))
+        .unwrap_or(path);
+
+    path.trim_start_matches("./").to_string()
+}
@@ -0,0 +1,28 @@
+//! `cargo audit --json` report, rustsec advisories of the crates in Cargo.lock
+
+use serde_json::Value;
+
+pub fn is_cargo_audit(report: &Value) -> bool {
+    report["vulnerabilities"]["list"].is_array() && report["database"].is_object()
+}
+
+/// `(kind, item)` of the vulnerabilities and of the warnings carrying an advisory (unmaintained, unsound, ...)
+pub fn advisories(report: &Value) -> Vec<(&str, &Value)> {
+    let mut items: Vec<(&str, &Value)> = vec![];
+    for item in report["vulnerabilities"]["list"].as_array().into_iter().flatten() {
+        items.push(("vulnerability", item));
+    }
+    for (kind, warnings) in report["warnings"].as_object().into_iter().flatten() {
+        // yanked crates come without an advisory
+        for item in warnings.as_array().into_iter().flatten().filter(|w| w["advisory"].is_object()) {
+            items.push((kind.as_str(), item));
+        }
+    }
+
+    items
+}
+
+/// rustsec page of an advisory
+pub fn url(advisory: &Value) -> String {
+    format!("https://rustsec.org/advisories/{}.html", advisory["id"].as_str().unwrap_or(""))
+}
@@ -0,0 +1,435 @@
+// container scanning
+
+use std::collections::HashMap;
+
+use crate::{
+    conf::setting::*,
+    utils::{
+        export, gate,
+        gitlab::{self, Issue},
+        i18n, render, template,
+        version::{self, Scheme},
+    },
+};
+
+use super::base::{fingerprint_or, severity_name, BaseParser, BaseReport, Finding};
+use serde_json::{json, Value};
+
+const ISSUE_TEMPLATE: &str = "{{#if base_images}}<br>**{{{base_image_intro}}}**<br>\n\
+{{#each base_images}}- {{{this}}}\n{{/each}}\n{{/if}}<br>{{{intro}}}<br>\n\
+{{#each groups}}\n\
+----------------------------------{{location}}------------------------------------------\n\
+{{{header}}}{{#each findings}}\n|{{id}}{{{record}}}{{/each}}\n\n{{/each}}";
+
+/// a vulnerable os package of an image
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContainerVul {
+    id: String,
+    message: String,
+    description: String,
+    severity: String,
+    cve: String,
+    image: String,
+    operating_system: String,
+    package: String,
+    version: String,
+    fixed_versions: Vec<String>,
+    solution: String,
+    identifiers: Vec<String>,
+    // stable id given by the scanner, computed from the fields when empty
+    fingerprint: String,
+}
+
+impl ContainerVul {
+    fn get_risk_num(self: &Self) -> i32 {
+        match self.severity.to_lowercase().as_str() {
+            "critical" => 4,
+            "high" => 3,
+            "medium" => 2,
+            "low" => 1,
+            _ => 0,
+        }
+    }
+
+    // `image (os)`, the issue group
+    fn group(self: &Self) -> String {
+        if self.operating_system.is_empty() {
+            self.image.to_string()
+        } else {
+            format!("{} ({})", self.image, self.operating_system)
+        }
+    }
+
+    fn to_issue_record(self: &Self) -> String {
+        let mut values: Vec<String> = Vec::new();
+        values.push(render::code(format!("{}:{}", self.package, self.version).as_str()));
+        values.push(render::cell(self.severity.as_str(), 20));
+        values.push(render::cell(self.cve.as_str(), 300));
+        values.push(render::cell(self.solution.as_str(), 300));
+        values.push(render::cell(self.description.as_str(), render::MAX_CELL_LEN));
+
+        render::row(&values)
+    }
+}
+
+impl From<&Finding> for ContainerVul {
+    // `file` is the image, `dependency` the os package
+    fn from(finding: &Finding) -> Self {
+        let (package, version) = finding.package();
+        let id = finding.identifiers.first().cloned().unwrap_or_default();
+
+        ContainerVul {
+            message: finding.name.to_string(),
+            description: finding.description.to_string(),
+            severity: severity_name(finding.severity.as_str()),
+            cve: finding.identifiers.iter().find(|i| i.starts_with("CVE-")).cloned().unwrap_or(id.to_string()),
+            id: id,
+            image: finding.file.to_string(),
+            package: package,
+            version: version,
+            identifiers: finding.identifiers.clone(),
+            fingerprint: finding.fingerprint.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Value> for ContainerVul {
+    fn from(value: &Value) -> Self {
+        let location = &value["location"];
+        let mut identifiers: Vec<String> = vec![];
+        for id in value["identifiers"].as_array().unwrap_or(&vec![]) {
+            if let Some(name) = id["value"].as_str().or(id["name"].as_str()) {
+                if !identifiers.contains(&name.to_string()) {
+                    identifiers.push(name.to_string());
+                }
+            }
+        }
+        let cve = identifiers
+            .iter()
+            .find(|id| id.starts_with("CVE-"))
+            .or(identifiers.first())
+            .cloned()
+            .unwrap_or(value["cve"].as_str().unwrap_or("").to_string());
+        let solution = value["solution"].as_str().unwrap_or("").to_string();
+        let message = value["name"]
+            .as_str()
+            .or(value["message"].as_str())
+            .unwrap_or(cve.as_str())
+            .to_string();
+
+        ContainerVul {
+            id: value["id"].as_str().unwrap_or("").to_string(),
+            message: message,
+            description: value["description"].as_str().unwrap_or("").to_string(),
+            severity: value["severity"].as_str().unwrap_or("Unknown").to_string(),
+            cve: cve,
+            image: location["image"]
+                .as_str()
+                .or(location["default_branch_image"].as_str())
+                .unwrap_or("")
+                .to_string(),
+            operating_system: location["operating_system"].as_str().unwrap_or("").to_string(),
+            package: location["dependency"]["package"]["name"].as_str().unwrap_or("").to_string(),
+            version: location["dependency"]["version"].as_str().unwrap_or("").to_string(),
+            // e.g. "Upgrade openssl to 1.1.1n-0+deb11u3"
+            fixed_versions: version::parse_fixed_versions(solution.as_str()),
+            solution: solution,
+            identifiers: identifiers,
+            fingerprint: String::new(),
+        }
+    }
+}
+
+// remediation diffs are base64 encoded
+fn decode_base64(text: &str) -> Option<String> {
+    let mut bytes: Vec<u8> = vec![];
+    let mut buf: u32 = 0;
+    let mut bits = 0;
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        buf = (buf << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buf >> bits) as u8);
+            buf &= (1 << bits) - 1;
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+#[derive(Debug)]
+pub struct ContainerReport {
+    pub engine: String,
+    pub(crate) vuls: Vec<ContainerVul>,
+    // image group -> one row per os package, see aggregate
+    pub(crate) merged_vuls_map: Vec<(String, Vec<ContainerVul>)>,
+    // base image upgrades from the remediations, `FROM` lines of the dockerfile diff or the summary
+    base_images: Vec<String>,
+}
+
+impl ContainerReport {
+    pub fn new() -> Self {
+        ContainerReport {
+            engine: String::from("Container"),
+            vuls: vec![],
+            merged_vuls_map: vec![],
+            base_images: vec![],
+        }
+    }
+
+    fn parse_remediations(self: &mut Self, report: &Value) {
+        for remediation in report["remediations"].as_array().unwrap_or(&vec![]) {
+            let summary = remediation["summary"].as_str().unwrap_or("").trim();
+            let diff = decode_base64(remediation["diff"].as_str().unwrap_or("")).unwrap_or_default();
+            let from: Vec<String> = diff
+                .lines()
+                .filter_map(|line| line.strip_prefix('+'))
+                .map(|line| line.trim())
+                .filter(|line| line.len() > 5 && line[..5].eq_ignore_ascii_case("FROM "))
+                .map(|line| line[5..].trim().to_string())
+                .collect();
+            let suggestions: Vec<String> = if !from.is_empty() {
+                from.iter()
+                    .map(|image| i18n::tf("container.base_image.upgrade", &[("image", render::code(image).as_str())]))
+                    .collect()
+            } else if summary.to_lowercase().contains("image") {
+                vec![render::escape(summary)]
+            } else {
+                vec![]
+            };
+            for suggestion in suggestions {
+                if !self.base_images.contains(&suggestion) {
+                    self.base_images.push(suggestion);
+                }
+            }
+
+            // package upgrades named by the remediation
+            let versions = version::parse_fixed_versions(summary);
+            for fix in remediation["fixes"].as_array().unwrap_or(&vec![]) {
+                let id = fix["id"].as_str().or(fix["cve"].as_str()).unwrap_or("");
+                for vul in self.vuls.iter_mut().filter(|vul| !id.is_empty() && (vul.id == id || vul.cve == id)) {
+                    for v in versions.iter() {
+                        if !vul.fixed_versions.contains(v) {
+                            vul.fixed_versions.push(v.to_string());
+                        }
+                    }
+                    if vul.solution.is_empty() {
+                        vul.solution = summary.to_string();
+                    }
+                }
+            }
+        }
+    }
+
+    /// one row per os package (image, package, version): highest severity, every cve and the
+    /// lowest version fixing all of them
+    pub fn aggregate(self: &mut Self) -> &Vec<(String, Vec<ContainerVul>)> {
+        let mut keys: Vec<(String, String, String)> = vec![];
+        let mut groups: HashMap<(String, String, String), Vec<&ContainerVul>> = HashMap::new();
+        for vul in self.vuls.iter() {
+            let key = (vul.group(), vul.package.to_string(), vul.version.to_string());
+            if !groups.contains_key(&key) {
+                keys.push(key.clone());
+            }
+            groups.entry(key).or_default().push(vul);
+        }
+
+        let mut result: Vec<(String, Vec<ContainerVul>)> = vec![];
+        for key in keys.iter() {
+            let vuls = groups.get(key).unwrap();
+            let top = vuls.iter().max_by_key(|vul| vul.get_risk_num()).unwrap();
+            let mut cves: Vec<String> = vec![];
+            let mut names: Vec<String> = vec![];
+            for vul in vuls.iter() {
+                if !vul.cve.is_empty() && !cves.contains(&vul.cve) {
+                    cves.push(vul.cve.to_string());
+                }
+                if !vul.message.is_empty() && !names.contains(&vul.message) {
+                    names.push(vul.message.to_string());
+                }
+            }
+            let fixes: Vec<Vec<String>> = vuls.iter().map(|vul| vul.fixed_versions.clone()).collect();
+            let solution = match version::minimal_safe_version(Scheme::Generic, key.2.as_str(), &fixes) {
+                Some(version) => i18n::tf("sca.solution.upgrade", &[("version", version.as_str())]),
+                None => i18n::t("container.solution.default"),
+            };
+            let row = ContainerVul {
+                cve: cves.join(", "),
+                description: names.join("; "),
+                solution: solution,
+                identifiers: cves,
+                ..(*top).clone()
+            };
+            match result.iter_mut().find(|(group, _)| *group == key.0) {
+                Some((_, rows)) => rows.push(row),
+                None => result.push((key.0.to_string(), vec![row])),
+            }
+        }
+        for (_, rows) in result.iter_mut() {
+            rows.sort_by(|a, b| b.get_risk_num().cmp(&a.get_risk_num()).then(a.package.cmp(&b.package)));
+        }
+        self.merged_vuls_map = result;
+
+        &self.merged_vuls_map
+    }
+
+    pub fn issue_context(self: &Self) -> Value {
+        const COLS: [&str; 6] = ["id", "dependency", "severity", "cve", "solution", "description"];
+        let to_values = |vuls: &Vec<ContainerVul>| -> Vec<Value> {
+            vuls.iter()
+                .enumerate()
+                .map(|(idx, vul)| {
+                    template::finding_value(idx + 1, &self.to_finding(vul), vul.to_issue_record())
+                })
+                .collect()
+        };
+        let groups: Vec<Value> = self
+            .merged_vuls_map
+            .iter()
+            .map(|(group, vuls)| json!({ "location": group, "findings": to_values(vuls) }))
+            .collect();
+
+        template::context(
+            self.engine.as_str(),
+            to_values(&self.vuls),
+            json!({
+                "header": i18n::table_header(&COLS),
+                "intro": i18n::t("report.intro"),
+                "base_image_intro": i18n::t("container.base_image"),
+                "base_images": self.base_images,
+                "grouped": true,
+                "groups": groups,
+            }),
+        )
+    }
+
+    fn to_finding(self: &Self, vul: &ContainerVul) -> Finding {
+        Finding {
+            engine: self.engine.to_string(),
+            name: vul.message.to_string(),
+            description: vul.description.to_string(),
+            severity: vul.severity.to_string(),
+            identifiers: vul.identifiers.clone(),
+            file: vul.image.to_string(),
+            line: 0,
+            dependency: format!("{}:{}", vul.package, vul.version),
+            fingerprint: fingerprint_or(vul.fingerprint.as_str(), &[
+                self.engine.as_str(),
+                vul.image.as_str(),
+                vul.package.as_str(),
+                vul.version.as_str(),
+                vul.cve.as_str(),
+            ]),
+            ..Default::default()
+        }
+    }
+}
+
+impl BaseParser<ContainerVul> for ContainerReport {
+    fn parse(self: &mut Self, content: &str) -> &Vec<ContainerVul> {
+        let report: Value = serde_json::from_str(content).expect("json format error");
+        for vul in report["vulnerabilities"].as_array().unwrap_or(&vec![]) {
+            self.vuls.push(ContainerVul::from(vul));
+        }
+        self.parse_remediations(&report);
+        println!("vul(container) count: {}", self.vuls.len());
+
+        &self.vuls
+    }
+
+    fn merge(self: &mut Self, findings: &[Finding]) -> &Vec<ContainerVul> {
+        self.vuls.extend(findings.iter().map(ContainerVul::from));
+
+        &self.vuls
+    }
+
+    fn export(self: &Self, fpath: &str) -> bool {
+        export::write(&self.findings(), fpath)
+    }
+
+    fn to_issue(self: &Self) -> Issue {
+        let ctx = self.issue_context();
+        let mut issue: Issue = Issue::new();
+        issue.engine = self.engine.to_string();
+        issue.project_id = CI_PROJECT_ID.to_string();
+        issue.title = template::issue_title(&ctx);
+        issue.description = template::issue_description(&ctx, ISSUE_TEMPLATE);
+        issue
+    }
+
+    fn findings(self: &Self) -> Vec<Finding> {
+        self.vuls.iter().map(|vul| self.to_finding(vul)).collect()
+    }
+
+    async fn filter(self: &mut Self) -> Vec<ContainerVul> {
+        self.vuls.clone()
+    }
+}
+
+impl BaseReport<ContainerVul> for ContainerReport {
+    async fn report(self: &mut Self) {
+        // images are not repository files, no code quality entries
+        self.filter().await;
+        for fpath in export::export_paths(self.engine.as_str()) {
+            self.export(fpath.as_str());
+        }
+        gate::evaluate(&self.findings());
+
+        if self.vuls.len() < 1 {
+            println!("no container vuls, skip create new issue...");
+            return;
+        }
+        if CI_MERGE_REQUEST_IID.as_str() == "" {
+            log::error!("no merge request, skip create issue...");
+            return;
+        }
+
+        self.aggregate();
+        let mut issue = self.to_issue();
+        let notes = template::split_issue(&mut issue, &self.issue_context());
+        let _ = gitlab::new_issue_with_notes(&issue, &notes).await;
+        if let Some(note) = template::mr_note(&self.issue_context()) {
+            let _ = gitlab::new_mr_comment(note).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_image_suggestions() {
+        let report = json!({
+            "vulnerabilities": [{
+                "id": "v1",
+                "name": "CVE-2024-0001 in openssl",
+                "severity": "High",
+                "identifiers": [{ "value": "CVE-2024-0001" }],
+                "location": { "image": "app:latest", "dependency": { "package": { "name": "openssl" }, "version": "3.0.1" } },
+            }],
+            "remediations": [
+                // -FROM node:18 / +FROM node:20
+                { "summary": "Upgrade the base image", "fixes": [{ "id": "v1" }],
+                  "diff": "LS0tIGEvRG9ja2VyZmlsZQorKysgYi9Eb2NrZXJmaWxlCi1GUk9NIG5vZGU6MTgKK0ZST00gbm9kZToyMAo=" },
+                { "summary": "Use a slim image & rebuild", "fixes": [] },
+            ],
+        });
+        let mut container = ContainerReport::new();
+        container.parse(report.to_string().as_str());
+        let description = container.to_issue().description;
+        assert!(description.contains("- 升级基础镜像至 `node:20`\n"), "{description}");
+        assert!(description.contains("- Use a slim image &amp; rebuild\n"), "{description}");
+        assert!(!description.contains("\\`") && !description.contains("&amp;amp;"), "{description}");
+    }
+}
@@ -0,0 +1,313 @@
+//! owasp dependency-check report (`--format JSON` or `XML`), the xml form is read into the json shape
+
+use serde_json::{json, Value};
+
+use crate::utils::cvss;
+
+pub fn is_depcheck(report: &Value) -> bool {
+    report["reportSchema"].is_string() && report["dependencies"].is_array()
+}
+
+pub fn is_depcheck_xml(content: &str) -> bool {
+    content.trim_start().starts_with('<') && content.contains("<analysis") && content.contains("dependency-check")
+}
+
+// bodies of the `<tag>` / `<tag attr="..">` elements of `text`, same named elements do not nest in the report
+fn elements<'a>(text: &'a str, tag: &str) -> Vec<(&'a str, &'a str)> {
+    let open = format!("<{tag}");
+    let close = format!("</{tag}>");
+    let mut items: Vec<(&str, &str)> = vec![];
+    let mut pos = 0;
+    while let Some(idx) = text[pos..].find(open.as_str()) {
+        let start = pos + idx;
+        let after = start + open.len();
+        // `<dependency` must not match `<dependencyPath`
+        if !text[after..].starts_with(['>', ' ', '/', '\n', '\r', '\t']) {
+            pos = after;
+            continue;
+        }
+        let Some(head_len) = text[after..].find('>') else {
+            break;
+        };
+        let head_end = after + head_len;
+        let attrs = &text[after..head_end];
+        if attrs.ends_with('/') {
+            items.push((attrs, ""));
+            pos = head_end + 1;
+            continue;
+        }
+        let Some(body_len) = text[head_end + 1..].find(close.as_str()) else {
+            break;
+        };
+        items.push((attrs, &text[head_end + 1..head_end + 1 + body_len]));
+        pos = head_end + 1 + body_len + close.len();
+    }
+
+    items
+}
+
+fn unescape(text: &str) -> String {
+    let text = text.trim();
+    let text = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+        .map(|t| t.to_string())
+        .unwrap_or_else(|| {
+            text.replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&")
+        });
+
+    text
+}
+
+// text of the first `<tag>` of `text`
+fn text(text: &str, tag: &str) -> Value {
+    match elements(text, tag).first() {
+        Some((_, body)) => Value::String(unescape(body)),
+        None => Value::Null,
+    }
+}
+
+fn attr(attrs: &str, name: &str) -> Value {
+    let key = format!("{name}=\"");
+    match attrs.find(key.as_str()) {
+        Some(idx) => {
+            let rest = &attrs[idx + key.len()..];
+            Value::String(unescape(&rest[..rest.find('"').unwrap_or(rest.len())]))
+        }
+        None => Value::Null,
+    }
+}
+
+// `<cvssV3>` children under their json names
+fn cvss_v3(body: &str) -> Value {
+    let mut obj = json!({});
+    for name in [
+        "baseScore",
+        "attackVector",
+        "attackComplexity",
+        "privilegesRequired",
+        "userInteraction",
+        "scope",
+        "confidentialityImpact",
+        "integrityImpact",
+        "availabilityImpact",
+        "baseSeverity",
+        "version",
+    ] {
+        let value = text(body, name);
+        obj[name] = match name {
+            "baseScore" => value.as_str().and_then(|s| s.parse::<f64>().ok()).map_or(Value::Null, |s| json!(s)),
+            _ => value,
+        };
+    }
+
+    obj
+}
+
+/// xml report in the shape of the json report, `None` when the document is not a dependency-check report
+pub fn from_xml(content: &str) -> Option<Value> {
+    let (_, analysis) = elements(content, "analysis").into_iter().next()?;
+    let mut dependencies: Vec<Value> = vec![];
+    let (_, body) = elements(analysis, "dependencies").into_iter().next().unwrap_or(("", ""));
+    for (attrs, dependency) in elements(body, "dependency") {
+        let packages: Vec<Value> = elements(dependency, "identifiers")
+            .iter()
+            .flat_map(|(_, ids)| elements(ids, "package"))
+            .map(|(attrs, package)| json!({ "id": text(package, "id"), "confidence": attr(attrs, "confidence") }))
+            .collect();
+        let mut vulnerabilities: Vec<Value> = vec![];
+        for (_, vulns) in elements(dependency, "vulnerabilities") {
+            for (attrs, vuln) in elements(vulns, "vulnerability") {
+                let cwes: Vec<Value> = elements(vuln, "cwe").iter().map(|(_, c)| Value::String(unescape(c))).collect();
+                let references: Vec<Value> = elements(vuln, "reference")
+                    .iter()
+                    .map(|(_, r)| json!({ "source": text(r, "source"), "url": text(r, "url"), "name": text(r, "name") }))
+                    .collect();
+                let software: Vec<Value> = elements(vuln, "software")
+                    .iter()
+                    .map(|(attrs, s)| {
+                        json!({ "software": {
+                            "id": unescape(s),
+                            "vulnerabilityIdMatched": attr(attrs, "vulnerabilityIdMatched"),
+                            "versionEndExcluding": attr(attrs, "versionEndExcluding"),
+                        }})
+                    })
+                    .collect();
+                vulnerabilities.push(json!({
+                    "source": attr(attrs, "source"),
+                    "name": text(vuln, "name"),
+                    "severity": text(vuln, "severity"),
+                    "description": text(vuln, "description"),
+                    "cvssv3": elements(vuln, "cvssV3").first().map_or(Value::Null, |(_, body)| cvss_v3(body)),
+                    "cvssv2": elements(vuln, "cvssV2").first().map_or(Value::Null, |(_, body)| json!({
+                        "score": text(body, "score").as_str().and_then(|s| s.parse::<f64>().ok()),
+                    })),
+                    "cwes": cwes,
+                    "references": references,
+                    "vulnerableSoftware": software,
+                }));
+            }
+        }
+        dependencies.push(json!({
+            "isVirtual": attr(attrs, "isVirtual") == "true",
+            "fileName": text(dependency, "fileName"),
+            "filePath": text(dependency, "filePath"),
+            "packages": packages,
+            "vulnerabilities": vulnerabilities,
+        }));
+    }
+
+    Some(json!({
+        "reportSchema": "xml",
+        "dependencies": dependencies,
+    }))
+}
+
+/// `(name, version, purl)` of the first package identifier, maven names as `group/artifact`
+pub fn package(dependency: &Value) -> Option<(String, String, String)> {
+    let purl = dependency["packages"]
+        .as_array()?
+        .iter()
+        .find_map(|p| p["id"].as_str().filter(|id| id.starts_with("pkg:")))?;
+    let path = purl.split(['?', '#']).next().unwrap_or(purl);
+    let rest = path.strip_prefix("pkg:")?.split_once('/')?.1;
+    let (name, version) = rest.rsplit_once('@').unwrap_or((rest, ""));
+
+    Some((name.replace("%40", "@"), version.replace("%2B", "+"), purl.to_string()))
+}
+
+/// `(score, vector)` of the v3 metrics, v2 only gives the score
+pub fn cvss(vuln: &Value) -> Option<(f64, String)> {
+    let v3 = &vuln["cvssv3"];
+    if v3.is_object() {
+        let metric = |name: &str| v3[name].as_str().and_then(|v| v.chars().next()).unwrap_or('X');
+        let vector = format!(
+            "CVSS:{}/AV:{}/AC:{}/PR:{}/UI:{}/S:{}/C:{}/I:{}/A:{}",
+            v3["version"].as_str().filter(|v| v.starts_with('3')).unwrap_or("3.1"),
+            metric("attackVector"),
+            metric("attackComplexity"),
+            metric("privilegesRequired"),
+            metric("userInteraction"),
+            metric("scope"),
+            metric("confidentialityImpact"),
+            metric("integrityImpact"),
+            metric("availabilityImpact"),
+        );
+        if let Some(parsed) = cvss::parse(vector.as_str()) {
+            return Some((parsed.score, parsed.vector));
+        }
+        if let Some(score) = v3["baseScore"].as_f64() {
+            return Some((score, String::new()));
+        }
+    }
+
+    vuln["cvssv2"]["score"].as_f64().map(|score| (score, String::new()))
+}
+
+/// first fixed versions of the matched cpe ranges
+///
+/// dependency-check flags the matched cpe only, the other configurations are skipped when one is flagged
+pub fn fixed_versions(vuln: &Value) -> Vec<String> {
+    let mut versions: Vec<String> = vec![];
+    let items: Vec<&Value> = vuln["vulnerableSoftware"].as_array().into_iter().flatten().collect();
+    let flagged = items.iter().any(|item| !item["software"]["vulnerabilityIdMatched"].is_null());
+    for item in items {
+        let software = &item["software"];
+        let matched = &software["vulnerabilityIdMatched"];
+        if flagged && matched != "true" && matched != true {
+            continue;
+        }
+        if let Some(v) = software["versionEndExcluding"].as_str().filter(|v| !versions.contains(&v.to_string())) {
+            versions.push(v.to_string());
+        }
+    }
+
+    versions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<analysis xmlns="https://jeremylong.github.io/DependencyCheck/dependency-check.2.5.xsd">
+  <dependencies>
+    <dependency isVirtual="false">
+      <fileName>jackson-databind-2.9.8.jar</fileName>
+      <filePath>/app/lib/jackson-databind-2.9.8.jar</filePath>
+      <dependencyPath>/app/lib</dependencyPath>
+      <identifiers>
+        <package confidence="HIGHEST"><id>pkg:maven/com.fasterxml.jackson.core/jackson-databind@2.9.8</id></package>
+      </identifiers>
+      <vulnerabilities>
+        <vulnerability source="NVD">
+          <name>CVE-2020-36518</name>
+          <severity>HIGH</severity>
+          <cvssV3>
+            <baseScore>7.5</baseScore><attackVector>NETWORK</attackVector><attackComplexity>LOW</attackComplexity>
+            <privilegesRequired>NONE</privilegesRequired><userInteraction>NONE</userInteraction><scope>UNCHANGED</scope>
+            <confidentialityImpact>NONE</confidentialityImpact><integrityImpact>NONE</integrityImpact>
+            <availabilityImpact>HIGH</availabilityImpact><version>3.1</version>
+          </cvssV3>
+          <cwes><cwe>CWE-787</cwe></cwes>
+          <description><![CDATA[deeply <nested> objects & a stack overflow]]></description>
+          <references>
+            <reference><source>MISC</source><url>https://github.com/x/issues?a=1&amp;b=2</url><name>issue</name></reference>
+          </references>
+          <vulnerableSoftware>
+            <software vulnerabilityIdMatched="true" versionEndExcluding="2.12.6.1">cpe:2.3:a:fasterxml:jackson-databind:*</software>
+            <software versionEndExcluding="2.13.2.1">cpe:2.3:a:fasterxml:jackson-databind:*</software>
+          </vulnerableSoftware>
+        </vulnerability>
+      </vulnerabilities>
+    </dependency>
+    <dependency isVirtual="true">
+      <fileName>clean.jar</fileName>
+    </dependency>
+  </dependencies>
+</analysis>"#;
+
+    #[test]
+    fn test_from_xml() {
+        assert!(is_depcheck_xml(XML));
+        let report = from_xml(XML).unwrap();
+        assert!(is_depcheck(&report));
+        let dependencies = report["dependencies"].as_array().unwrap();
+        assert_eq!(dependencies.len(), 2);
+        let dependency = &dependencies[0];
+        assert_eq!(dependency["fileName"], "jackson-databind-2.9.8.jar");
+        assert_eq!(dependency["isVirtual"], false);
+        assert_eq!(dependencies[1]["isVirtual"], true);
+        assert_eq!(
+            package(dependency),
+            Some((
+                String::from("com.fasterxml.jackson.core/jackson-databind"),
+                String::from("2.9.8"),
+                String::from("pkg:maven/com.fasterxml.jackson.core/jackson-databind@2.9.8")
+            ))
+        );
+
+        let vuln = &dependency["vulnerabilities"][0];
+        assert_eq!((vuln["source"].as_str(), vuln["name"].as_str()), (Some("NVD"), Some("CVE-2020-36518")));
+        assert_eq!(vuln["description"], "deeply <nested> objects & a stack overflow");
+        assert_eq!(vuln["cwes"], json!(["CWE-787"]));
+        assert_eq!(vuln["references"][0]["url"], "https://github.com/x/issues?a=1&b=2");
+        assert_eq!(cvss(vuln).map(|(score, _)| score), Some(7.5));
+        // only the matched cpe names the fix
+        assert_eq!(fixed_versions(vuln), vec![String::from("2.12.6.1")]);
+    }
+
+    #[test]
+    fn test_fixed_versions_without_flags() {
+        let vuln = json!({ "vulnerableSoftware": [
+            { "software": { "id": "cpe:a", "versionEndExcluding": "1.2.0" } },
+            { "software": { "id": "cpe:b", "versionEndExcluding": "2.1.0" } },
+            { "software": { "id": "cpe:c" } },
+        ] });
+        assert_eq!(fixed_versions(&vuln), vec![String::from("1.2.0"), String::from("2.1.0")]);
+    }
+}
@@ -0,0 +1,456 @@
+//! input discovery and format sniffing, every report file is routed to the engines reading its format
+
+use std::{fmt, fs, path::Path};
+
+use serde_json::Value;
+
+use crate::utils::cyclonedx::Bom;
+
+use super::{cargo_audit, depcheck, grype, iac, npm_audit, registry::ParserRegistry, sast::SastFormat, trivy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    Sast,
+    Sca,
+    Secret,
+    Iac,
+    License,
+    Container,
+    Dast,
+}
+
+impl Engine {
+    pub const ALL: [Engine; 7] = [
+        Engine::Sast,
+        Engine::Sca,
+        Engine::Secret,
+        Engine::Iac,
+        Engine::License,
+        Engine::Container,
+        Engine::Dast,
+    ];
+
+    /// command line name, e.g. `sast`
+    pub fn name(self: &Self) -> &'static str {
+        match self {
+            Engine::Sast => "sast",
+            Engine::Sca => "sca",
+            Engine::Secret => "secret",
+            Engine::Iac => "iac",
+            Engine::License => "license",
+            Engine::Container => "container",
+            Engine::Dast => "dast",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Engine::ALL.into_iter().find(|engine| engine.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Format {
+    /// gitlab security report of the given `scan.type`, e.g. `sast`, `dependency_scanning`
+    Gitlab(String),
+    GitlabLicense,
+    /// kics output, the gitlab iac scanning report or a raw `results.json`
+    Kics,
+    /// sarif log, lower case name of the tool of the first run
+    Sarif(String),
+    CycloneDx,
+    Trivy,
+    Grype,
+    Semgrep,
+    Bandit,
+    Gosec,
+    Eslint,
+    DependencyCheck,
+    NpmAudit,
+    CargoAudit,
+    Gitleaks,
+    TruffleHog,
+    /// out-of-tree format of the [`ParserRegistry`], its name and engine
+    Plugin(String, Engine),
+    /// `sec-engine-plugin/1` output of an external command plugin, its name and engine
+    Command(String, Engine),
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Gitlab(kind) => write!(f, "gitlab {kind}"),
+            Format::Sarif(tool) => write!(f, "sarif ({tool})"),
+            Format::Plugin(name, _) => write!(f, "{name}"),
+            Format::Command(name, _) => write!(f, "plugin {name}"),
+            other => write!(f, "{}", format!("{other:?}").to_lowercase()),
+        }
+    }
+}
+
+impl Format {
+    /// engines parsing this format, trivy and sboms feed several of them
+    pub fn engines(self: &Self) -> Vec<Engine> {
+        match self {
+            Format::Gitlab(kind) => match kind.as_str() {
+                "sast" => vec![Engine::Sast],
+                "dependency_scanning" => vec![Engine::Sca, Engine::License],
+                "secret_detection" => vec![Engine::Secret],
+                "container_scanning" | "cluster_image_scanning" => vec![Engine::Container],
+                "dast" | "api_fuzzing" => vec![Engine::Dast],
+                _ => vec![],
+            },
+            Format::GitlabLicense => vec![Engine::License],
+            Format::Kics => vec![Engine::Iac],
+            Format::Sarif(tool) if tool.contains("gitleaks") || tool.contains("trufflehog") => vec![Engine::Secret],
+            Format::Sarif(_) => vec![Engine::Sast],
+            Format::CycloneDx | Format::Grype => vec![Engine::Sca, Engine::License],
+            Format::Trivy => vec![Engine::Sca, Engine::Secret, Engine::Iac, Engine::License],
+            Format::Semgrep | Format::Bandit | Format::Gosec | Format::Eslint => vec![Engine::Sast],
+            Format::DependencyCheck | Format::NpmAudit | Format::CargoAudit => vec![Engine::Sca],
+            Format::Gitleaks | Format::TruffleHog => vec![Engine::Secret],
+            Format::Plugin(_, engine) | Format::Command(_, engine) => vec![*engine],
+        }
+    }
+
+    // merge order within an engine: gitlab reports first, sboms add the dependency graph,
+    // other scanners only add what is not known yet. license sources come before package lists
+    fn rank(self: &Self, engine: Engine) -> u8 {
+        match self {
+            Format::GitlabLicense => 0,
+            Format::Gitlab(_) if engine == Engine::License => 2,
+            Format::Gitlab(_) | Format::Kics => 0,
+            Format::CycloneDx => 1,
+            _ => 3,
+        }
+    }
+}
+
+/// a readable input file and its detected format
+#[derive(Debug, Clone)]
+pub struct Input {
+    pub path: String,
+    pub format: Format,
+    pub content: String,
+}
+
+// `sast` from `gl-sast-report.json`, for gitlab reports written before `scan.type`
+fn gitlab_kind(fpath: &str) -> Option<String> {
+    let name = fpath.rsplit('/').next().unwrap_or(fpath);
+    let kind = name.strip_prefix("gl-")?.strip_suffix("-report.json")?;
+
+    Some(kind.replace('-', "_"))
+}
+
+/// format of a report, `fpath` is only used for gitlab reports without `scan.type`
+pub fn detect(fpath: &str, content: &str) -> Option<Format> {
+    if depcheck::is_depcheck_xml(content) {
+        return Some(Format::DependencyCheck);
+    }
+    let Ok(report) = serde_json::from_str::<Value>(content) else {
+        // trufflehog prints one json object per line
+        let first = content.lines().find(|l| !l.trim().is_empty())?;
+        let finding: Value = serde_json::from_str(first).ok()?;
+        return finding["DetectorName"].is_string().then_some(Format::TruffleHog);
+    };
+    if report.as_array().map_or(false, |items| items.is_empty()) {
+        return None;
+    }
+    if report["DetectorName"].is_string() {
+        return Some(Format::TruffleHog);
+    }
+    // kics writes a gitlab sast report too
+    if iac::is_kics(&report) {
+        return Some(Format::Kics);
+    }
+    if let Some(kind) = report["scan"]["type"].as_str() {
+        return Some(Format::Gitlab(kind.to_string()));
+    }
+    if Bom::is_cyclonedx(&report) {
+        return Some(Format::CycloneDx);
+    }
+    if trivy::is_trivy(&report) {
+        return Some(Format::Trivy);
+    }
+    if grype::is_grype(&report) {
+        return Some(Format::Grype);
+    }
+    if depcheck::is_depcheck(&report) {
+        return Some(Format::DependencyCheck);
+    }
+    if npm_audit::is_npm_audit(&report) {
+        return Some(Format::NpmAudit);
+    }
+    if cargo_audit::is_cargo_audit(&report) {
+        return Some(Format::CargoAudit);
+    }
+    if report["licenses"].is_array() && report["dependencies"].is_array() {
+        return Some(Format::GitlabLicense);
+    }
+    if report.as_array().map_or(false, |leaks| leaks.iter().all(|l| l["RuleID"].is_string())) {
+        return Some(Format::Gitleaks);
+    }
+
+    match SastFormat::detect(&report)? {
+        SastFormat::Sarif => {
+            let tool = report["runs"][0]["tool"]["driver"]["name"].as_str().unwrap_or("");
+            Some(Format::Sarif(tool.to_lowercase()))
+        }
+        SastFormat::Gitlab => gitlab_kind(fpath).map(Format::Gitlab),
+        SastFormat::Semgrep => Some(Format::Semgrep),
+        SastFormat::Bandit => Some(Format::Bandit),
+        SastFormat::Gosec => Some(Format::Gosec),
+        SastFormat::Eslint => Some(Format::Eslint),
+    }
+}
+
+// `*` and `?` match within a path segment, `**` any number of segments
+fn segment_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => segment_match(&pattern[1..], name) || (!name.is_empty() && segment_match(pattern, &name[1..])),
+        (Some(b'?'), Some(_)) => segment_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) => p == n && segment_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+fn glob_match(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (Some(&"**"), _) => glob_match(&pattern[1..], path) || (!path.is_empty() && glob_match(pattern, &path[1..])),
+        (Some(p), Some(s)) => segment_match(p.as_bytes(), s.as_bytes()) && glob_match(&pattern[1..], &path[1..]),
+        _ => false,
+    }
+}
+
+// every file below `dir`, vcs and dependency directories are skipped
+fn walk(dir: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "node_modules" && name != "target" {
+                walk(&path, files);
+            }
+        } else {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+}
+
+fn glob(spec: &str) -> Vec<String> {
+    let segments: Vec<&str> = spec.split('/').collect();
+    let fixed = segments.iter().position(|s| s.contains(['*', '?'])).unwrap_or(segments.len());
+    let base = match segments[..fixed].join("/") {
+        base if base.is_empty() && spec.starts_with('/') => String::from("/"),
+        base if base.is_empty() => String::from("."),
+        base => base,
+    };
+    let mut files: Vec<String> = vec![];
+    walk(Path::new(base.as_str()), &mut files);
+    files.retain(|file| {
+        let rest = file.strip_prefix(base.as_str()).unwrap_or(file).trim_start_matches('/');
+        glob_match(&segments[fixed..], &rest.split('/').collect::<Vec<&str>>())
+    });
+
+    files
+}
+
+/// files named by `specs`: plain files, the report files directly inside a directory, or globs like `reports/**/*.json`
+pub fn expand(specs: &[String]) -> Vec<String> {
+    let mut files: Vec<String> = vec![];
+    for spec in specs.iter() {
+        let path = Path::new(spec.as_str());
+        let mut found: Vec<String> = if spec.contains(['*', '?']) {
+            glob(spec.as_str())
+        } else if path.is_dir() {
+            fs::read_dir(path)
+                .map(|dir| {
+                    dir.filter_map(|e| e.ok())
+                        .map(|e| e.path())
+                        .filter(|p| p.is_file())
+                        .filter(|p| p.extension().map_or(false, |ext| ["json", "sarif", "xml"].contains(&ext.to_string_lossy().as_ref())))
+                        .map(|p| p.to_string_lossy().to_string())
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else if path.is_file() {
+            vec![spec.to_string()]
+        } else {
+            log::warn!("not such file! {spec}");
+            vec![]
+        };
+        found.sort();
+        for file in found {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    files
+}
+
+/// the readable files of `specs` in a known format, registered formats win over the built-in ones
+pub fn inputs(specs: &[String], registry: &ParserRegistry) -> Vec<Input> {
+    let mut inputs: Vec<Input> = vec![];
+    for fpath in expand(specs) {
+        let Ok(content) = fs::read_to_string(fpath.as_str()) else {
+            log::warn!("unreadable file, skip {fpath}");
+            continue;
+        };
+        let plugin = registry
+            .detect(fpath.as_str(), content.as_str())
+            .map(|r| Format::Plugin(r.name.to_string(), r.engine));
+        match plugin.or_else(|| detect(fpath.as_str(), content.as_str())) {
+            Some(format) => {
+                log::info!("{fpath}: {format}");
+                inputs.push(Input {
+                    path: fpath,
+                    format: format,
+                    content: content,
+                });
+            }
+            None => log::debug!("unknown format, skip {fpath}"),
+        }
+    }
+
+    inputs
+}
+
+/// inputs of `engine` in merge order
+pub fn select(inputs: &[Input], engine: Engine) -> Vec<&Input> {
+    let mut selected: Vec<&Input> = inputs.iter().filter(|i| i.format.engines().contains(&engine)).collect();
+    selected.sort_by_key(|i| i.format.rank(engine));
+
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(doc: &str) -> Option<Format> {
+        detect("report.json", doc)
+    }
+
+    #[test]
+    fn test_detect_gitlab() {
+        assert_eq!(
+            detected(r#"{"scan":{"type":"dependency_scanning"},"vulnerabilities":[]}"#),
+            Some(Format::Gitlab(String::from("dependency_scanning")))
+        );
+        // reports written before `scan.type` are named by their file
+        assert_eq!(
+            detect("out/gl-secret-detection-report.json", r#"{"vulnerabilities":[]}"#),
+            Some(Format::Gitlab(String::from("secret_detection")))
+        );
+        assert_eq!(detected(r#"{"vulnerabilities":[]}"#), None);
+        assert_eq!(detected(r#"{"licenses":[],"dependencies":[]}"#), Some(Format::GitlabLicense));
+    }
+
+    #[test]
+    fn test_kics_before_scan_type() {
+        let doc = r#"{"scan":{"type":"sast","scanner":{"id":"kics"}},"vulnerabilities":[]}"#;
+        assert_eq!(detected(doc), Some(Format::Kics));
+        assert_eq!(detected(r#"{"kics_version":"1.7","queries":[]}"#), Some(Format::Kics));
+    }
+
+    #[test]
+    fn test_detect_dependency_scanners() {
+        assert_eq!(detected(r#"{"bomFormat":"CycloneDX","specVersion":"1.5"}"#), Some(Format::CycloneDx));
+        assert_eq!(detected(r#"{"SchemaVersion":2,"ArtifactName":".","Results":[]}"#), Some(Format::Trivy));
+        assert_eq!(detected(r#"{"matches":[],"descriptor":{"name":"grype"}}"#), Some(Format::Grype));
+        assert_eq!(detected(r#"{"reportSchema":"1.1","dependencies":[]}"#), Some(Format::DependencyCheck));
+        assert_eq!(
+            detected(r#"<?xml version="1.0"?><analysis xmlns="https://jeremylong.github.io/DependencyCheck/dependency-check.2.5.xsd"></analysis>"#),
+            Some(Format::DependencyCheck)
+        );
+        // npm and cargo audit carry a `vulnerabilities` object, not the gitlab list
+        assert_eq!(detected(r#"{"auditReportVersion":2,"vulnerabilities":{}}"#), Some(Format::NpmAudit));
+        assert_eq!(detected(r#"{"advisories":{},"actions":[]}"#), Some(Format::NpmAudit));
+        assert_eq!(detected(r#"{"database":{},"vulnerabilities":{"list":[]}}"#), Some(Format::CargoAudit));
+    }
+
+    #[test]
+    fn test_detect_secret_scanners() {
+        // gitleaks is a list of objects like eslint
+        assert_eq!(detected(r#"[{"RuleID":"aws-access-token","File":"a.env"}]"#), Some(Format::Gitleaks));
+        let trufflehog = "{\"DetectorName\":\"AWS\",\"Raw\":\"x\"}\n{\"DetectorName\":\"Github\",\"Raw\":\"y\"}\n";
+        assert_eq!(detected(trufflehog), Some(Format::TruffleHog));
+        assert_eq!(detected("[]"), None);
+        assert_eq!(detected("not json"), None);
+    }
+
+    #[test]
+    fn test_detect_sast_tools() {
+        let sarif = r#"{"version":"2.1.0","runs":[{"tool":{"driver":{"name":"Gitleaks"}}}]}"#;
+        assert_eq!(detected(sarif), Some(Format::Sarif(String::from("gitleaks"))));
+        assert_eq!(detected(r#"{"results":[{"check_id":"rule"}],"errors":[]}"#), Some(Format::Semgrep));
+        assert_eq!(detected(r#"{"results":[{"test_id":"B101"}],"generated_at":"x"}"#), Some(Format::Bandit));
+        assert_eq!(detected(r#"{"Issues":[],"Stats":{}}"#), Some(Format::Gosec));
+        assert_eq!(detected(r#"[{"filePath":"a.js","messages":[]}]"#), Some(Format::Eslint));
+    }
+
+    #[test]
+    fn test_engines() {
+        assert_eq!(Format::Gitlab(String::from("dependency_scanning")).engines(), vec![Engine::Sca, Engine::License]);
+        assert_eq!(Format::Gitlab(String::from("coverage_fuzzing")).engines(), vec![]);
+        assert_eq!(Format::Sarif(String::from("trufflehog")).engines(), vec![Engine::Secret]);
+        assert_eq!(Format::Sarif(String::from("codeql")).engines(), vec![Engine::Sast]);
+        assert_eq!(Format::Trivy.engines(), vec![Engine::Sca, Engine::Secret, Engine::Iac, Engine::License]);
+        assert_eq!(Format::Command(String::from("x"), Engine::Dast).engines(), vec![Engine::Dast]);
+    }
+
+    #[test]
+    fn test_select_order() {
+        let input = |path: &str, format: Format| Input {
+            path: path.to_string(),
+            format: format,
+            content: String::new(),
+        };
+        let inputs = vec![
+            input("trivy.json", Format::Trivy),
+            input("sbom.json", Format::CycloneDx),
+            input("gl-dependency-scanning-report.json", Format::Gitlab(String::from("dependency_scanning"))),
+            input("gl-license-scanning-report.json", Format::GitlabLicense),
+            input("gl-sast-report.json", Format::Gitlab(String::from("sast"))),
+        ];
+        let paths = |engine: Engine| -> Vec<String> { select(&inputs, engine).iter().map(|i| i.path.to_string()).collect() };
+        assert_eq!(paths(Engine::Sca), vec!["gl-dependency-scanning-report.json", "sbom.json", "trivy.json"]);
+        assert_eq!(
+            paths(Engine::License),
+            vec!["gl-license-scanning-report.json", "sbom.json", "gl-dependency-scanning-report.json", "trivy.json"]
+        );
+        assert_eq!(paths(Engine::Sast), vec!["gl-sast-report.json"]);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        let matches = |pattern: &str, path: &str| {
+            glob_match(&pattern.split('/').collect::<Vec<&str>>(), &path.split('/').collect::<Vec<&str>>())
+        };
+        assert!(matches("reports/**/*.json", "reports/a.json"));
+        assert!(matches("reports/**/*.json", "reports/x/y/b.json"));
+        assert!(!matches("reports/**/*.json", "reports/x/b.sarif"));
+        assert!(!matches("reports/**/*.json", "other/a.json"));
+        assert!(matches("gl-?ast-report.json", "gl-sast-report.json"));
+        assert!(!matches("*.json", "x/a.json"));
+    }
+
+    #[test]
+    fn test_expand_dedup() {
+        let dir = std::env::temp_dir().join(format!("detect-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        for file in ["a.json", "b.txt", "sub/c.json"] {
+            fs::write(dir.join(file), "{}").unwrap();
+        }
+        let base = dir.to_string_lossy().to_string();
+        let files = expand(&[base.to_string(), format!("{base}/**/*.json"), format!("{base}/a.json")]);
+        assert_eq!(files, vec![format!("{base}/a.json"), format!("{base}/sub/c.json")]);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
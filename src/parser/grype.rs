@@ -0,0 +1,47 @@
+//! grype json report (`grype -o json`), shared by the engines
+
+use serde_json::Value;
+
+use crate::utils::cvss;
+
+pub fn is_grype(report: &Value) -> bool {
+    report["matches"].is_array()
+        && (report["descriptor"]["name"] == "grype" || report["source"].is_object())
+}
+
+/// path of the first location of the matched artifact, relative to the scanned directory
+pub fn location(artifact: &Value) -> String {
+    artifact["locations"][0]["path"]
+        .as_str()
+        .unwrap_or("")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// license names of an artifact, plain strings in older grype versions
+pub fn licenses(artifact: &Value) -> Vec<String> {
+    artifact["licenses"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|l| l.as_str().or(l["spdxExpression"].as_str().filter(|e| !e.is_empty())).or(l["value"].as_str()))
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// highest `(score, vector)` of the match and its related vulnerabilities
+pub fn cvss(matched: &Value) -> Option<(f64, String)> {
+    let mut best: Option<(f64, String)> = None;
+    let related = matched["relatedVulnerabilities"].as_array().cloned().unwrap_or_default();
+    for vuln in [&matched["vulnerability"]].into_iter().chain(related.iter()) {
+        for item in vuln["cvss"].as_array().into_iter().flatten() {
+            if let Some(parsed) = cvss::parse(item["vector"].as_str().unwrap_or("")) {
+                if best.as_ref().map_or(true, |(score, _)| parsed.score > *score) {
+                    best = Some((parsed.score, parsed.vector));
+                }
+            }
+        }
+    }
+
+    best
+}
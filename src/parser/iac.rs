@@ -0,0 +1,485 @@
+// iac (kics)
+
+use std::fs;
+
+use crate::{
+    conf::setting::*,
+    utils::{
+        codequality, export, gate,
+        gitlab::{self, Issue},
+        i18n, render, template,
+    },
+};
+
+use super::base::{fingerprint_or, severity_name, BaseParser, BaseReport, Finding};
+use super::trivy;
+use serde_json::{json, Value};
+
+const ISSUE_TEMPLATE: &str = "<br>{{{intro}}}<br>\n\
+{{#each groups}}\n\
+----------------------------------{{location}}------------------------------------------\n\
+{{{header}}}{{#each findings}}\n|{{id}}{{{record}}}{{/each}}\n\n{{/each}}";
+
+/// gitlab sast report written by the kics analyzer, or a raw kics `results.json`
+pub fn is_kics(report: &Value) -> bool {
+    let scan = &report["scan"];
+    let by_scanner = [&scan["analyzer"]["id"], &scan["scanner"]["id"]]
+        .iter()
+        .any(|id| id.as_str().map_or(false, |id| id.eq_ignore_ascii_case("kics")));
+    let by_identifier = report["vulnerabilities"].as_array().map_or(false, |vuls| {
+        !vuls.is_empty()
+            && vuls.iter().all(|vul| {
+                vul["identifiers"]
+                    .as_array()
+                    .map_or(false, |ids| ids.iter().any(|id| id["type"] == "kics_id"))
+            })
+    });
+
+    by_scanner || by_identifier || (report["queries"].is_array() && report["kics_version"].is_string())
+}
+
+// terraform resource address, kubernetes kind/name or dockerfile instruction around `line`
+fn resource_from_source(file: &str, content: &str, line: u64) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let idx = (line as usize).saturating_sub(1).min(lines.len().saturating_sub(1));
+    if lines.is_empty() {
+        return String::new();
+    }
+    let name = file.rsplit('/').next().unwrap_or(file).to_lowercase();
+
+    if name.ends_with(".tf") {
+        for l in lines[..=idx].iter().rev() {
+            let words: Vec<&str> = l.split_whitespace().map(|w| w.trim_matches('"')).collect();
+            match words.as_slice() {
+                ["resource", kind, name, ..] => return format!("{kind}.{name}"),
+                ["data", kind, name, ..] => return format!("data.{kind}.{name}"),
+                ["module", name, ..] => return format!("module.{name}"),
+                _ => continue,
+            }
+        }
+        return String::new();
+    }
+
+    if name.contains("dockerfile") {
+        // continuation lines belong to the instruction above
+        let mut start = idx;
+        while start > 0 && lines[start - 1].trim_end().ends_with('\\') {
+            start -= 1;
+        }
+        let instruction = lines[start].split_whitespace().next().unwrap_or("").to_uppercase();
+        let stage = lines[..=start]
+            .iter()
+            .rev()
+            .find(|l| l.trim_start().to_uppercase().starts_with("FROM "))
+            .map(|l| l.split_whitespace().nth(1).unwrap_or("").to_string())
+            .unwrap_or_default();
+        return match (instruction.as_str(), stage.is_empty()) {
+            ("", _) => String::new(),
+            ("FROM", _) | (_, true) => instruction,
+            _ => format!("{instruction} ({stage})"),
+        };
+    }
+
+    if name.ends_with(".yaml") || name.ends_with(".yml") {
+        // yaml document containing the line
+        let start = lines[..=idx].iter().rposition(|l| l.starts_with("---")).map_or(0, |i| i + 1);
+        let end = lines[idx..].iter().position(|l| l.starts_with("---")).map_or(lines.len(), |i| idx + i);
+        let doc = &lines[start..end];
+        let kind = doc
+            .iter()
+            .find_map(|l| l.strip_prefix("kind:"))
+            .map(|k| k.trim().trim_matches(['"', '\'']).to_string())
+            .unwrap_or_default();
+        let metadata = doc.iter().position(|l| l.starts_with("metadata:"));
+        let name = metadata
+            .and_then(|m| {
+                doc[m + 1..]
+                    .iter()
+                    .take_while(|l| l.is_empty() || l.starts_with(' '))
+                    .find_map(|l| l.trim_start().strip_prefix("name:").filter(|_| l.len() - l.trim_start().len() <= 2))
+            })
+            .map(|n| n.trim().trim_matches(['"', '\'']).to_string())
+            .unwrap_or_default();
+        return match (kind.is_empty(), name.is_empty()) {
+            (false, false) => format!("{kind}/{name}"),
+            (false, true) => kind,
+            _ => String::new(),
+        };
+    }
+
+    String::new()
+}
+
+/// an iac misconfiguration
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IacVul {
+    message: String,
+    description: String,
+    severity: String,
+    // kics query id
+    cve: String,
+    file: String,
+    line: u64,
+    href: String,
+    resource: String,
+    remediation: String,
+    // cis benchmark ids, e.g. "CIS 5.2.6"
+    cis: Vec<String>,
+    identifiers: Vec<String>,
+    // stable id given by the scanner, computed from the fields when empty
+    fingerprint: String,
+}
+
+impl IacVul {
+    /// `file resource`, the issue group
+    pub fn group(self: &Self) -> String {
+        if self.resource.is_empty() {
+            self.file.to_string()
+        } else {
+            format!("{} {}", self.file, self.resource)
+        }
+    }
+
+    fn to_issue_record(self: &Self) -> String {
+        let mut values: Vec<String> = Vec::new();
+        values.push(render::cell(self.message.as_str(), 200));
+        values.push(render::cell(self.severity.as_str(), 20));
+        values.push(render::cell(self.cis.join(", ").as_str(), 100));
+        values.push(render::link(format!("{}:{}", self.file, self.line).as_str(), self.href.as_str()));
+        values.push(render::cell(self.remediation.as_str(), 300));
+        values.push(render::cell(self.description.as_str(), render::MAX_CELL_LEN));
+
+        render::row(&values)
+    }
+
+    fn resolve_resource(self: &mut Self, source_dir: &str) {
+        if !self.resource.is_empty() {
+            return;
+        }
+        if let Ok(content) = fs::read_to_string(format!("{source_dir}/{}", self.file)) {
+            self.resource = resource_from_source(self.file.as_str(), content.as_str(), self.line);
+        }
+    }
+}
+
+// `CIS 5.2.6` style ids from the cis fields of a kics query or a gitlab identifier
+fn cis_id(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    let id = value.trim_start_matches("CIS").trim_start_matches("cis").trim_start_matches([' ', '_', '-', ':']);
+    Some(format!("CIS {id}"))
+}
+
+impl From<&Value> for IacVul {
+    // a vulnerability of the gitlab report
+    fn from(value: &Value) -> Self {
+        let mut identifiers: Vec<String> = vec![];
+        let mut cis: Vec<String> = vec![];
+        let mut query = String::new();
+        for id in value["identifiers"].as_array().unwrap_or(&vec![]) {
+            let kind = id["type"].as_str().unwrap_or("").to_lowercase();
+            let v = id["value"].as_str().or(id["name"].as_str()).unwrap_or("");
+            if kind == "kics_id" && query.is_empty() {
+                query = v.to_string();
+            }
+            if kind.starts_with("cis") {
+                cis.extend(cis_id(v));
+            }
+            if !v.is_empty() && !identifiers.contains(&v.to_string()) {
+                identifiers.push(v.to_string());
+            }
+        }
+        let file = value["location"]["file"].as_str().unwrap_or("").to_string();
+        let line = value["location"]["start_line"].as_u64().unwrap_or(0);
+
+        IacVul {
+            message: value["message"].as_str().or(value["name"].as_str()).unwrap_or("").to_string(),
+            description: value["description"].as_str().unwrap_or("").to_string(),
+            severity: value["severity"].as_str().unwrap_or("Unknown").to_string(),
+            cve: if query.is_empty() { value["cve"].as_str().unwrap_or("").to_string() } else { query },
+            href: gitlab::blob_url(file.as_str(), line),
+            file: file,
+            line: line,
+            resource: String::new(),
+            remediation: value["solution"].as_str().unwrap_or("").to_string(),
+            cis: cis,
+            identifiers: identifiers,
+            fingerprint: String::new(),
+        }
+    }
+}
+
+impl From<&Finding> for IacVul {
+    fn from(finding: &Finding) -> Self {
+        IacVul {
+            message: finding.name.to_string(),
+            description: finding.description.to_string(),
+            severity: severity_name(finding.severity.as_str()),
+            cve: finding.identifiers.first().cloned().unwrap_or_default(),
+            href: gitlab::blob_url(finding.file.as_str(), finding.line),
+            file: finding.file.to_string(),
+            line: finding.line,
+            cis: finding.identifiers.iter().filter_map(|id| id.strip_prefix("CIS").and_then(cis_id)).collect(),
+            identifiers: finding.identifiers.clone(),
+            fingerprint: finding.fingerprint.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+// checkout the resources are read from
+fn source_dir() -> &'static str {
+    if CI_PROJECT_DIR.is_empty() {
+        TARGET.as_str()
+    } else {
+        CI_PROJECT_DIR.as_str()
+    }
+}
+
+#[derive(Debug)]
+pub struct IacReport {
+    pub engine: String,
+    pub(crate) vuls: Vec<IacVul>,
+}
+
+impl IacReport {
+    pub fn new() -> Self {
+        IacReport {
+            engine: String::from("IaC"),
+            vuls: vec![],
+        }
+    }
+
+    // raw kics results.json, one vul per query and file
+    fn parse_kics(self: &mut Self, report: &Value) {
+        for query in report["queries"].as_array().unwrap_or(&vec![]) {
+            let id = query["query_id"].as_str().unwrap_or("").to_string();
+            let cis: Vec<String> = cis_id(query["cis_description_id"].as_str().unwrap_or("")).into_iter().collect();
+            let mut identifiers: Vec<String> = vec![id.to_string()];
+            identifiers.extend(query["cwe"].as_str().filter(|c| !c.is_empty()).map(|c| format!("CWE-{c}")));
+            identifiers.extend(cis.iter().cloned());
+            for file in query["files"].as_array().unwrap_or(&vec![]) {
+                let fpath = file["file_name"].as_str().unwrap_or("").trim_start_matches("./").to_string();
+                let line = file["line"].as_u64().unwrap_or(0);
+                let kind = file["resource_type"].as_str().unwrap_or("");
+                let name = file["resource_name"].as_str().unwrap_or("");
+                let resource = match (kind.is_empty(), name.is_empty()) {
+                    (false, false) if fpath.ends_with(".tf") => format!("{kind}.{name}"),
+                    (false, false) => format!("{kind}/{name}"),
+                    _ => String::new(),
+                };
+                let remediation = match (file["remediation"].as_str(), file["expected_value"].as_str()) {
+                    (Some(r), _) if !r.is_empty() => r.to_string(),
+                    (_, Some(expected)) => expected.to_string(),
+                    _ => String::new(),
+                };
+                let actual = file["actual_value"].as_str().unwrap_or("");
+                let mut description = query["description"].as_str().unwrap_or("").to_string();
+                if !actual.is_empty() {
+                    description = format!("{description}\n{actual}");
+                }
+                self.vuls.push(IacVul {
+                    message: query["query_name"].as_str().unwrap_or("").to_string(),
+                    description: description,
+                    severity: severity_name(query["severity"].as_str().unwrap_or("")),
+                    cve: id.to_string(),
+                    href: gitlab::blob_url(fpath.as_str(), line),
+                    file: fpath,
+                    line: line,
+                    resource: resource,
+                    remediation: remediation,
+                    cis: cis.clone(),
+                    identifiers: identifiers.clone(),
+                    fingerprint: String::new(),
+                });
+            }
+        }
+    }
+
+    // failed checks of trivy misconfiguration scanning
+    fn parse_trivy(self: &mut Self, report: &Value) {
+        for (target, misconf) in trivy::results(report, "Misconfigurations") {
+            if misconf["Status"].as_str().map_or(false, |status| status != "FAIL") {
+                continue;
+            }
+            let cause = &misconf["CauseMetadata"];
+            let line = cause["StartLine"].as_u64().unwrap_or(0);
+            let mut identifiers: Vec<String> = vec![];
+            for id in [&misconf["ID"], &misconf["AVDID"]] {
+                if let Some(id) = id.as_str().filter(|id| !id.is_empty() && !identifiers.contains(&id.to_string())) {
+                    identifiers.push(id.to_string());
+                }
+            }
+            let mut description = misconf["Description"].as_str().unwrap_or("").to_string();
+            if let Some(message) = misconf["Message"].as_str().filter(|m| !m.is_empty()) {
+                description = format!("{description}\n{message}");
+            }
+            self.vuls.push(IacVul {
+                message: misconf["Title"].as_str().unwrap_or("").to_string(),
+                description: description,
+                severity: severity_name(misconf["Severity"].as_str().unwrap_or("")),
+                cve: identifiers.first().cloned().unwrap_or_default(),
+                href: gitlab::blob_url(target, line),
+                file: target.to_string(),
+                line: line,
+                resource: cause["Resource"].as_str().unwrap_or("").to_string(),
+                remediation: misconf["Resolution"].as_str().unwrap_or("").to_string(),
+                cis: vec![],
+                identifiers: identifiers,
+                fingerprint: String::new(),
+            });
+        }
+    }
+
+    /// file resource -> vuls, in order of first appearance
+    pub fn groups(self: &Self) -> Vec<(String, Vec<IacVul>)> {
+        let mut groups: Vec<(String, Vec<IacVul>)> = vec![];
+        for vul in self.vuls.iter() {
+            let group = vul.group();
+            match groups.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, vuls)) => vuls.push(vul.clone()),
+                None => groups.push((group, vec![vul.clone()])),
+            }
+        }
+
+        groups
+    }
+
+    pub fn issue_context(self: &Self) -> Value {
+        const COLS: [&str; 7] = ["id", "title", "severity", "cis", "location", "solution", "description"];
+        let to_values = |vuls: &Vec<IacVul>| -> Vec<Value> {
+            vuls.iter()
+                .enumerate()
+                .map(|(idx, vul)| template::finding_value(idx + 1, &self.to_finding(vul), vul.to_issue_record()))
+                .collect()
+        };
+        let groups: Vec<Value> = self
+            .groups()
+            .iter()
+            .map(|(group, vuls)| json!({ "location": group, "findings": to_values(vuls) }))
+            .collect();
+
+        template::context(
+            self.engine.as_str(),
+            to_values(&self.vuls),
+            json!({
+                "header": i18n::table_header(&COLS),
+                "intro": i18n::t("report.intro"),
+                "grouped": true,
+                "groups": groups,
+            }),
+        )
+    }
+
+    fn to_finding(self: &Self, vul: &IacVul) -> Finding {
+        let mut identifiers = vul.identifiers.clone();
+        for cis in vul.cis.iter() {
+            if !identifiers.contains(cis) {
+                identifiers.push(cis.to_string());
+            }
+        }
+        Finding {
+            engine: self.engine.to_string(),
+            name: vul.message.to_string(),
+            description: vul.description.to_string(),
+            severity: vul.severity.to_string(),
+            identifiers: identifiers,
+            file: vul.file.to_string(),
+            line: vul.line,
+            dependency: vul.resource.to_string(),
+            // keyed on the resource, lines move when the file is edited
+            fingerprint: fingerprint_or(vul.fingerprint.as_str(), &[
+                self.engine.as_str(),
+                vul.cve.as_str(),
+                vul.file.as_str(),
+                vul.resource.as_str(),
+            ]),
+            ..Default::default()
+        }
+    }
+}
+
+impl BaseParser<IacVul> for IacReport {
+    /// accepts a gitlab sast report of the kics analyzer, a raw kics results.json or a trivy report
+    fn parse(self: &mut Self, content: &str) -> &Vec<IacVul> {
+        let report: Value = serde_json::from_str(content).expect("json format error");
+        let start = self.vuls.len();
+        if trivy::is_trivy(&report) {
+            self.parse_trivy(&report);
+        } else if report["queries"].is_array() {
+            self.parse_kics(&report);
+        } else {
+            for vul in report["vulnerabilities"].as_array().unwrap_or(&vec![]) {
+                self.vuls.push(IacVul::from(vul));
+            }
+        }
+        // the gitlab report has no resource, read it from the scanned sources for this input only
+        for vul in self.vuls[start..].iter_mut() {
+            vul.resolve_resource(source_dir());
+        }
+        println!("vul(iac) count: {}", self.vuls.len());
+
+        &self.vuls
+    }
+
+    fn merge(self: &mut Self, findings: &[Finding]) -> &Vec<IacVul> {
+        for finding in findings.iter() {
+            let mut vul = IacVul::from(finding);
+            vul.resolve_resource(source_dir());
+            self.vuls.push(vul);
+        }
+
+        &self.vuls
+    }
+
+    fn export(self: &Self, fpath: &str) -> bool {
+        export::write(&self.findings(), fpath)
+    }
+
+    fn to_issue(self: &Self) -> Issue {
+        let ctx = self.issue_context();
+        let mut issue: Issue = Issue::new();
+        issue.engine = self.engine.to_string();
+        issue.project_id = CI_PROJECT_ID.to_string();
+        issue.title = template::issue_title(&ctx);
+        issue.description = template::issue_description(&ctx, ISSUE_TEMPLATE);
+        issue
+    }
+
+    fn findings(self: &Self) -> Vec<Finding> {
+        self.vuls.iter().map(|vul| self.to_finding(vul)).collect()
+    }
+
+    async fn filter(self: &mut Self) -> Vec<IacVul> {
+        self.vuls.clone()
+    }
+}
+
+impl BaseReport<IacVul> for IacReport {
+    async fn report(self: &mut Self) {
+        codequality::write_report(&self.findings());
+        self.filter().await;
+        for fpath in export::export_paths(self.engine.as_str()) {
+            self.export(fpath.as_str());
+        }
+        gate::evaluate(&self.findings());
+
+        if self.vuls.len() < 1 {
+            println!("no iac vuls, skip create new issue...");
+            return;
+        }
+        if CI_MERGE_REQUEST_IID.as_str() == "" {
+            log::error!("no merge request, skip create issue...");
+            return;
+        }
+
+        let mut issue = self.to_issue();
+        let notes = template::split_issue(&mut issue, &self.issue_context());
+        let _ = gitlab::new_issue_with_notes(&issue, &notes).await;
+        if let Some(note) = template::mr_note(&self.issue_context()) {
+            let _ = gitlab::new_mr_comment(note).await;
+        }
+    }
+}
@@ -0,0 +1,384 @@
+// license
+
+use std::collections::HashMap;
+
+use crate::{
+    conf::setting::*,
+    utils::{
+        codequality,
+        cyclonedx::Bom,
+        export, gate,
+        gitlab::{self, Issue},
+        i18n, render,
+        spdx::{self, Decision, Policy},
+        template,
+    },
+};
+
+use super::base::{fingerprint, BaseParser, BaseReport, Finding};
+use super::{grype, trivy};
+use serde_json::{json, Value};
+
+const ISSUE_TEMPLATE: &str = "{{{header}}}{{#each findings}}\n|{{id}}{{{record}}}{{/each}}";
+
+/// a package whose license is not allowed by the policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct LicenseVul {
+    name: String,
+    version: String,
+    package_manager: String,
+    location: String,
+    // spdx expressions, all of them apply
+    licenses: Vec<String>,
+    decision: Decision,
+}
+
+impl LicenseVul {
+    fn expression(self: &Self) -> String {
+        if self.licenses.len() == 1 {
+            return self.licenses[0].to_string();
+        }
+        self.licenses
+            .iter()
+            .map(|l| if l.contains(' ') { format!("({l})") } else { l.to_string() })
+            .collect::<Vec<String>>()
+            .join(" AND ")
+    }
+
+    // deny fails a `GATE_SEVERITY` of critical and below, review is reported as medium
+    fn severity(self: &Self) -> &'static str {
+        match self.decision {
+            Decision::Deny => "Critical",
+            Decision::Review => "Medium",
+            Decision::Allow => "Info",
+        }
+    }
+
+    fn to_issue_record(self: &Self) -> String {
+        let expression = self.expression();
+        let mut values: Vec<String> = Vec::new();
+        values.push(render::code(format!("{}:{}", self.name, self.version).as_str()));
+        if expression.is_empty() {
+            values.push(i18n::t("license.unknown"));
+        } else {
+            values.push(render::code(expression.as_str()));
+        }
+        values.push(i18n::t(format!("license.{}", self.decision.as_str()).as_str()));
+        values.push(render::cell(self.location.as_str(), 200));
+
+        render::row(&values)
+    }
+}
+
+#[derive(Debug)]
+pub struct LicenseReport {
+    pub engine: String,
+    pub(crate) vuls: Vec<LicenseVul>,
+    // every package seen, (name, version) -> (package manager, location, licenses)
+    packages: HashMap<(String, String), (String, String, Vec<String>)>,
+    order: Vec<(String, String)>,
+    policy: Policy,
+}
+
+impl LicenseReport {
+    pub fn new() -> Self {
+        LicenseReport {
+            engine: String::from("License"),
+            vuls: vec![],
+            packages: HashMap::new(),
+            order: vec![],
+            policy: Policy::new(
+                LICENSE_ALLOW.as_str(),
+                LICENSE_DENY.as_str(),
+                LICENSE_REVIEW.as_str(),
+                LICENSE_DEFAULT.as_str(),
+                LICENSE_UNKNOWN.as_str(),
+            ),
+        }
+    }
+
+    fn add(self: &mut Self, name: &str, version: &str, package_manager: &str, location: &str, licenses: Vec<String>) {
+        if name.is_empty() {
+            return;
+        }
+        let key = (name.to_string(), version.to_string());
+        if !self.packages.contains_key(&key) {
+            self.order.push(key.clone());
+        }
+        let entry = self.packages.entry(key).or_default();
+        if entry.0.is_empty() {
+            entry.0 = package_manager.to_string();
+        }
+        if entry.1.is_empty() {
+            entry.1 = location.to_string();
+        }
+        for license in licenses {
+            if !license.trim().is_empty() && !entry.2.contains(&license) {
+                entry.2.push(license);
+            }
+        }
+    }
+
+    // gitlab license scanning report, 2.x and 1.x
+    fn parse_license_scanning(self: &mut Self, report: &Value) {
+        for dep in report["dependencies"].as_array().unwrap_or(&vec![]) {
+            if dep["dependency"].is_object() {
+                self.add(
+                    dep["dependency"]["name"].as_str().unwrap_or(""),
+                    dep["dependency"]["version"].as_str().unwrap_or(""),
+                    dep["dependency"]["package_manager"].as_str().unwrap_or(""),
+                    dep["dependency"]["path"].as_str().unwrap_or(""),
+                    dep["license"]["name"].as_str().map(|l| vec![l.to_string()]).unwrap_or_default(),
+                );
+                continue;
+            }
+            let licenses = dep["licenses"]
+                .as_array()
+                .unwrap_or(&vec![])
+                .iter()
+                .filter_map(|l| l.as_str().map(|l| l.to_string()))
+                .collect();
+            self.add(
+                dep["name"].as_str().unwrap_or(""),
+                dep["version"].as_str().unwrap_or(""),
+                dep["package_manager"].as_str().unwrap_or(""),
+                dep["path"].as_str().unwrap_or(""),
+                licenses,
+            );
+        }
+    }
+
+    // cyclonedx sbom, `licenses` holds license ids, names or an expression
+    fn parse_cyclonedx(self: &mut Self, doc: &Value) {
+        for component in Bom::parse(doc).components {
+            self.add(
+                component.package_name().as_str(),
+                component.version.as_str(),
+                component.scheme().purl_type(),
+                component.location.as_str(),
+                component.licenses,
+            );
+        }
+    }
+
+    // trivy license scanning (`Licenses`) and the packages listed with `--list-all-pkgs`
+    fn parse_trivy(self: &mut Self, report: &Value) {
+        for (target, license) in trivy::results(report, "Licenses") {
+            let name = license["PkgName"].as_str().filter(|n| !n.is_empty());
+            let path = license["FilePath"].as_str().filter(|p| !p.is_empty()).unwrap_or(target);
+            self.add(
+                name.unwrap_or(path),
+                "",
+                "",
+                path,
+                license["Name"].as_str().map(|l| vec![l.to_string()]).unwrap_or_default(),
+            );
+        }
+        for result in report["Results"].as_array().unwrap_or(&vec![]) {
+            let target = result["Target"].as_str().unwrap_or("");
+            let kind = result["Type"].as_str().unwrap_or("");
+            for package in result["Packages"].as_array().unwrap_or(&vec![]) {
+                let licenses = package["Licenses"]
+                    .as_array()
+                    .unwrap_or(&vec![])
+                    .iter()
+                    .filter_map(|l| l.as_str().map(|l| l.to_string()))
+                    .collect();
+                self.add(
+                    package["Name"].as_str().unwrap_or(""),
+                    package["Version"].as_str().unwrap_or(""),
+                    kind,
+                    target,
+                    licenses,
+                );
+            }
+        }
+    }
+
+    // grype only lists the vulnerable packages
+    fn parse_grype(self: &mut Self, report: &Value) {
+        for matched in report["matches"].as_array().unwrap_or(&vec![]) {
+            let artifact = &matched["artifact"];
+            self.add(
+                artifact["name"].as_str().unwrap_or(""),
+                artifact["version"].as_str().unwrap_or(""),
+                artifact["type"].as_str().unwrap_or(""),
+                grype::location(artifact).as_str(),
+                grype::licenses(artifact),
+            );
+        }
+    }
+
+    // dependency scanning report, packages only
+    fn parse_dependency_files(self: &mut Self, report: &Value) {
+        for file in report["dependency_files"].as_array().unwrap_or(&vec![]) {
+            let path = file["path"].as_str().unwrap_or("");
+            let package_manager = file["package_manager"].as_str().unwrap_or("");
+            for dep in file["dependencies"].as_array().unwrap_or(&vec![]) {
+                self.add(
+                    dep["package"]["name"].as_str().unwrap_or(""),
+                    dep["version"].as_str().unwrap_or(""),
+                    package_manager,
+                    path,
+                    vec![],
+                );
+            }
+        }
+    }
+
+    /// evaluate every package against the policy, keeps the ones not allowed
+    pub fn evaluate(self: &mut Self) -> &Vec<LicenseVul> {
+        self.vuls.clear();
+        for key in self.order.iter() {
+            let (package_manager, location, licenses) = self.packages.get(key).unwrap();
+            let decision = if licenses.is_empty() {
+                self.policy.unknown
+            } else {
+                licenses
+                    .iter()
+                    .map(|l| self.policy.evaluate(l))
+                    .max()
+                    .unwrap_or_default()
+            };
+            if decision == Decision::Allow {
+                continue;
+            }
+            self.vuls.push(LicenseVul {
+                name: key.0.to_string(),
+                version: key.1.to_string(),
+                package_manager: package_manager.to_string(),
+                location: location.to_string(),
+                licenses: licenses.clone(),
+                decision: decision,
+            });
+        }
+        self.vuls.sort_by(|a, b| b.decision.cmp(&a.decision));
+        println!("license violations: {}/{}", self.vuls.len(), self.order.len());
+
+        &self.vuls
+    }
+
+    pub fn issue_context(self: &Self) -> Value {
+        const COLS: [&str; 5] = ["id", "dependency", "license", "decision", "location"];
+        let findings: Vec<Value> = self
+            .findings()
+            .iter()
+            .zip(self.vuls.iter())
+            .enumerate()
+            .map(|(idx, (finding, vul))| {
+                template::finding_value(idx + 1, finding, vul.to_issue_record())
+            })
+            .collect();
+
+        template::context(
+            self.engine.as_str(),
+            findings,
+            json!({ "header": i18n::table_header(&COLS) }),
+        )
+    }
+}
+
+impl BaseParser<LicenseVul> for LicenseReport {
+    /// accepts a license scanning report, a cyclonedx sbom, a dependency scanning report or a
+    /// trivy / grype report, call it once per file, packages are merged by name and version
+    fn parse(self: &mut Self, content: &str) -> &Vec<LicenseVul> {
+        let report: Value = serde_json::from_str(content).expect("json format error");
+        if Bom::is_cyclonedx(&report) {
+            self.parse_cyclonedx(&report);
+        } else if trivy::is_trivy(&report) {
+            self.parse_trivy(&report);
+        } else if grype::is_grype(&report) {
+            self.parse_grype(&report);
+        } else if report["dependency_files"].is_array() {
+            self.parse_dependency_files(&report);
+        } else if report["dependencies"].is_array() {
+            self.parse_license_scanning(&report);
+        } else {
+            log::warn!("not a license source, skip");
+        }
+
+        self.evaluate()
+    }
+
+    fn merge(self: &mut Self, findings: &[Finding]) -> &Vec<LicenseVul> {
+        // `dependency` is the package, `identifiers` its spdx expressions
+        for finding in findings.iter() {
+            let (name, version) = finding.package();
+            self.add(name.as_str(), version.as_str(), "", finding.file.as_str(), finding.identifiers.clone());
+        }
+
+        self.evaluate()
+    }
+
+    fn export(self: &Self, fpath: &str) -> bool {
+        export::write(&self.findings(), fpath)
+    }
+
+    fn to_issue(self: &Self) -> Issue {
+        let ctx = self.issue_context();
+        let mut issue: Issue = Issue::new();
+        issue.engine = self.engine.to_string();
+        issue.project_id = CI_PROJECT_ID.to_string();
+        issue.title = template::issue_title(&ctx);
+        issue.description = template::issue_description(&ctx, ISSUE_TEMPLATE);
+        issue
+    }
+
+    fn findings(self: &Self) -> Vec<Finding> {
+        self.vuls
+            .iter()
+            .map(|vul| {
+                let expression = vul.expression();
+                Finding {
+                    engine: self.engine.to_string(),
+                    name: format!("{} {}", i18n::t(format!("license.{}", vul.decision.as_str()).as_str()), expression),
+                    description: format!("{}:{} {}", vul.name, vul.version, expression),
+                    severity: vul.severity().to_string(),
+                    identifiers: spdx::license_ids(expression.as_str()),
+                    file: vul.location.to_string(),
+                    line: 0,
+                    dependency: format!("{}:{}", vul.name, vul.version),
+                    fingerprint: fingerprint(&[
+                        self.engine.as_str(),
+                        vul.location.as_str(),
+                        vul.name.as_str(),
+                        vul.version.as_str(),
+                        expression.as_str(),
+                    ]),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    async fn filter(self: &mut Self) -> Vec<LicenseVul> {
+        self.vuls.clone()
+    }
+}
+
+impl BaseReport<LicenseVul> for LicenseReport {
+    async fn report(self: &mut Self) {
+        codequality::write_report(&self.findings());
+        self.filter().await;
+        for fpath in export::export_paths(self.engine.as_str()) {
+            self.export(fpath.as_str());
+        }
+        gate::evaluate(&self.findings());
+
+        if self.vuls.len() < 1 {
+            println!("no license violation, skip create new issue...");
+            return;
+        }
+        if CI_MERGE_REQUEST_IID.as_str() == "" {
+            log::error!("no merge request, skip create issue...");
+            return;
+        }
+
+        let mut issue = self.to_issue();
+        let notes = template::split_issue(&mut issue, &self.issue_context());
+        let _ = gitlab::new_issue_with_notes(&issue, &notes).await;
+        if let Some(note) = template::mr_note(&self.issue_context()) {
+            let _ = gitlab::new_mr_comment(note).await;
+        }
+    }
+}
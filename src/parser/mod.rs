@@ -3,3 +3,16 @@ pub mod base;
 pub mod sast;
 pub mod sca;
 pub mod secret;
+pub mod license;
+pub mod container;
+pub mod dast;
+pub mod iac;
+pub mod trivy;
+pub mod grype;
+pub mod sarif;
+pub mod depcheck;
+pub mod npm_audit;
+pub mod cargo_audit;
+pub mod detect;
+pub mod registry;
+pub mod plugin;
@@ -0,0 +1,145 @@
+//! `npm audit --json` report, v1 (npm 6, `advisories`) and v2 (npm 7+, `auditReportVersion`)
+
+use std::{fs, path::Path};
+
+use serde_json::Value;
+
+pub fn is_npm_audit(report: &Value) -> bool {
+    report["auditReportVersion"].as_u64() == Some(2) || (report["advisories"].is_object() && report["actions"].is_array())
+}
+
+pub fn is_v2(report: &Value) -> bool {
+    report["auditReportVersion"].as_u64() == Some(2)
+}
+
+/// advisory id of a v2 `via` entry, the ghsa id closing its url
+pub fn advisory_id(via: &Value) -> String {
+    via["url"]
+        .as_str()
+        .and_then(|url| url.rsplit('/').next())
+        .filter(|id| id.starts_with("GHSA-"))
+        .map(|id| id.to_string())
+        .or_else(|| npm_id(&via["source"]))
+        .or_else(|| via["title"].as_str().map(|title| title.to_string()))
+        .unwrap_or_default()
+}
+
+/// `NPM-<id>` of a npm advisory id, None when it is missing
+pub fn npm_id(id: &Value) -> Option<String> {
+    match id {
+        Value::Number(n) => Some(format!("NPM-{n}")),
+        Value::String(s) if !s.is_empty() => Some(format!("NPM-{s}")),
+        _ => None,
+    }
+}
+
+/// `package-lock.json` of `dir`, null when there is none
+pub fn lockfile(dir: &str) -> Value {
+    let path = Path::new(dir).join("package-lock.json");
+    match fs::read_to_string(&path) {
+        Ok(cont) => serde_json::from_str(cont.as_str()).unwrap_or_default(),
+        Err(_) => {
+            log::warn!("no {}, npm audit versions unknown", path.display());
+            Value::Null
+        }
+    }
+}
+
+/// installed versions of the v2 `nodes`, read from the package-lock (lockfile v2 / v3) next to the report,
+/// see [`lockfile`]
+pub fn installed_versions(entry: &Value, lock: &Value) -> Vec<String> {
+    let mut versions: Vec<String> = vec![];
+    for node in entry["nodes"].as_array().into_iter().flatten() {
+        let version = node.as_str().and_then(|n| lock["packages"][n]["version"].as_str());
+        if let Some(v) = version.filter(|v| !versions.contains(&v.to_string())) {
+            versions.push(v.to_string());
+        }
+    }
+
+    versions
+}
+
+/// cwe ids, a string in v1 and a list in v2
+pub fn cwes(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(cwe) => vec![cwe.to_string()],
+        Value::Array(items) => items.iter().filter_map(|c| c.as_str().map(|c| c.to_string())).collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{base::BaseParser, sca::SCAReport};
+    use serde_json::json;
+
+    #[test]
+    fn test_v1_advisories() {
+        let report = json!({
+            "actions": [],
+            "advisories": {
+                "1179": {
+                    "id": 1179, "title": "Prototype Pollution", "module_name": "minimist", "severity": "moderate",
+                    "cwe": "CWE-471", "patched_versions": ">=1.2.3", "url": "https://npmjs.com/advisories/1179",
+                    "findings": [{ "version": "1.2.0" }, { "version": "0.0.8" }],
+                },
+            },
+        });
+        assert!(is_npm_audit(&report) && !is_v2(&report));
+        let mut sca = SCAReport::new();
+        sca.parse_file(report.to_string().as_str(), "reports/npm-audit.json");
+        let findings = sca.findings();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].identifiers[0], "NPM-1179");
+        assert_eq!(findings[0].dependency, "minimist:1.2.0");
+        assert_eq!(findings[1].dependency, "minimist:0.0.8");
+    }
+
+    #[test]
+    fn test_v2_versions_from_the_lockfile_next_to_the_report() {
+        let dir = std::env::temp_dir().join(format!("npm-audit-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let lock = json!({ "lockfileVersion": 3, "packages": {
+            "node_modules/lodash": { "version": "4.17.20" },
+            "node_modules/a/node_modules/lodash": { "version": "4.17.15" },
+        } });
+        fs::write(dir.join("package-lock.json"), lock.to_string()).unwrap();
+        let report = json!({
+            "auditReportVersion": 2,
+            "vulnerabilities": {
+                "lodash": {
+                    "name": "lodash", "severity": "high", "range": "<4.17.21",
+                    "nodes": ["node_modules/lodash", "node_modules/a/node_modules/lodash"],
+                    "via": [
+                        { "source": 1673, "name": "lodash", "title": "Command Injection", "severity": "high",
+                          "url": "https://github.com/advisories/GHSA-35jh-r3h4-6jhm", "cwe": ["CWE-77"], "range": "<4.17.21" },
+                        { "name": "lodash", "title": "ReDoS", "severity": "moderate", "range": "<4.17.21" },
+                    ],
+                    "fixAvailable": true,
+                },
+                "a": { "name": "a", "severity": "high", "via": ["lodash"], "nodes": ["node_modules/a"] },
+            },
+        });
+        assert_eq!(installed_versions(&report["vulnerabilities"]["lodash"], &lockfile(dir.to_str().unwrap())), vec![
+            String::from("4.17.20"),
+            String::from("4.17.15")
+        ]);
+        assert_eq!(advisory_id(&report["vulnerabilities"]["lodash"]["via"][0]), "GHSA-35jh-r3h4-6jhm");
+        // no url and no source
+        assert_eq!(advisory_id(&report["vulnerabilities"]["lodash"]["via"][1]), "ReDoS");
+
+        let mut sca = SCAReport::new();
+        sca.parse_file(report.to_string().as_str(), dir.join("npm-audit.json").to_str().unwrap());
+        let deps: Vec<String> = sca.findings().iter().map(|f| f.dependency.to_string()).collect();
+        assert_eq!(deps, vec!["lodash:4.17.20", "lodash:4.17.15", "lodash:4.17.20", "lodash:4.17.15"]);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_cwes() {
+        assert_eq!(cwes(&json!("CWE-1")), vec![String::from("CWE-1")]);
+        assert_eq!(cwes(&json!(["CWE-1", "CWE-2"])).len(), 2);
+        assert!(cwes(&Value::Null).is_empty());
+    }
+}
@@ -0,0 +1,354 @@
+//! external command plugins, executables of any language printing their findings as json on stdout
+//!
+//! plugins are listed in the file named by `PLUGIN_CONFIG`, none run when it is unset. keep the file
+//! outside the scanned checkout, anyone able to push a branch controls what is inside it:
+//!
+//! ```json
+//! {
+//!   "plugins": [
+//!     {
+//!       "name": "insecure-calls",
+//!       "engine": "sast",
+//!       "command": "python3",
+//!       "args": ["plugins/insecure_calls.py", "{target}"],
+//!       "timeout": 120,
+//!       "env": { "INSECURE_CALLS_EXCLUDE": "tests" }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `engine` is one of the command line engine names and decides the report the findings are merged into.
+//! `{target}` and `{project_dir}` in `args` are replaced with `TARGET` and `CI_PROJECT_DIR`, the same
+//! values are passed as `SEC_ENGINE_TARGET` / `SEC_ENGINE_PROJECT_DIR` next to `SEC_ENGINE_ENGINE` and
+//! `SEC_ENGINE_PROTOCOL`. the job environment is inherited without the tokens of [`SECRET_ENV`], a plugin
+//! needing one names it in `env`. stdin is closed, every stderr line is written to the log, and the
+//! process is killed once it runs longer than `timeout` seconds (`PLUGIN_TIMEOUT` when unset).
+//!
+//! protocol `sec-engine-plugin/1`: the plugin exits with 0 and prints one json document on stdout
+//!
+//! ```json
+//! {
+//!   "protocol": "sec-engine-plugin/1",
+//!   "tool": { "name": "insecure-calls", "version": "1.0.0" },
+//!   "findings": [
+//!     {
+//!       "name": "eval of untrusted input",
+//!       "severity": "high",
+//!       "description": "markdown text",
+//!       "identifiers": ["CWE-95", "insecure-calls.eval"],
+//!       "file": "app/views.py",
+//!       "line": 12,
+//!       "end_line": 14,
+//!       "dependency": "name:version",
+//!       "fingerprint": "stable id of the finding"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `protocol` and `findings` are required, `tool` is optional. a finding needs a non empty `name`,
+//! `severity` is one of `critical`, `high`, `medium`, `low`, `info` or `unknown` (the default), every
+//! other field may be left out. `file` is relative to the project, absolute paths below it are shortened.
+//! `fingerprint` replaces the id the engine would compute, except in the license engine whose findings
+//! are the violations of its policy.
+//! a finding breaking these rules is skipped with a warning. a non-zero exit, a timeout or a document
+//! that is not json of this protocol fails the plugin: its findings are dropped and the engines run on.
+
+use std::{
+    fs,
+    process::Stdio,
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+    process::Command,
+    time,
+};
+
+use crate::conf::setting::{CI_PROJECT_DIR, PLUGIN_TIMEOUT};
+
+use super::{
+    base::{relative_path, severity_name, Finding},
+    detect::{Engine, Format, Input},
+};
+
+pub const PROTOCOL: &str = "sec-engine-plugin/1";
+
+/// credentials of the job, removed from the environment of the plugins
+pub const SECRET_ENV: [&str; 6] = [
+    "GITLAB_TOKEN",
+    "AI_TOKEN",
+    "CI_JOB_TOKEN",
+    "CI_REGISTRY_PASSWORD",
+    "CI_DEPLOY_PASSWORD",
+    "CI_JOB_JWT",
+];
+
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    pub name: String,
+    pub engine: Engine,
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub timeout: Duration,
+}
+
+/// stdout of a finished plugin and the lines it wrote to stderr
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub stdout: String,
+    pub stderr: Vec<String>,
+}
+
+impl Plugin {
+    /// plugin of a config entry
+    pub fn from_config(entry: &Value) -> Result<Self, String> {
+        let name = entry["name"].as_str().filter(|n| !n.is_empty()).ok_or("missing name")?;
+        let engine = entry["engine"]
+            .as_str()
+            .and_then(Engine::from_name)
+            .ok_or(format!("{name}: unknown engine {}", entry["engine"]))?;
+        let command = entry["command"]
+            .as_str()
+            .filter(|c| !c.is_empty())
+            .ok_or(format!("{name}: missing command"))?;
+        let args: Vec<String> = entry["args"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|a| a.as_str().map(|a| a.to_string()))
+            .collect();
+        let env: Vec<(String, String)> = entry["env"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(k, v)| v.as_str().map(|v| (k.to_string(), v.to_string())))
+            .collect();
+
+        Ok(Plugin {
+            name: name.to_string(),
+            engine: engine,
+            command: command.to_string(),
+            args: args,
+            env: env,
+            timeout: Duration::from_secs(entry["timeout"].as_u64().unwrap_or(*PLUGIN_TIMEOUT)),
+        })
+    }
+
+    /// run the plugin on `target`, killed after `timeout`. stderr lines are logged while it runs
+    pub async fn execute(self: &Self, target: &str) -> Result<Output, String> {
+        let project_dir = match CI_PROJECT_DIR.as_str() {
+            "" => target,
+            dir => dir,
+        };
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|a| a.replace("{target}", target).replace("{project_dir}", project_dir))
+            .collect();
+        let mut command = Command::new(self.command.as_str());
+        for name in SECRET_ENV {
+            command.env_remove(name);
+        }
+        let mut child = command
+            .args(args)
+            .envs(self.env.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+            .env("SEC_ENGINE_TARGET", target)
+            .env("SEC_ENGINE_PROJECT_DIR", project_dir)
+            .env("SEC_ENGINE_ENGINE", self.engine.name())
+            .env("SEC_ENGINE_PROTOCOL", PROTOCOL)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("can not start {}: {e}", self.command))?;
+
+        // both pipes are drained while the plugin runs, a full pipe would block it
+        let mut stdout = child.stdout.take().ok_or("no stdout")?;
+        let stderr = child.stderr.take().ok_or("no stderr")?;
+        let name = self.name.to_string();
+        let log_stderr = tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            let mut captured: Vec<String> = vec![];
+            while let Ok(Some(line)) = lines.next_line().await {
+                log::info!("[{name}] {line}");
+                captured.push(line);
+            }
+            captured
+        });
+        let read_stdout = tokio::spawn(async move {
+            let mut buf = String::new();
+            stdout.read_to_string(&mut buf).await.map(|_| buf)
+        });
+
+        let start = Instant::now();
+        let finished = time::timeout(self.timeout, async {
+            let status = child.wait().await;
+            // children of the plugin may hold the pipes open, they are read within the timeout too
+            (status, read_stdout.await, log_stderr.await)
+        })
+        .await;
+        let (status, stdout, stderr) = match finished {
+            Ok(done) => done,
+            Err(_) => {
+                let _ = child.kill().await;
+                return Err(format!("timed out after {}s, killed", self.timeout.as_secs()));
+            }
+        };
+        let status = status.map_err(|e| format!("wait failed: {e}"))?;
+        let stderr = stderr.unwrap_or_default();
+        log::info!("plugin {} exited with {status} in {:?}", self.name, start.elapsed());
+        if !status.success() {
+            let last = stderr.last().map(|l| format!(": {l}")).unwrap_or_default();
+            return Err(format!("{status}{last}"));
+        }
+        let stdout = match stdout {
+            Ok(Ok(buf)) => buf,
+            Ok(Err(e)) => return Err(format!("stdout is not utf-8: {e}")),
+            Err(e) => return Err(format!("stdout lost: {e}")),
+        };
+
+        Ok(Output {
+            stdout: stdout,
+            stderr: stderr,
+        })
+    }
+
+    /// output of a conforming run as input of the engine, `None` when the plugin failed
+    pub async fn run(self: &Self, target: &str) -> Option<Input> {
+        log::info!("plugin {}: {} {}", self.name, self.command, self.args.join(" "));
+        let output = match self.execute(target).await {
+            Ok(output) => output,
+            Err(e) => {
+                log::error!("plugin {} failed, {e}", self.name);
+                return None;
+            }
+        };
+        match parse_output(self.engine, output.stdout.as_str()) {
+            Ok(findings) => log::info!("plugin {}: {} findings", self.name, findings.len()),
+            Err(e) => {
+                log::error!("plugin {} broke the protocol, {e}", self.name);
+                return None;
+            }
+        }
+
+        Some(Input {
+            path: format!("plugin:{}", self.name),
+            format: Format::Command(self.name.to_string(), self.engine),
+            content: output.stdout,
+        })
+    }
+}
+
+/// plugins of a config document, invalid entries are skipped with a warning
+pub fn config(content: &str) -> Vec<Plugin> {
+    let doc: Value = match serde_json::from_str(content) {
+        Ok(doc) => doc,
+        Err(e) => {
+            log::error!("plugin config is not json, {e}");
+            return vec![];
+        }
+    };
+    let mut plugins: Vec<Plugin> = vec![];
+    for entry in doc["plugins"].as_array().into_iter().flatten() {
+        match Plugin::from_config(entry) {
+            Ok(plugin) if plugins.iter().any(|p| p.name == plugin.name) => {
+                log::warn!("plugin {} is configured twice, skip", plugin.name)
+            }
+            Ok(plugin) => plugins.push(plugin),
+            Err(e) => log::warn!("invalid plugin config, skip {e}"),
+        }
+    }
+
+    plugins
+}
+
+/// plugins of the config file, none when `fpath` is empty or does not exist
+pub fn load(fpath: &str) -> Vec<Plugin> {
+    if fpath.is_empty() {
+        return vec![];
+    }
+    match fs::read_to_string(fpath) {
+        Ok(content) => config(content.as_str()),
+        Err(_) => {
+            log::debug!("no plugin config {fpath}");
+            vec![]
+        }
+    }
+}
+
+/// run the plugins of `engines` one after another, the conforming outputs become inputs
+pub async fn inputs(plugins: &[Plugin], engines: &[Engine], target: &str) -> Vec<Input> {
+    let mut inputs: Vec<Input> = vec![];
+    for plugin in plugins.iter().filter(|p| engines.contains(&p.engine)) {
+        if let Some(input) = plugin.run(target).await {
+            inputs.push(input);
+        }
+    }
+
+    inputs
+}
+
+fn finding(engine: Engine, item: &Value) -> Result<Finding, String> {
+    if !item.is_object() {
+        return Err(String::from("not an object"));
+    }
+    let name = item["name"]
+        .as_str()
+        .filter(|n| !n.trim().is_empty())
+        .ok_or("missing name")?;
+    let severity = match item["severity"].as_str() {
+        None => String::from("Unknown"),
+        Some(s) => match severity_name(s) {
+            unknown if unknown == "Unknown" && !s.eq_ignore_ascii_case("unknown") => {
+                return Err(format!("{name}: unknown severity {s}"));
+            }
+            severity => severity,
+        },
+    };
+    let text = |field: &str| item[field].as_str().unwrap_or("").to_string();
+
+    Ok(Finding {
+        engine: engine.name().to_string(),
+        name: name.to_string(),
+        description: text("description"),
+        severity: severity,
+        identifiers: item["identifiers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|i| i.as_str().map(|i| i.to_string()))
+            .collect(),
+        file: relative_path(item["file"].as_str().unwrap_or("")),
+        line: item["line"].as_u64().unwrap_or(0),
+        end_line: item["end_line"].as_u64().unwrap_or(0),
+        dependency: text("dependency"),
+        fingerprint: text("fingerprint"),
+        ..Default::default()
+    })
+}
+
+/// findings of a `sec-engine-plugin/1` document, an error when the document breaks the protocol
+pub fn parse_output(engine: Engine, stdout: &str) -> Result<Vec<Finding>, String> {
+    let doc: Value = serde_json::from_str(stdout.trim()).map_err(|e| format!("stdout is not json: {e}"))?;
+    match doc["protocol"].as_str() {
+        Some(PROTOCOL) => {}
+        Some(other) => return Err(format!("unsupported protocol {other}, expected {PROTOCOL}")),
+        None => return Err(format!("missing protocol, expected {PROTOCOL}")),
+    }
+    let items = doc["findings"].as_array().ok_or("missing findings list")?;
+    let mut findings: Vec<Finding> = vec![];
+    for (idx, item) in items.iter().enumerate() {
+        match finding(engine, item) {
+            Ok(finding) => findings.push(finding),
+            Err(e) => log::warn!("finding {idx} skipped, {e}"),
+        }
+    }
+
+    Ok(findings)
+}
@@ -0,0 +1,123 @@
+//! out-of-tree report formats
+//!
+//! a crate of the workspace registers its formats and runs the command line with the registry:
+//!
+//! ```no_run
+//! use sec_engine::parser::base::Finding;
+//! use sec_engine::parser::detect::Engine;
+//! use sec_engine::parser::registry::{FindingParser, ParserRegistry};
+//!
+//! #[derive(Default)]
+//! struct AcmeScan {
+//!     findings: Vec<Finding>,
+//! }
+//!
+//! impl FindingParser for AcmeScan {
+//!     fn parse(&mut self, content: &str) {
+//!         let report: serde_json::Value = serde_json::from_str(content).unwrap_or_default();
+//!         for issue in report["issues"].as_array().into_iter().flatten() {
+//!             self.findings.push(Finding {
+//!                 name: issue["title"].as_str().unwrap_or("").to_string(),
+//!                 severity: issue["level"].as_str().unwrap_or("").to_string(),
+//!                 file: issue["path"].as_str().unwrap_or("").to_string(),
+//!                 line: issue["line"].as_u64().unwrap_or(0),
+//!                 ..Default::default()
+//!             });
+//!         }
+//!     }
+//!
+//!     fn findings(&self) -> Vec<Finding> {
+//!         self.findings.clone()
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     let mut registry = ParserRegistry::new();
+//!     registry.register(
+//!         "acme-scan",
+//!         Engine::Sast,
+//!         |_fpath, content| content.contains("\"acme_version\""),
+//!         AcmeScan::default,
+//!     );
+//!     let code = sec_engine::cli::run(std::env::args().collect(), &registry).await;
+//!     std::process::exit(code);
+//! }
+//! ```
+
+use super::{base::Finding, detect::Engine};
+
+/// a parser producing engine independent findings, one instance per input file
+pub trait FindingParser {
+    fn parse(&mut self, content: &str);
+    fn findings(&self) -> Vec<Finding>;
+}
+
+type Detect = Box<dyn Fn(&str, &str) -> bool + Send + Sync>;
+type Build = Box<dyn Fn() -> Box<dyn FindingParser> + Send + Sync>;
+
+/// a registered format, its findings are merged into the report of `engine`
+pub struct Registration {
+    pub name: String,
+    pub engine: Engine,
+    detect: Detect,
+    build: Build,
+}
+
+impl Registration {
+    /// `detect` predicate of the format, called with the path and content of an input file
+    pub fn matches(self: &Self, fpath: &str, content: &str) -> bool {
+        (self.detect)(fpath, content)
+    }
+
+    pub fn build(self: &Self) -> Box<dyn FindingParser> {
+        (self.build)()
+    }
+}
+
+#[derive(Default)]
+pub struct ParserRegistry {
+    registrations: Vec<Registration>,
+}
+
+impl ParserRegistry {
+    pub fn new() -> Self {
+        ParserRegistry {
+            registrations: vec![],
+        }
+    }
+
+    /// add a format, registered formats are detected before the built-in ones in registration order
+    pub fn register<D, B, P>(self: &mut Self, name: &str, engine: Engine, detect: D, build: B) -> &mut Self
+    where
+        D: Fn(&str, &str) -> bool + Send + Sync + 'static,
+        B: Fn() -> P + Send + Sync + 'static,
+        P: FindingParser + 'static,
+    {
+        if self.get(name).is_some() {
+            log::warn!("parser {name} is registered already, replaced");
+            self.registrations.retain(|r| r.name != name);
+        }
+        self.registrations.push(Registration {
+            name: name.to_string(),
+            engine: engine,
+            detect: Box::new(detect),
+            build: Box::new(move || Box::new(build()) as Box<dyn FindingParser>),
+        });
+
+        self
+    }
+
+    /// first registration accepting the input file
+    pub fn detect(self: &Self, fpath: &str, content: &str) -> Option<&Registration> {
+        self.registrations.iter().find(|r| r.matches(fpath, content))
+    }
+
+    pub fn get(self: &Self, name: &str) -> Option<&Registration> {
+        self.registrations.iter().find(|r| r.name == name)
+    }
+
+    pub fn names(self: &Self) -> Vec<&str> {
+        self.registrations.iter().map(|r| r.name.as_str()).collect()
+    }
+}
@@ -0,0 +1,91 @@
+//! sarif 2.1.0 logs, shared by the engines reading tool output (gitleaks, semgrep, gosec, ...)
+
+use serde_json::Value;
+
+use crate::utils::cvss;
+
+pub fn is_sarif(report: &Value) -> bool {
+    report["runs"].is_array()
+        && (report["version"].as_str().map_or(false, |v| v.starts_with("2.")) || report["$schema"].is_string())
+}
+
+/// a result with its rule resolved from `tool.driver.rules`
+#[derive(Debug, Clone, Default)]
+pub struct SarifResult<'a> {
+    pub tool: String,
+    pub rule_id: String,
+    // `shortDescription` of the rule, falls back to the message
+    pub rule_name: String,
+    pub message: String,
+    // error / warning / note / none, `security-severity` wins when the rule sets one
+    pub level: String,
+    pub file: String,
+    pub line: u64,
+    pub snippet: String,
+    pub rule: Option<&'a Value>,
+    pub result: Option<&'a Value>,
+}
+
+// cvss like `security-severity` of github code scanning
+fn security_severity(rule: Option<&Value>) -> Option<&'static str> {
+    let score: f64 = rule?["properties"]["security-severity"].as_str()?.parse().ok()?;
+    Some(cvss::severity(score))
+}
+
+/// every result of every run
+pub fn results(report: &Value) -> Vec<SarifResult<'_>> {
+    let mut items: Vec<SarifResult> = vec![];
+    for run in report["runs"].as_array().into_iter().flatten() {
+        let driver = &run["tool"]["driver"];
+        let rules = driver["rules"].as_array();
+        for result in run["results"].as_array().into_iter().flatten() {
+            let rule_id = result["ruleId"].as_str().or(result["rule"]["id"].as_str()).unwrap_or("");
+            let rule = result["ruleIndex"]
+                .as_u64()
+                .and_then(|idx| rules.and_then(|r| r.get(idx as usize)))
+                .or(rules.and_then(|r| r.iter().find(|rule| rule["id"] == rule_id)));
+            let message = result["message"]["text"].as_str().unwrap_or("").trim().to_string();
+            let level = security_severity(rule)
+                .or(result["level"].as_str())
+                .or(rule.and_then(|r| r["defaultConfiguration"]["level"].as_str()))
+                .unwrap_or("warning")
+                .to_string();
+            let location = &result["locations"][0]["physicalLocation"];
+            items.push(SarifResult {
+                tool: driver["name"].as_str().unwrap_or("").to_string(),
+                rule_id: rule_id.to_string(),
+                rule_name: rule
+                    .and_then(|r| r["shortDescription"]["text"].as_str().or(r["name"].as_str()))
+                    .unwrap_or(message.as_str())
+                    .to_string(),
+                message: message,
+                level: level,
+                file: location["artifactLocation"]["uri"]
+                    .as_str()
+                    .unwrap_or("")
+                    .trim_start_matches("file://")
+                    .trim_start_matches("./")
+                    .to_string(),
+                line: location["region"]["startLine"].as_u64().unwrap_or(0),
+                snippet: location["region"]["snippet"]["text"].as_str().unwrap_or("").to_string(),
+                rule: rule,
+                result: Some(result),
+            });
+        }
+    }
+
+    items
+}
+
+/// gitlab severity of a sarif level, `security-severity` levels pass through
+pub fn severity(level: &str) -> String {
+    match level.to_lowercase().as_str() {
+        "critical" => "Critical",
+        "error" | "high" => "High",
+        "warning" | "medium" => "Medium",
+        "note" | "low" => "Low",
+        "none" | "info" => "Info",
+        _ => "Unknown",
+    }
+    .to_string()
+}
@@ -8,13 +8,22 @@ use std::{
 
 use crate::{
     conf::setting::*,
-    parser::base::BaseParser,
-    utils::gitlab::{self, get_commit_diff, get_mr_commit_hash, Issue},
+    parser::{
+        base::{fingerprint_or, relative_path, severity_name, BaseParser, Finding},
+        sarif::{self, SarifResult},
+    },
+    utils::{
+        codequality, export, gate,
+        gitlab::{self, get_commit_diff, get_mr_commit_hash, Issue},
+        i18n, render, template,
+    },
 };
-use serde_json::Value;
+use serde_json::{json, Value};
 
 use super::base::BaseReport;
 
+const ISSUE_TEMPLATE: &str = "{{{header}}}{{#each findings}}\n|{{id}}{{{record}}}{{/each}}";
+
 #[derive(Debug)]
 pub struct SASTReport {
     pub engine: String,
@@ -22,14 +31,98 @@ pub struct SASTReport {
     pub diff_files: Vec<(String, String)>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SASTVul {
     message: String,
     description: String,
     severity: String,
     cve: String,
     location: String,
+    href: String,
+    file: String,
+    line: u64,
+    identifiers: Vec<String>,
     // owner: RiskOwner,
+    end_line: u64,
+    // High / Medium / Low, empty when the scanner does not tell
+    confidence: String,
+    // stable id given by the scanner, computed from the fields when empty
+    fingerprint: String,
+}
+
+/// document shapes the sast engine understands
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SastFormat {
+    Gitlab,
+    Semgrep,
+    Bandit,
+    Gosec,
+    Eslint,
+    Sarif,
+}
+
+impl SastFormat {
+    pub fn detect(report: &Value) -> Option<Self> {
+        if sarif::is_sarif(report) {
+            return Some(SastFormat::Sarif);
+        }
+        if report["vulnerabilities"].is_array() {
+            return Some(SastFormat::Gitlab);
+        }
+        if report["Issues"].is_array() && (report["Stats"].is_object() || report["GosecVersion"].is_string()) {
+            return Some(SastFormat::Gosec);
+        }
+        if let Some(results) = report["results"].as_array() {
+            let first = results.first().unwrap_or(&Value::Null);
+            if first["test_id"].is_string() || report["generated_at"].is_string() {
+                return Some(SastFormat::Bandit);
+            }
+            if first["check_id"].is_string() || report["paths"].is_object() || report["errors"].is_array() {
+                return Some(SastFormat::Semgrep);
+            }
+        }
+        let is_eslint = report.as_array().map_or(false, |files| {
+            files.iter().all(|f| f["filePath"].is_string() && f["messages"].is_array())
+        });
+        if is_eslint {
+            return Some(SastFormat::Eslint);
+        }
+
+        None
+    }
+}
+
+// eslint plugins and core rules with security relevance, other lint messages are ignored
+const ESLINT_SECURITY_PLUGINS: [&str; 6] = [
+    "security/",
+    "security-node/",
+    "no-unsanitized/",
+    "@microsoft/sdl/",
+    "xss/",
+    "react/no-danger",
+];
+const ESLINT_SECURITY_RULES: [&str; 4] = ["no-eval", "no-implied-eval", "no-new-func", "no-script-url"];
+
+// `High` from `HIGH`, `high`, empty stays empty
+fn capitalize(value: &str) -> String {
+    let value = value.trim().to_lowercase();
+    let mut chars = value.chars();
+    chars.next().map_or(String::new(), |c| c.to_uppercase().collect::<String>() + chars.as_str())
+}
+
+// `CWE-79` from `CWE-79: Improper Neutralization ...`, `79` or 79
+fn cwe_id(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.to_string(),
+        _ => return None,
+    };
+    let id = text.trim().trim_start_matches("CWE-").split(|c: char| !c.is_ascii_digit()).next()?;
+    if id.is_empty() {
+        None
+    } else {
+        Some(format!("CWE-{id}"))
+    }
 }
 
 impl SASTVul {
@@ -40,25 +133,211 @@ impl SASTVul {
             severity: String::new(),
             cve: String::new(),
             location: String::new(),
+            href: String::new(),
+            file: String::new(),
+            line: 0,
+            identifiers: vec![],
             // owner: todo!(),
+            end_line: 0,
+            confidence: String::new(),
+            fingerprint: String::new(),
         }
     }
 
     pub fn to_issue_record(self: &Self) -> String {
         let mut values: Vec<String> = Vec::new();
-        values.push(self.message.clone());
-        values.push(self.severity.clone());
-        values.push(format!(
-            "{}",
-            self.cve.replace("semgrep_id:find_sec_bugs.", ""),
+        values.push(render::cell(self.message.as_str(), 200));
+        values.push(render::cell(self.severity.as_str(), 20));
+        values.push(render::code(
+            self.cve.replace("semgrep_id:find_sec_bugs.", "").as_str(),
         ));
-        values.push(self.location.clone());
-        values.push(self.description.replace("\n", "<br>").clone());
+        values.push(render::link(self.location.as_str(), self.href.as_str()));
+        let mut description = render::cell(self.description.as_str(), render::MAX_CELL_LEN);
+        let mut meta: Vec<String> = vec![];
+        if !self.confidence.is_empty() {
+            meta.push(format!("confidence {}", self.confidence));
+        }
+        meta.extend(self.identifiers.iter().filter(|id| **id != self.cve).cloned());
+        if !meta.is_empty() {
+            description = format!("{description} {}", render::code(meta.join(", ").as_str()));
+        }
+        values.push(description);
 
-        let result: String = values.join("|");
-        // println!("issue: |{result}|");
+        render::row(&values)
+    }
 
-        format!("|{}|", result)
+    // normalized vul of a native scanner, `line` and `end_line` span the finding
+    fn native(
+        rule: &str,
+        message: &str,
+        description: &str,
+        severity: String,
+        confidence: &str,
+        file: &str,
+        line: u64,
+        end_line: u64,
+        identifiers: Vec<String>,
+    ) -> Self {
+        let file = relative_path(file);
+        let location = if end_line > line {
+            format!("{file}:{line}-{end_line}")
+        } else {
+            format!("{file}:{line}")
+        };
+        let mut ids: Vec<String> = vec![rule.to_string()];
+        for id in identifiers {
+            if !id.is_empty() && !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+
+        SASTVul {
+            message: message.trim().to_string(),
+            description: description.trim().to_string(),
+            severity: severity,
+            cve: rule.to_string(),
+            location: location,
+            href: gitlab::blob_url(file.as_str(), line),
+            file: file,
+            line: line,
+            identifiers: ids,
+            end_line: end_line.max(line),
+            confidence: capitalize(confidence),
+            fingerprint: String::new(),
+        }
+    }
+
+    /// `results[]` entry of `semgrep --json`, cwe and owasp from `extra.metadata`
+    fn from_semgrep(result: &Value) -> Self {
+        let extra = &result["extra"];
+        let metadata = &extra["metadata"];
+        let mut identifiers: Vec<String> = vec![];
+        for key in ["cwe", "owasp"] {
+            let values = match &metadata[key] {
+                Value::Array(items) => items.clone(),
+                Value::Null => vec![],
+                value => vec![value.clone()],
+            };
+            for value in values.iter() {
+                let id = if key == "cwe" {
+                    cwe_id(value)
+                } else {
+                    // `A03:2021 - Injection`
+                    value.as_str().map(|v| v.split(" - ").next().unwrap_or(v).trim().to_string())
+                };
+                identifiers.extend(id);
+            }
+        }
+        // gitlab maps semgrep ERROR / WARNING / INFO to high / medium / low
+        let severity = match extra["severity"].as_str().unwrap_or("").to_uppercase().as_str() {
+            "ERROR" => String::from("High"),
+            "WARNING" => String::from("Medium"),
+            "INFO" => String::from("Low"),
+            other => severity_name(other),
+        };
+        let message = extra["message"].as_str().unwrap_or("");
+
+        SASTVul::native(
+            result["check_id"].as_str().unwrap_or(""),
+            message.lines().next().unwrap_or(""),
+            message,
+            severity,
+            metadata["confidence"].as_str().unwrap_or(""),
+            result["path"].as_str().unwrap_or(""),
+            result["start"]["line"].as_u64().unwrap_or(0),
+            result["end"]["line"].as_u64().unwrap_or(0),
+            identifiers,
+        )
+    }
+
+    /// `results[]` entry of `bandit -f json`
+    fn from_bandit(result: &Value) -> Self {
+        let range: Vec<u64> = result["line_range"]
+            .as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .filter_map(|l| l.as_u64())
+            .collect();
+        let line = result["line_number"].as_u64().unwrap_or(0);
+        let rule = result["test_id"].as_str().unwrap_or("");
+        let name = result["test_name"].as_str().unwrap_or(rule);
+
+        SASTVul::native(
+            rule,
+            format!("{rule} {name}").as_str(),
+            result["issue_text"].as_str().unwrap_or(""),
+            severity_name(result["issue_severity"].as_str().unwrap_or("")),
+            result["issue_confidence"].as_str().unwrap_or(""),
+            result["filename"].as_str().unwrap_or(""),
+            line,
+            range.iter().max().copied().unwrap_or(line),
+            cwe_id(&result["issue_cwe"]["id"]).into_iter().collect(),
+        )
+    }
+
+    /// `Issues[]` entry of `gosec -fmt json`, `line` is `42` or `42-45`
+    fn from_gosec(issue: &Value) -> Self {
+        let span = issue["line"].as_str().unwrap_or("0");
+        let (start, end) = span.split_once('-').unwrap_or((span, span));
+        let line: u64 = start.trim().parse().unwrap_or(0);
+        let details = issue["details"].as_str().unwrap_or("");
+
+        SASTVul::native(
+            issue["rule_id"].as_str().unwrap_or(""),
+            details,
+            details,
+            severity_name(issue["severity"].as_str().unwrap_or("")),
+            issue["confidence"].as_str().unwrap_or(""),
+            issue["file"].as_str().unwrap_or(""),
+            line,
+            end.trim().parse().unwrap_or(line),
+            cwe_id(&issue["cwe"]["id"]).into_iter().collect(),
+        )
+    }
+
+    /// `messages[]` entry of `eslint -f json`, severity 2 is an error
+    fn from_eslint(message: &Value, file: &str) -> Self {
+        let line = message["line"].as_u64().unwrap_or(0);
+        let text = message["message"].as_str().unwrap_or("");
+
+        SASTVul::native(
+            message["ruleId"].as_str().unwrap_or(""),
+            text,
+            text,
+            String::from(if message["severity"].as_u64() == Some(2) { "High" } else { "Medium" }),
+            "",
+            file,
+            line,
+            message["endLine"].as_u64().unwrap_or(line),
+            vec![],
+        )
+    }
+
+    /// sarif result (semgrep, gosec, eslint sarif formatters), cwe ids from the rule tags
+    fn from_sarif(result: &SarifResult) -> Self {
+        let tags = result.rule.map_or(&Value::Null, |r| r)["properties"]["tags"].clone();
+        let identifiers: Vec<String> = tags
+            .as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .filter(|tag| tag.as_str().map_or(false, |t| t.starts_with("CWE")))
+            .filter_map(cwe_id)
+            .collect();
+        let end_line = result.result.map_or(&Value::Null, |r| r)["locations"][0]["physicalLocation"]["region"]["endLine"]
+            .as_u64()
+            .unwrap_or(result.line);
+
+        SASTVul::native(
+            result.rule_id.as_str(),
+            result.rule_name.as_str(),
+            result.message.as_str(),
+            sarif::severity(result.level.as_str()),
+            "",
+            result.file.as_str(),
+            result.line,
+            end_line,
+            identifiers,
+        )
     }
 }
 
@@ -71,13 +350,18 @@ impl From<&Value> for SASTVul {
         // let location = obj["location"]..as_str().unwrap();
         let location_fpath = obj["location"]["file"].as_str().unwrap().to_string();
         let location_lineno = obj["location"]["start_line"].to_string().replace("\"", "");
-        let location_href = format!(
-            "{}/-/blob/develop/{}",
-            CI_PROJECT_URL.as_str(),
-            location_fpath
-        );
+        let line = obj["location"]["start_line"].as_u64().unwrap_or(0);
+        let location_href = gitlab::blob_url(location_fpath.as_str(), line);
 
-        let location = format!("[{location_fpath}:{location_lineno}]({location_href})");
+        let location = format!("{location_fpath}:{location_lineno}");
+        let mut identifiers: Vec<String> = vec![];
+        if let Some(ids) = obj["identifiers"].as_array() {
+            for id in ids {
+                if let Some(name) = id["name"].as_str() {
+                    identifiers.push(name.to_string());
+                }
+            }
+        }
 
         SASTVul {
             message,
@@ -85,11 +369,39 @@ impl From<&Value> for SASTVul {
             severity,
             cve,
             location,
+            href: location_href,
+            file: location_fpath,
+            line,
+            identifiers,
             // owner: todo!(),
+            end_line: line,
+            confidence: String::new(),
+            fingerprint: String::new(),
         }
     }
 }
 
+impl From<&Finding> for SASTVul {
+    fn from(finding: &Finding) -> Self {
+        let rule = finding.identifiers.first().cloned().unwrap_or_default();
+
+        let mut vul = SASTVul::native(
+            rule.as_str(),
+            finding.name.as_str(),
+            finding.description.as_str(),
+            severity_name(finding.severity.as_str()),
+            "",
+            finding.file.as_str(),
+            finding.line,
+            finding.end_line,
+            finding.identifiers.clone(),
+        );
+        vul.fingerprint = finding.fingerprint.to_string();
+
+        vul
+    }
+}
+
 impl SASTReport {
     pub fn new() -> Self {
         let report = SASTReport {
@@ -118,6 +430,25 @@ impl SASTReport {
 
         self.diff_files.clone()
     }
+
+    pub fn issue_context(self: &Self) -> Value {
+        const COLS: [&str; 6] = ["id", "title", "severity", "cve", "location", "description"];
+        let findings: Vec<Value> = self
+            .findings()
+            .iter()
+            .zip(self.vuls.iter())
+            .enumerate()
+            .map(|(idx, (finding, vul))| {
+                template::finding_value(idx + 1, finding, vul.to_issue_record())
+            })
+            .collect();
+
+        template::context(
+            self.engine.as_str(),
+            findings,
+            json!({ "header": i18n::table_header(&COLS) }),
+        )
+    }
 }
 
 impl Display for SASTReport {
@@ -130,43 +461,103 @@ impl BaseParser<SASTVul> for SASTReport {
     fn parse(self: &mut Self, content: &str) -> &Vec<SASTVul> {
         let report: Value = serde_json::from_str(content).expect("json format error");
         // let reports = Rc::new(RefCell::new(self));
-        if let Some(vulnerabilities) = report["vulnerabilities"].as_array() {
-            for vul in vulnerabilities {
-                let _vul: SASTVul = SASTVul::from(vul);
-                // println!("{:?}", _vul);
-                // reports.push(_vul);
-                self.vuls.push(_vul);
+        match SastFormat::detect(&report) {
+            Some(SastFormat::Gitlab) => {
+                for vul in report["vulnerabilities"].as_array().unwrap() {
+                    let _vul: SASTVul = SASTVul::from(vul);
+                    // println!("{:?}", _vul);
+                    // reports.push(_vul);
+                    self.vuls.push(_vul);
+                }
+            }
+            Some(SastFormat::Semgrep) => {
+                for result in report["results"].as_array().unwrap() {
+                    self.vuls.push(SASTVul::from_semgrep(result));
+                }
+            }
+            Some(SastFormat::Bandit) => {
+                for result in report["results"].as_array().unwrap() {
+                    self.vuls.push(SASTVul::from_bandit(result));
+                }
+            }
+            Some(SastFormat::Gosec) => {
+                // `-show-ignored` keeps #nosec findings in the output, they stay suppressed
+                for issue in report["Issues"].as_array().unwrap() {
+                    if issue["nosec"].as_bool() != Some(true) {
+                        self.vuls.push(SASTVul::from_gosec(issue));
+                    }
+                }
             }
+            Some(SastFormat::Eslint) => {
+                for file in report.as_array().unwrap() {
+                    let fpath = file["filePath"].as_str().unwrap_or("");
+                    for message in file["messages"].as_array().unwrap() {
+                        let rule = message["ruleId"].as_str().unwrap_or("");
+                        if ESLINT_SECURITY_PLUGINS.iter().any(|p| rule.starts_with(p))
+                            || ESLINT_SECURITY_RULES.contains(&rule)
+                        {
+                            self.vuls.push(SASTVul::from_eslint(message, fpath));
+                        }
+                    }
+                }
+            }
+            Some(SastFormat::Sarif) => {
+                for result in sarif::results(&report).iter() {
+                    self.vuls.push(SASTVul::from_sarif(result));
+                }
+            }
+            None => log::warn!("unknown sast report format"),
         }
 
         &self.vuls
     }
 
-    fn export(self: &Self, excel: &str) -> bool {
-        todo!()
+    fn merge(self: &mut Self, findings: &[Finding]) -> &Vec<SASTVul> {
+        self.vuls.extend(findings.iter().map(SASTVul::from));
+
+        &self.vuls
+    }
+
+    fn export(self: &Self, fpath: &str) -> bool {
+        export::write(&self.findings(), fpath)
     }
 
     fn to_issue(self: &Self) -> Issue {
-        const COLS: [&str; 6] = ["id", "title", "severity", "cve", "location", "description"];
-        let title = format!("|{}|", COLS.join("|"));
-        let sep = format!("|{}|", ["--"; COLS.len()].join("|"));
-        let mut desc: String = format!("{title}\n{sep}");
+        let ctx = self.issue_context();
         let mut issue: Issue = Issue::new();
-        
-        let mut idx = 1;
-        for vul in self.vuls.iter() {
-            desc = format!("{desc}\n|{idx}{}", vul.to_issue_record());
-            idx+=1;
-        }
-
         issue.engine = self.engine.to_string();
         issue.project_id = CI_PROJECT_ID.to_string();
         issue.assignee_id = GITLAB_USER_ID.to_string();
-        issue.title = format!("{} scan report", self.engine);
-        issue.description = desc.replace("\\n", "\n");
+        issue.title = template::issue_title(&ctx);
+        issue.description = template::issue_description(&ctx, ISSUE_TEMPLATE);
         issue
     }
 
+    fn findings(self: &Self) -> Vec<Finding> {
+        self.vuls
+            .iter()
+            .map(|vul| Finding {
+                engine: self.engine.to_string(),
+                name: vul.message.to_string(),
+                description: vul.description.to_string(),
+                severity: vul.severity.to_string(),
+                identifiers: vul.identifiers.clone(),
+                file: vul.file.to_string(),
+                line: vul.line,
+                end_line: vul.end_line,
+                dependency: String::new(),
+                // line excluded so the fingerprint survives code moving around
+                fingerprint: fingerprint_or(vul.fingerprint.as_str(), &[
+                    self.engine.as_str(),
+                    vul.identifiers.first().unwrap_or(&vul.cve).as_str(),
+                    vul.file.as_str(),
+                    vul.message.as_str(),
+                ]),
+                ..Default::default()
+            })
+            .collect()
+    }
+
     async fn is_in_diff(self: &Self, fpath: &str) -> bool {
         let mut flag = false;
         for (_fpath, _) in self.diff_files.iter() {
@@ -182,7 +573,7 @@ impl BaseParser<SASTVul> for SASTReport {
     async fn filter(self: &mut Self) -> Vec<SASTVul> {
         let mut vuls: Vec<SASTVul> = vec![];
         for vul in &self.vuls {
-            if CI_MERGE_REQUEST_IID.as_str() == "" || self.is_in_diff(vul.location.as_str()).await {
+            if CI_MERGE_REQUEST_IID.as_str() == "" || self.is_in_diff(vul.file.as_str()).await {
                 vuls.push(vul.clone());
             }
         }
@@ -196,20 +587,163 @@ impl BaseParser<SASTVul> for SASTReport {
 
 impl BaseReport<SASTVul> for SASTReport {
     async fn report(self: &mut Self) {
+        codequality::write_report(&self.findings());
         // comment for debug
         self.filter().await;
+        for fpath in export::export_paths(self.engine.as_str()) {
+            self.export(fpath.as_str());
+        }
+        gate::evaluate(&self.findings());
 
         if self.vuls.len() < 1 {
             println!("no issue, skip create new issue...");
             return;
         }
 
-        let issue = self.to_issue();
+        let mut issue = self.to_issue();
 
         if CI_MERGE_REQUEST_IID.as_str() == "" {
             log::error!("no merge request, skip create issue...");
             return;
         }
-        let _ = gitlab::new_issue(&issue).await;
+        let notes = template::split_issue(&mut issue, &self.issue_context());
+        let _ = gitlab::new_issue_with_notes(&issue, &notes).await;
+        if let Some(note) = template::mr_note(&self.issue_context()) {
+            let _ = gitlab::new_mr_comment(note).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(report: Value) -> Vec<SASTVul> {
+        let mut sast = SASTReport::new();
+        sast.parse(report.to_string().as_str()).clone()
+    }
+
+    #[test]
+    fn test_detect() {
+        // an empty run carries no result to look at, the top level keys decide
+        let bandit = json!({ "generated_at": "2024-01-01T00:00:00Z", "results": [], "errors": [] });
+        assert_eq!(SastFormat::detect(&bandit), Some(SastFormat::Bandit));
+        let semgrep = json!({ "results": [], "paths": { "scanned": [] } });
+        assert_eq!(SastFormat::detect(&semgrep), Some(SastFormat::Semgrep));
+        let semgrep = json!({ "results": [], "errors": [] });
+        assert_eq!(SastFormat::detect(&semgrep), Some(SastFormat::Semgrep));
+        let gosec = json!({ "Issues": [], "Stats": {} });
+        assert_eq!(SastFormat::detect(&gosec), Some(SastFormat::Gosec));
+        let eslint = json!([{ "filePath": "a.js", "messages": [] }]);
+        assert_eq!(SastFormat::detect(&eslint), Some(SastFormat::Eslint));
+        assert!(SastFormat::detect(&json!({ "results": [] })).is_none());
+    }
+
+    #[test]
+    fn test_from_semgrep() {
+        let vuls = parse(json!({
+            "results": [{
+                "check_id": "python.lang.security.audit.eval-detected",
+                "path": "app/main.py",
+                "start": { "line": 10 },
+                "end": { "line": 12 },
+                "extra": {
+                    "severity": "ERROR",
+                    "message": "Detected the use of eval()",
+                    "metadata": {
+                        "confidence": "LOW",
+                        "cwe": ["CWE-95: Improper Neutralization of Directives"],
+                        "owasp": ["A03:2021 - Injection"],
+                    },
+                },
+            }],
+            "paths": { "scanned": ["app/main.py"] },
+        }));
+        assert_eq!(vuls.len(), 1);
+        assert_eq!(
+            vuls[0].identifiers,
+            vec!["python.lang.security.audit.eval-detected", "CWE-95", "A03:2021"]
+        );
+        assert_eq!(vuls[0].severity, "High");
+        assert_eq!(vuls[0].confidence, "Low");
+        assert_eq!((vuls[0].line, vuls[0].end_line), (10, 12));
+    }
+
+    #[test]
+    fn test_from_bandit() {
+        let vuls = parse(json!({
+            "generated_at": "2024-01-01T00:00:00Z",
+            "results": [{
+                "test_id": "B307",
+                "test_name": "blacklist",
+                "filename": "app/main.py",
+                "issue_text": "Use of possibly insecure function",
+                "issue_severity": "MEDIUM",
+                "issue_confidence": "HIGH",
+                "issue_cwe": { "id": 78 },
+                "line_number": 7,
+                "line_range": [7, 8, 9],
+            }],
+        }));
+        assert_eq!(vuls.len(), 1);
+        assert_eq!(vuls[0].identifiers, vec!["B307", "CWE-78"]);
+        assert_eq!(vuls[0].severity, "Medium");
+        assert_eq!(vuls[0].confidence, "High");
+        assert_eq!((vuls[0].line, vuls[0].end_line), (7, 9));
+    }
+
+    #[test]
+    fn test_from_gosec() {
+        let vuls = parse(json!({
+            "GosecVersion": "2.18.2",
+            "Issues": [
+                {
+                    "rule_id": "G204",
+                    "details": "Subprocess launched with variable",
+                    "severity": "MEDIUM",
+                    "confidence": "HIGH",
+                    "cwe": { "id": "78" },
+                    "file": "cmd/run.go",
+                    "line": "42-45",
+                },
+                {
+                    "rule_id": "G104",
+                    "details": "Errors unhandled",
+                    "severity": "LOW",
+                    "confidence": "HIGH",
+                    "file": "cmd/run.go",
+                    "line": "50",
+                    "nosec": true,
+                },
+            ],
+        }));
+        // the #nosec finding stays suppressed
+        assert_eq!(vuls.len(), 1);
+        assert_eq!(vuls[0].identifiers, vec!["G204", "CWE-78"]);
+        assert_eq!(vuls[0].severity, "Medium");
+        assert_eq!(vuls[0].confidence, "High");
+        assert_eq!((vuls[0].line, vuls[0].end_line), (42, 45));
+        assert_eq!(vuls[0].location, "cmd/run.go:42-45");
+    }
+
+    #[test]
+    fn test_from_eslint() {
+        let vuls = parse(json!([{
+            "filePath": "src/app.js",
+            "messages": [
+                { "ruleId": "security/detect-object-injection", "severity": 2, "message": "Generic Object Injection Sink", "line": 3, "endLine": 4 },
+                { "ruleId": "no-eval", "severity": 1, "message": "eval can be harmful.", "line": 8 },
+                { "ruleId": "no-unused-vars", "severity": 2, "message": "'x' is defined but never used.", "line": 1 },
+            ],
+        }]));
+        // lint rules without security relevance are dropped
+        assert_eq!(vuls.len(), 2);
+        assert_eq!(vuls[0].identifiers, vec!["security/detect-object-injection"]);
+        assert_eq!(vuls[0].severity, "High");
+        assert_eq!(vuls[0].confidence, "");
+        assert_eq!((vuls[0].line, vuls[0].end_line), (3, 4));
+        assert_eq!(vuls[1].identifiers, vec!["no-eval"]);
+        assert_eq!(vuls[1].severity, "Medium");
+        assert_eq!((vuls[1].line, vuls[1].end_line), (8, 8));
     }
 }
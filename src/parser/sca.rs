@@ -1,29 +1,45 @@
 use std::borrow::Borrow;
 use std::cmp::{self, Ordering};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::format;
 use std::hash::Hash;
+use std::path::Path;
 
 // SCA
 use crate::conf::setting::*;
-use crate::utils::{gitlab, llm};
-use crate::{parser::base::BaseParser, utils::gitlab::Issue};
+use crate::utils::version::{self, Scheme};
+use crate::utils::cvedb::{CveDb, CveRecord};
+use crate::utils::cyclonedx::{self, Bom, Component, Vex};
+use crate::utils::remediate::Upgrade;
+use crate::utils::threat::{self, ThreatIntel};
+use crate::utils::{cvss, export, gate, gitlab, i18n, llm, render, spdx_export, template};
+use crate::{
+    parser::base::{fingerprint_or, relative_path, severity_name, BaseParser, Finding},
+    parser::{cargo_audit, depcheck, grype, npm_audit, trivy},
+    utils::gitlab::Issue,
+};
 use serde_json::{json, map, Value};
 
 use super::base::BaseReport;
 use super::secret::SecVul;
 use log::*;
 
+const ISSUE_TEMPLATE: &str = "{{#if kev_warning}}<br>**{{{kev_warning}}}**<br>{{/if}}<br>{{{intro}}}<br>\n\
+{{#if grouped}}{{#each groups}}\n\
+----------------------------------{{location}}------------------------------------------\n\
+{{{header}}}{{#each findings}}\n|{{id}}{{{record}}}{{/each}}\n\n{{/each}}\
+{{else}}{{#each findings}}\n|{{id}}{{{record}}}{{/each}}{{/if}}";
+
 #[derive(Debug)]
 pub struct SCAReport {
     pub engine: String,
     pub(crate) vuls: Vec<SCAVul>,
     pub(crate) merged_vuls: Vec<SCAVul>,
-    pub(crate) merged_vuls_map: HashMap<String, Vec<SCAVul>>, // or group map with ai
-    pub(crate) cached_vuls_map: HashMap<String, SCAVul>, // for distinct vul map
+    pub(crate) merged_vuls_map: Vec<(String, Vec<SCAVul>)>, // aggregated rows by manifest, in order of first appearance
+    pub(crate) sbom: Bom, // components and dependency graph of every parsed input
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct SCAVul {
     message: String,
     description: String,
@@ -33,6 +49,23 @@ pub struct SCAVul {
     solution: String,
     dependency_name: String,
     dependency_version: String,
+    reference: Option<(String, String)>, // (name, url) linked after the description
+    id: String,
+    identifiers: Vec<String>,
+    fixed_versions: Vec<String>,
+    scheme: Scheme,
+    cvss_score: Option<f64>,
+    cvss_vector: String,
+    cwes: Vec<String>,
+    published: String,
+    epss: Option<f64>,
+    kev: bool,
+    priority: f64,
+    // shortest chain from a direct dependency, `name@version`, empty when unknown or direct
+    dependency_path: Vec<String>,
+    affected_locations: usize,
+    // stable id given by the scanner, computed from the fields when empty
+    fingerprint: String,
 }
 
 impl SCAVul {
@@ -49,19 +82,6 @@ impl SCAVul {
         v
     }
 
-    fn get_risk_cn(self: & Self) -> String {
-        let v = match self.severity.to_lowercase().as_str() {
-            "critical" => "严重",
-            "high" => "高",
-            "medium" => "中",
-            "low" => "低",
-            "info" => "可忽略",
-            _ => "可忽略",
-        };
-
-        String::from(v)
-    }
-
     fn to_issue_record(self: &Self) -> String {
         let mut values = vec![];
 
@@ -77,6 +97,516 @@ impl SCAVul {
 
         format!("|{}|", values.join("|"))
     }
+
+    /// fill the gaps of the report from a local cve db record
+    fn enrich(self: &mut Self, record: &CveRecord) {
+        // the report vector wins unless the db has a v3 one for a v2-only vul
+        if record.cvss_score.is_some()
+            && (self.cvss_score.is_none()
+                || (!self.cvss_vector.starts_with("CVSS:3")
+                    && record.cvss_vector.starts_with("CVSS:3")))
+        {
+            self.cvss_score = record.cvss_score;
+            self.cvss_vector = record.cvss_vector.to_string();
+        }
+        for cwe in record.cwes.iter() {
+            if !self.cwes.contains(cwe) {
+                self.cwes.push(cwe.to_string());
+            }
+        }
+        if self.published.is_empty() {
+            self.published = record.published.to_string();
+        }
+        for alias in [&record.id].into_iter().chain(record.aliases.iter()) {
+            if self.cve.is_empty() && alias.starts_with("CVE-") {
+                self.cve = alias.to_string();
+            }
+            if !self.identifiers.contains(alias) {
+                self.identifiers.push(alias.to_string());
+            }
+        }
+
+        // maven names are group/artifact in gemnasium and group:artifact in osv, nvd only has the cpe product
+        let name = self.dependency_name.to_lowercase().replace(':', "/");
+        let product = name.rsplit('/').next().unwrap_or("").to_string();
+        for range in record.fixed.iter() {
+            let package = range.package.to_lowercase().replace(':', "/");
+            if package != name && package != product {
+                continue;
+            }
+            let current = self.dependency_version.as_str();
+            let introduced = range.introduced.as_str();
+            if !introduced.is_empty()
+                && introduced != "0"
+                && version::compare(self.scheme, introduced, current) == Ordering::Greater
+            {
+                continue;
+            }
+            if version::compare(self.scheme, current, range.fixed.as_str()) == Ordering::Less
+                && !self.fixed_versions.contains(&range.fixed)
+            {
+                self.fixed_versions.push(range.fixed.to_string());
+            }
+        }
+    }
+
+    fn to_finding(self: &Self, engine: &str) -> Finding {
+        Finding {
+            engine: engine.to_string(),
+            name: self.message.to_string(),
+            description: self.description.to_string(),
+            severity: self.severity.to_string(),
+            identifiers: if self.identifiers.is_empty() {
+                vec![self.cve.to_string()]
+            } else {
+                self.identifiers.iter().chain(self.cwes.iter()).cloned().collect()
+            },
+            file: self.location.to_string(),
+            line: 0,
+            end_line: 0,
+            dependency: format!("{}:{}", self.dependency_name, self.dependency_version),
+            fingerprint: fingerprint_or(self.fingerprint.as_str(), &[
+                engine,
+                self.location.as_str(),
+                self.dependency_name.as_str(),
+                self.dependency_version.as_str(),
+                self.cve.as_str(),
+            ]),
+            kev: self.kev,
+            priority: Some(self.priority),
+        }
+    }
+
+    // escaped issue table row, to_issue_record keeps the raw values for the llm
+    fn to_issue_row(self: &Self) -> String {
+        let mut values = vec![];
+        let mut dependency = render::code(
+            format!("{}:{}", self.dependency_name, self.dependency_version).as_str(),
+        );
+        if self.kev {
+            dependency = format!("**KEV** {dependency}");
+        }
+        if self.dependency_path.len() > 1 {
+            let path = render::cell(self.dependency_path.join(" > ").as_str(), 300);
+            dependency = format!("{dependency}<br>{}", i18n::tf("sca.path", &[("path", path.as_str())]));
+        }
+        values.push(dependency);
+        values.push(render::cell(self.severity.as_str(), 20));
+        values.push(format!("{:.0}", self.priority));
+        values.push(render::cell(self.cve.as_str(), 200));
+        values.push(render::cell(self.location.as_str(), 200));
+        values.push(self.affected_locations.to_string());
+        values.push(render::cell(self.solution.as_str(), 300));
+        let mut description = render::cell(self.description.as_str(), render::MAX_CELL_LEN);
+        let mut meta: Vec<String> = vec![];
+        if let Some(score) = self.cvss_score {
+            meta.push(format!("CVSS {score:.1}"));
+        }
+        if let Some(epss) = self.epss {
+            meta.push(format!("EPSS {:.1}%", epss * 100.0));
+        }
+        meta.extend(self.cwes.iter().cloned());
+        if !self.published.is_empty() {
+            meta.push(self.published.chars().take(10).collect());
+        }
+        if !meta.is_empty() {
+            description = format!("{description} {}", render::code(meta.join(", ").as_str()));
+        }
+        if let Some((name, url)) = &self.reference {
+            description = format!("{description} {}", render::link(name, url));
+        }
+        values.push(description);
+
+        render::row(&values)
+    }
+}
+
+impl SCAVul {
+    /// one vul per affected component of a cyclonedx vulnerability
+    fn from_cyclonedx(vuln: &Value, component: &Component) -> Self {
+        let id = vuln["id"].as_str().unwrap_or("").to_string();
+        let mut identifiers: Vec<String> = vec![id.to_string()];
+        for reference in vuln["references"].as_array().unwrap_or(&vec![]) {
+            if let Some(rid) = reference["id"].as_str() {
+                if !identifiers.contains(&rid.to_string()) {
+                    identifiers.push(rid.to_string());
+                }
+            }
+        }
+        identifiers.retain(|i| !i.is_empty());
+        let cve = identifiers
+            .iter()
+            .find(|i| i.starts_with("CVE-"))
+            .cloned()
+            .unwrap_or_default();
+
+        let mut severity = String::from("Unknown");
+        let mut cvss_score: Option<f64> = None;
+        let mut cvss_vector = String::new();
+        for rating in vuln["ratings"].as_array().unwrap_or(&vec![]) {
+            if let Some(level) = rating["severity"].as_str().filter(|s| !s.is_empty() && *s != "unknown") {
+                if severity == "Unknown" {
+                    let mut chars = level.chars();
+                    severity = chars.next().map_or(String::new(), |c| c.to_uppercase().collect())
+                        + chars.as_str();
+                }
+            }
+            // the vector may be given without its `CVSS:3.x/` prefix
+            let mut vector = rating["vector"].as_str().unwrap_or("").to_string();
+            match rating["method"].as_str().unwrap_or("") {
+                "CVSSv31" if !vector.starts_with("CVSS:") => vector = format!("CVSS:3.1/{vector}"),
+                "CVSSv3" if !vector.starts_with("CVSS:") => vector = format!("CVSS:3.0/{vector}"),
+                _ => {}
+            }
+            if let Some(parsed) = cvss::parse(vector.as_str()) {
+                if cvss_score.map_or(true, |score| parsed.score > score) {
+                    cvss_score = Some(parsed.score);
+                    cvss_vector = parsed.vector;
+                }
+            }
+        }
+
+        let description = vuln["description"]
+            .as_str()
+            .or(vuln["detail"].as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        let solution = vuln["recommendation"].as_str().unwrap_or("").to_string();
+        let mut scheme = component.scheme();
+        if scheme == Scheme::Generic {
+            scheme = Scheme::from_file(component.location.as_str());
+        }
+
+        SCAVul {
+            message: description.lines().next().filter(|l| !l.is_empty()).unwrap_or(id.as_str()).to_string(),
+            description: description.to_string(),
+            severity: severity,
+            cve: cve,
+            location: component.location.to_string(),
+            fixed_versions: version::parse_fixed_versions(solution.as_str()),
+            solution: solution,
+            dependency_name: component.package_name(),
+            dependency_version: component.version.to_string(),
+            id: id,
+            identifiers: identifiers,
+            scheme: scheme,
+            cvss_score: cvss_score,
+            cvss_vector: cvss_vector,
+            cwes: vuln["cwes"]
+                .as_array()
+                .unwrap_or(&vec![])
+                .iter()
+                .filter_map(|cwe| cwe.as_u64().map(|n| format!("CWE-{n}")))
+                .collect(),
+            published: vuln["published"].as_str().unwrap_or("").to_string(),
+            affected_locations: 1,
+            ..Default::default()
+        }
+    }
+
+    /// vul of a trivy `Results[].Vulnerabilities` entry, `kind` is the result `Type`
+    fn from_trivy(vuln: &Value, target: &str, kind: &str) -> Self {
+        let id = vuln["VulnerabilityID"].as_str().unwrap_or("").to_string();
+        let mut identifiers: Vec<String> = vec![id.to_string()];
+        for vid in vuln["VendorIDs"].as_array().unwrap_or(&vec![]) {
+            if let Some(vid) = vid.as_str().filter(|v| !identifiers.contains(&v.to_string())) {
+                identifiers.push(vid.to_string());
+            }
+        }
+        identifiers.retain(|i| !i.is_empty());
+        let name = vuln["PkgName"].as_str().unwrap_or("").to_string();
+        let fixed = vuln["FixedVersion"].as_str().unwrap_or("");
+        let (cvss_score, cvss_vector) = match trivy::cvss(vuln) {
+            Some((score, vector)) => (Some(score), vector),
+            None => (None, String::new()),
+        };
+
+        SCAVul {
+            message: vuln["Title"].as_str().filter(|t| !t.is_empty()).unwrap_or(id.as_str()).to_string(),
+            description: vuln["Description"].as_str().unwrap_or("").trim().to_string(),
+            severity: severity_name(vuln["Severity"].as_str().unwrap_or("")),
+            cve: identifiers.iter().find(|i| i.starts_with("CVE-")).cloned().unwrap_or_default(),
+            location: target.to_string(),
+            solution: if fixed.is_empty() { String::new() } else { format!("Upgrade {name} to {fixed}") },
+            dependency_version: vuln["InstalledVersion"].as_str().unwrap_or("").to_string(),
+            dependency_name: name,
+            reference: vuln["PrimaryURL"].as_str().map(|url| (id.to_string(), url.to_string())),
+            fixed_versions: version::parse_fixed_versions(fixed),
+            scheme: trivy::scheme(kind, target),
+            id: id,
+            identifiers: identifiers,
+            cvss_score: cvss_score,
+            cvss_vector: cvss_vector,
+            cwes: vuln["CweIDs"]
+                .as_array()
+                .unwrap_or(&vec![])
+                .iter()
+                .filter_map(|cwe| cwe.as_str().map(|c| c.to_string()))
+                .collect(),
+            published: vuln["PublishedDate"].as_str().unwrap_or("").to_string(),
+            affected_locations: 1,
+            ..Default::default()
+        }
+    }
+
+    /// vul of a grype `matches[]` entry
+    fn from_grype(matched: &Value) -> Self {
+        let vuln = &matched["vulnerability"];
+        let artifact = &matched["artifact"];
+        let id = vuln["id"].as_str().unwrap_or("").to_string();
+        let mut identifiers: Vec<String> = vec![id.to_string()];
+        for related in matched["relatedVulnerabilities"].as_array().unwrap_or(&vec![]) {
+            if let Some(rid) = related["id"].as_str().filter(|r| !identifiers.contains(&r.to_string())) {
+                identifiers.push(rid.to_string());
+            }
+        }
+        identifiers.retain(|i| !i.is_empty());
+        let description = vuln["description"]
+            .as_str()
+            .or(matched["relatedVulnerabilities"][0]["description"].as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        let fixed: Vec<String> = vuln["fix"]["versions"]
+            .as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .filter_map(|v| v.as_str().map(|v| v.to_string()))
+            .collect();
+        let name = artifact["name"].as_str().unwrap_or("").to_string();
+        let location = grype::location(artifact);
+        let mut scheme = Scheme::from_purl(artifact["purl"].as_str().unwrap_or(""));
+        if scheme == Scheme::Generic {
+            scheme = trivy::scheme(artifact["type"].as_str().unwrap_or(""), location.as_str());
+        }
+        let (cvss_score, cvss_vector) = match grype::cvss(matched) {
+            Some((score, vector)) => (Some(score), vector),
+            None => (None, String::new()),
+        };
+
+        SCAVul {
+            message: description.lines().next().filter(|l| !l.is_empty()).unwrap_or(id.as_str()).to_string(),
+            description: description.to_string(),
+            severity: severity_name(vuln["severity"].as_str().unwrap_or("")),
+            cve: identifiers.iter().find(|i| i.starts_with("CVE-")).cloned().unwrap_or_default(),
+            location: location,
+            solution: if fixed.is_empty() { String::new() } else { format!("Upgrade {name} to {}", fixed.join(", ")) },
+            dependency_version: artifact["version"].as_str().unwrap_or("").to_string(),
+            dependency_name: name,
+            reference: vuln["dataSource"].as_str().map(|url| (id.to_string(), url.to_string())),
+            fixed_versions: fixed,
+            scheme: scheme,
+            id: id,
+            identifiers: identifiers,
+            cvss_score: cvss_score,
+            cvss_vector: cvss_vector,
+            affected_locations: 1,
+            ..Default::default()
+        }
+    }
+
+    /// vul of a dependency-check `dependencies[].vulnerabilities[]` entry
+    fn from_depcheck(vuln: &Value, dependency: &Value) -> Self {
+        let id = vuln["name"].as_str().unwrap_or("").to_string();
+        let (name, version, purl) = depcheck::package(dependency).unwrap_or_else(|| {
+            (dependency["fileName"].as_str().unwrap_or("").to_string(), String::new(), String::new())
+        });
+        // virtual dependencies point into the manifest, e.g. `package-lock.json?minimist`
+        let fpath = dependency["filePath"].as_str().unwrap_or("");
+        let location = relative_path(fpath.split('?').next().unwrap_or(fpath));
+        let fixed = depcheck::fixed_versions(vuln);
+        let (cvss_score, cvss_vector) = match depcheck::cvss(vuln) {
+            Some((score, vector)) => (Some(score), vector),
+            None => (None, String::new()),
+        };
+        let description = vuln["description"].as_str().unwrap_or("").trim().to_string();
+        let mut scheme = Scheme::from_purl(purl.as_str());
+        if scheme == Scheme::Generic {
+            scheme = Scheme::from_file(location.as_str());
+        }
+
+        SCAVul {
+            message: description.lines().next().filter(|l| !l.is_empty()).unwrap_or(id.as_str()).to_string(),
+            description: description.to_string(),
+            severity: severity_name(vuln["severity"].as_str().unwrap_or("")),
+            cve: if id.starts_with("CVE-") { id.to_string() } else { String::new() },
+            location: location,
+            solution: if fixed.is_empty() { String::new() } else { format!("Upgrade {name} to {}", fixed.join(", ")) },
+            dependency_name: name,
+            dependency_version: version,
+            reference: vuln["references"][0]["url"].as_str().map(|url| (id.to_string(), url.to_string())),
+            fixed_versions: fixed,
+            scheme: scheme,
+            identifiers: vec![id.to_string()],
+            id: id,
+            cvss_score: cvss_score,
+            cvss_vector: cvss_vector,
+            cwes: vuln["cwes"]
+                .as_array()
+                .unwrap_or(&vec![])
+                .iter()
+                .filter_map(|cwe| cwe.as_str().filter(|c| c.starts_with("CWE-")).map(|c| c.to_string()))
+                .collect(),
+            affected_locations: 1,
+            ..Default::default()
+        }
+    }
+
+    /// vul of a npm audit v1 advisory at one of its installed versions
+    fn from_npm_advisory(advisory: &Value, version: &str) -> Self {
+        let mut identifiers: Vec<String> = advisory["cves"]
+            .as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .filter_map(|c| c.as_str().map(|c| c.to_string()))
+            .collect();
+        let ghsa = advisory["github_advisory_id"].as_str().unwrap_or("").to_string();
+        let id = if ghsa.is_empty() {
+            npm_audit::npm_id(&advisory["id"])
+                .or_else(|| advisory["title"].as_str().map(|title| title.to_string()))
+                .unwrap_or_default()
+        } else {
+            ghsa
+        };
+        identifiers.insert(0, id.to_string());
+        let name = advisory["module_name"].as_str().unwrap_or("").to_string();
+
+        SCAVul {
+            message: advisory["title"].as_str().unwrap_or(id.as_str()).to_string(),
+            description: advisory["overview"].as_str().unwrap_or("").trim().to_string(),
+            severity: severity_name(advisory["severity"].as_str().unwrap_or("")),
+            cve: identifiers.iter().find(|i| i.starts_with("CVE-")).cloned().unwrap_or_default(),
+            location: String::from("package-lock.json"),
+            solution: advisory["recommendation"].as_str().unwrap_or("").to_string(),
+            dependency_name: name,
+            dependency_version: version.to_string(),
+            reference: advisory["url"].as_str().map(|url| (id.to_string(), url.to_string())),
+            fixed_versions: version::range_lower_bounds(advisory["patched_versions"].as_str().unwrap_or("")),
+            scheme: Scheme::Npm,
+            id: id,
+            identifiers: identifiers,
+            cwes: npm_audit::cwes(&advisory["cwe"]),
+            published: advisory["created"].as_str().unwrap_or("").to_string(),
+            affected_locations: 1,
+            ..Default::default()
+        }
+    }
+
+    /// vul of a npm audit v2 `via` advisory of the package `entry`
+    fn from_npm_via(via: &Value, entry: &Value, version: &str) -> Self {
+        let id = npm_audit::advisory_id(via);
+        let name = entry["name"].as_str().or(via["name"].as_str()).unwrap_or("").to_string();
+        let mut fixed = version::range_upper_bounds(via["range"].as_str().unwrap_or(""));
+        // `fixAvailable` may name the top level package to upgrade instead
+        let fix = &entry["fixAvailable"];
+        let solution = match (fix["name"].as_str(), fix["version"].as_str()) {
+            (Some(fix_name), Some(fix_version)) => {
+                if fix_name == name && !fixed.contains(&fix_version.to_string()) {
+                    fixed.push(fix_version.to_string());
+                }
+                format!("Upgrade {fix_name} to {fix_version}")
+            }
+            _ if !fixed.is_empty() => format!("Upgrade {name} to {}", fixed.join(", ")),
+            _ => String::new(),
+        };
+        let (cvss_score, cvss_vector) = match cvss::parse(via["cvss"]["vectorString"].as_str().unwrap_or("")) {
+            Some(parsed) => (Some(parsed.score), parsed.vector),
+            None => (via["cvss"]["score"].as_f64().filter(|s| *s > 0.0), String::new()),
+        };
+
+        SCAVul {
+            message: via["title"].as_str().unwrap_or(id.as_str()).to_string(),
+            description: via["title"].as_str().unwrap_or("").to_string(),
+            severity: severity_name(via["severity"].as_str().unwrap_or("")),
+            location: String::from("package-lock.json"),
+            solution: solution,
+            dependency_name: name,
+            dependency_version: version.to_string(),
+            reference: via["url"].as_str().map(|url| (id.to_string(), url.to_string())),
+            fixed_versions: fixed,
+            scheme: Scheme::Npm,
+            identifiers: vec![id.to_string()],
+            id: id,
+            cvss_score: cvss_score,
+            cvss_vector: cvss_vector,
+            cwes: npm_audit::cwes(&via["cwe"]),
+            affected_locations: 1,
+            ..Default::default()
+        }
+    }
+
+    /// vul of a cargo audit advisory, `kind` is `vulnerability` or the warning kind
+    fn from_cargo_audit(item: &Value, kind: &str) -> Self {
+        let advisory = &item["advisory"];
+        let package = &item["package"];
+        let id = advisory["id"].as_str().unwrap_or("").to_string();
+        let mut identifiers: Vec<String> = vec![id.to_string()];
+        for alias in advisory["aliases"].as_array().unwrap_or(&vec![]) {
+            if let Some(alias) = alias.as_str().filter(|a| !identifiers.contains(&a.to_string())) {
+                identifiers.push(alias.to_string());
+            }
+        }
+        let name = package["name"].as_str().unwrap_or("").to_string();
+        let fixed: Vec<String> = item["versions"]["patched"]
+            .as_array()
+            .unwrap_or(&vec![])
+            .iter()
+            .flat_map(|range| version::range_lower_bounds(range.as_str().unwrap_or("")))
+            .collect();
+        let parsed = cvss::parse(advisory["cvss"].as_str().unwrap_or(""));
+        // rustsec has no severity, unmaintained / unsound warnings are informational
+        let severity = match (&parsed, kind) {
+            (_, kind) if kind != "vulnerability" => "Info",
+            (Some(parsed), _) => cvss::severity(parsed.score),
+            (None, _) => "Unknown",
+        };
+
+        SCAVul {
+            message: advisory["title"].as_str().unwrap_or(id.as_str()).to_string(),
+            description: advisory["description"].as_str().unwrap_or("").trim().to_string(),
+            severity: severity.to_string(),
+            cve: identifiers.iter().find(|i| i.starts_with("CVE-")).cloned().unwrap_or_default(),
+            location: String::from("Cargo.lock"),
+            solution: if fixed.is_empty() { String::new() } else { format!("Upgrade {name} to {}", fixed.join(", ")) },
+            dependency_name: name,
+            dependency_version: package["version"].as_str().unwrap_or("").to_string(),
+            reference: Some((id.to_string(), cargo_audit::url(advisory))),
+            fixed_versions: fixed,
+            scheme: Scheme::Cargo,
+            id: id,
+            identifiers: identifiers,
+            cvss_score: parsed.as_ref().map(|p| p.score),
+            cvss_vector: parsed.map(|p| p.vector).unwrap_or_default(),
+            published: advisory["date"].as_str().unwrap_or("").to_string(),
+            affected_locations: 1,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Finding> for SCAVul {
+    fn from(finding: &Finding) -> Self {
+        let (name, version) = finding.package();
+        let id = finding.identifiers.first().cloned().unwrap_or_default();
+
+        SCAVul {
+            message: finding.name.to_string(),
+            description: finding.description.to_string(),
+            severity: severity_name(finding.severity.as_str()),
+            cve: finding.identifiers.iter().find(|i| i.starts_with("CVE-")).cloned().unwrap_or_default(),
+            location: finding.file.to_string(),
+            dependency_name: name,
+            dependency_version: version,
+            id: id,
+            identifiers: finding.identifiers.clone(),
+            scheme: Scheme::from_file(finding.file.as_str()),
+            kev: finding.kev,
+            affected_locations: 1,
+            fingerprint: finding.fingerprint.to_string(),
+            ..Default::default()
+        }
+    }
 }
 
 impl From<&Value> for SCAVul {
@@ -96,11 +626,29 @@ impl From<&Value> for SCAVul {
             .unwrap()
             .to_owned();
 
+        let mut ids: Vec<String> = vec![];
         if let Some(identifiers) = obj["identifiers"].as_array() {
             for identifier in identifiers {
+                if let Some(name) = identifier["name"].as_str() {
+                    ids.push(name.to_string());
+                }
                 if let Some("cve") = identifier["type"].as_str() {
-                    cve = identifier["name"].to_string().replace("\"", "");
-                    break;
+                    if cve.is_empty() {
+                        cve = identifier["name"].to_string().replace("\"", "");
+                    }
+                }
+            }
+        }
+        let id = obj["id"].as_str().or(obj["cve"].as_str()).unwrap_or("").to_string();
+        let fixed_versions = version::parse_fixed_versions(solution.as_str());
+        let scheme = Scheme::from_file(location.as_str());
+        let mut cvss_score: Option<f64> = None;
+        let mut cvss_vector = String::new();
+        for item in obj["cvss_vectors"].as_array().unwrap_or(&vec![]) {
+            if let Some(parsed) = cvss::parse(item["vector"].as_str().unwrap_or("")) {
+                if cvss_score.map_or(true, |score| parsed.score > score) {
+                    cvss_score = Some(parsed.score);
+                    cvss_vector = parsed.vector;
                 }
             }
         }
@@ -114,6 +662,21 @@ impl From<&Value> for SCAVul {
             solution: solution,
             dependency_name: dependency_name,
             dependency_version: dependency_version,
+            reference: None,
+            id: id,
+            identifiers: ids,
+            fixed_versions: fixed_versions,
+            scheme: scheme,
+            cvss_score: cvss_score,
+            cvss_vector: cvss_vector,
+            cwes: vec![],
+            published: String::new(),
+            epss: None,
+            kev: false,
+            priority: 0.0,
+            dependency_path: vec![],
+            affected_locations: 1,
+            fingerprint: String::new(),
         }
     }
 }
@@ -123,8 +686,8 @@ impl SCAReport {
         SCAReport {
             vuls: vec![],
             merged_vuls: vec![],
-            merged_vuls_map: HashMap::new(),
-            cached_vuls_map: HashMap::new(),
+            merged_vuls_map: vec![],
+            sbom: Bom::default(),
             engine: String::from("SCA"),
         }
     }
@@ -146,7 +709,7 @@ impl SCAReport {
         let sz = self.vuls.len();
         let mut idx = 1;
         for vul in self.vuls.iter_mut() {
-            let body = llm::ask_ai(format!("{}, 简化但不丢失这句话的意思，要求输出内容长度最大化缩小, 输出内容长度必须小于原输入内容长度", vul.description).as_str()).await.unwrap();
+            let body = llm::ask_ai(i18n::tf("llm.simplify", &[("content", vul.description.as_str())]).as_str()).await.unwrap();
             let resp: Value = serde_json::from_str(body.as_str()).unwrap();
             let desc: String = resp["choices"][0]["message"]["content"]
                 .as_str()
@@ -172,41 +735,352 @@ impl SCAReport {
         }
     }
 
-    pub fn group_issue_local(self: &mut Self) -> HashMap<String, Vec<SCAVul>> { 
-        println!("group_issue_local调用");
-        let mut cached_result: HashMap<String, HashMap<String, SCAVul>> = HashMap::new();
-        let mut result: HashMap<String, Vec<SCAVul>> = HashMap::new();
-        for vul in self.vuls.iter() {
-            // 获取原始vul信息
-            let key = vul.location.to_string();
-            if let Some(group) = cached_result.get_mut(key.as_str()) {
-                // 获取location分组中的map
-                if let Some(cached_vul) = group.get(vul.dependency_name.as_str()) {
+    /// offline enrichment from the local osv / nvd dump at `CVE_DB_PATH`, skipped when unset
+    pub fn enrich(self: &mut Self) {
+        if CVE_DB_PATH.is_empty() {
+            return;
+        }
+        let db = match CveDb::open(CVE_DB_PATH.as_str(), CVE_CACHE_DIR.as_str()) {
+            Ok(db) => db,
+            Err(e) => {
+                warn!("cve db not available, skip enrichment: {e}");
+                return;
+            }
+        };
+
+        let mut hits = 0;
+        for vul in self.vuls.iter_mut() {
+            let mut ids: Vec<String> = vec![vul.cve.to_string(), vul.id.to_string()];
+            ids.extend(vul.identifiers.iter().cloned());
+            ids.retain(|id| !id.is_empty());
+            if let Some(record) = db.lookup(&ids) {
+                vul.enrich(&record);
+                hits += 1;
+            }
+        }
+        info!("cve db enriched {hits}/{} vuls", self.vuls.len());
+    }
+
+    /// epss score, kev listing and priority of every vul, from `EPSS_PATH` / `KEV_PATH`
+    pub fn prioritize(self: &mut Self) {
+        let intel = if EPSS_PATH.is_empty() && KEV_PATH.is_empty() {
+            ThreatIntel::default()
+        } else {
+            ThreatIntel::load(EPSS_PATH.as_str(), KEV_PATH.as_str())
+        };
+
+        for vul in self.vuls.iter_mut() {
+            let mut cves: Vec<&String> = vec![&vul.cve];
+            cves.extend(vul.identifiers.iter());
+            cves.retain(|id| id.starts_with("CVE-"));
+            for cve in cves {
+                if let Some(epss) = intel.epss(cve) {
+                    vul.epss = Some(vul.epss.map_or(epss.score, |score| score.max(epss.score)));
+                }
+                if let Some(entry) = intel.kev(cve) {
+                    debug!("kev listed: {} added {}", entry.cve, entry.date_added);
+                    vul.kev = true;
+                }
+            }
+            vul.priority = threat::priority(vul.cvss_score, vul.severity.as_str(), vul.epss, vul.kev);
+        }
+    }
+
+    /// shortest dependency path of every vul from the dependency_files and sbom graphs
+    pub fn resolve_paths(self: &mut Self) {
+        let parents = self.sbom.parents();
+        let mut resolved = 0;
+        for vul in self.vuls.iter_mut() {
+            let Some(component) = self
+                .sbom
+                .find(vul.location.as_str(), vul.dependency_name.as_str(), vul.dependency_version.as_str())
+            else {
+                continue;
+            };
+            vul.dependency_path = self
+                .sbom
+                .shortest_path(&parents, component.bom_ref.as_str())
+                .iter()
+                .filter_map(|r| self.sbom.component(r))
+                .map(|c| format!("{}@{}", c.package_name(), c.version))
+                .collect();
+            if vul.dependency_path.len() > 1 {
+                resolved += 1;
+            }
+        }
+        info!("transitive dependency paths: {resolved}/{}", self.vuls.len());
+    }
+
+    /// components, dependency graph and vulnerabilities of a cyclonedx sbom
+    pub fn parse_cyclonedx(self: &mut Self, doc: &Value) -> &Vec<SCAVul> {
+        let bom = Bom::parse(doc);
+        for vuln in bom.vulnerabilities.iter() {
+            for affected in vuln["affects"].as_array().unwrap_or(&vec![]) {
+                let Some(component) = affected["ref"].as_str().and_then(|r| bom.component(r)) else {
+                    warn!("unknown bom-ref: {}", affected["ref"]);
                     continue;
-                } else {
-                    let mut new_vul = self.cached_vuls_map.get(vul.dependency_name.as_str()).unwrap().clone();
-                    new_vul.location = vul.location.to_string();
-                    group.insert(vul.dependency_name.to_string(), new_vul.clone());
+                };
+                self.push_unique(SCAVul::from_cyclonedx(vuln, component));
+            }
+        }
+        self.sbom.merge(bom);
+
+        &self.vuls
+    }
+
+    // the same finding may come from several inputs (gitlab report, sbom, trivy, grype)
+    fn push_unique(self: &mut Self, vul: SCAVul) {
+        let known = self.vuls.iter().any(|v| {
+            v.location == vul.location
+                && v.dependency_name == vul.dependency_name
+                && v.dependency_version == vul.dependency_version
+                && v.identifiers.iter().any(|i| vul.identifiers.contains(i))
+        });
+        if !known {
+            self.vuls.push(vul);
+        }
+    }
+
+    /// vulnerabilities of a trivy report, language and os packages
+    pub fn parse_trivy(self: &mut Self, report: &Value) -> &Vec<SCAVul> {
+        for result in report["Results"].as_array().unwrap_or(&vec![]) {
+            let target = result["Target"].as_str().unwrap_or("");
+            let kind = result["Type"].as_str().unwrap_or("");
+            for vuln in result["Vulnerabilities"].as_array().unwrap_or(&vec![]) {
+                self.push_unique(SCAVul::from_trivy(vuln, target, kind));
+            }
+        }
+
+        &self.vuls
+    }
+
+    /// vulnerabilities of a grype report
+    pub fn parse_grype(self: &mut Self, report: &Value) -> &Vec<SCAVul> {
+        for matched in report["matches"].as_array().unwrap_or(&vec![]) {
+            self.push_unique(SCAVul::from_grype(matched));
+        }
+
+        &self.vuls
+    }
+
+    /// vulnerabilities of a dependency-check report, the xml report is converted by [`depcheck::from_xml`]
+    pub fn parse_depcheck(self: &mut Self, report: &Value) -> &Vec<SCAVul> {
+        for dependency in report["dependencies"].as_array().unwrap_or(&vec![]) {
+            for vuln in dependency["vulnerabilities"].as_array().unwrap_or(&vec![]) {
+                self.push_unique(SCAVul::from_depcheck(vuln, dependency));
+            }
+        }
+
+        &self.vuls
+    }
+
+    /// vulnerabilities of a npm audit report, v2 versions are read from the `package-lock.json` in `dir`
+    pub fn parse_npm_audit(self: &mut Self, report: &Value, dir: &str) -> &Vec<SCAVul> {
+        if !npm_audit::is_v2(report) {
+            for advisory in report["advisories"].as_object().into_iter().flat_map(|m| m.values()) {
+                for finding in advisory["findings"].as_array().unwrap_or(&vec![]) {
+                    let version = finding["version"].as_str().unwrap_or("");
+                    self.push_unique(SCAVul::from_npm_advisory(advisory, version));
                 }
+            }
+            return &self.vuls;
+        }
+
+        let lock = npm_audit::lockfile(dir);
+        for entry in report["vulnerabilities"].as_object().into_iter().flat_map(|m| m.values()) {
+            let mut versions = npm_audit::installed_versions(entry, &lock);
+            if versions.is_empty() {
+                versions.push(String::new());
+            }
+            // string `via` entries name the vulnerable dependency, its own entry carries the advisory
+            for via in entry["via"].as_array().unwrap_or(&vec![]).iter().filter(|v| v.is_object()) {
+                for version in versions.iter() {
+                    self.push_unique(SCAVul::from_npm_via(via, entry, version.as_str()));
+                }
+            }
+        }
+
+        &self.vuls
+    }
+
+    /// vulnerabilities and advisory warnings of a cargo audit report
+    pub fn parse_cargo_audit(self: &mut Self, report: &Value) -> &Vec<SCAVul> {
+        for (kind, item) in cargo_audit::advisories(report) {
+            self.push_unique(SCAVul::from_cargo_audit(item, kind));
+        }
+
+        &self.vuls
+    }
+
+    // bom-ref of the vulnerable dependency, added to the sbom when no input listed it
+    fn component_ref(sbom: &mut Bom, vul: &SCAVul) -> String {
+        match sbom.find(vul.location.as_str(), vul.dependency_name.as_str(), vul.dependency_version.as_str()) {
+            Some(component) => component.bom_ref.to_string(),
+            None => sbom.add_component(Component {
+                name: vul.dependency_name.to_string(),
+                version: vul.dependency_version.to_string(),
+                purl: cyclonedx::purl(vul.scheme, vul.dependency_name.as_str(), vul.dependency_version.as_str()),
+                location: vul.location.to_string(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// merged cyclonedx sbom of every input with the vuls attached as vex entries
+    pub fn write_sbom(self: &mut Self, fpath: &str) -> bool {
+        let mut vex: Vec<Vex> = vec![];
+        for vul in self.vuls.iter() {
+            let bom_ref = SCAReport::component_ref(&mut self.sbom, vul);
+            let id = if vul.cve.is_empty() {
+                vul.identifiers.first().unwrap_or(&vul.id).to_string()
             } else {
-                let mut group: HashMap<String, SCAVul> = HashMap::new();
-                let mut new_vul = self.cached_vuls_map.get(vul.dependency_name.as_str()).unwrap().clone();
-                new_vul.location = vul.location.to_string();
-                group.insert(vul.dependency_name.to_string(), new_vul.clone());
-                cached_result.insert(vul.location.to_string(), group);
+                vul.cve.to_string()
+            };
+            if let Some(known) = vex.iter_mut().find(|v| v.id == id) {
+                if !known.refs.contains(&bom_ref) {
+                    known.refs.push(bom_ref);
+                }
+                continue;
             }
+
+            let mut properties: Vec<(String, String)> = vec![
+                ("sec_engine:priority".to_string(), format!("{:.1}", vul.priority)),
+            ];
+            if let Some(epss) = vul.epss {
+                properties.push(("sec_engine:epss".to_string(), epss.to_string()));
+            }
+            if vul.kev {
+                properties.push(("sec_engine:kev".to_string(), "true".to_string()));
+            }
+            vex.push(Vex {
+                source_url: if id.starts_with("CVE-") {
+                    format!("https://nvd.nist.gov/vuln/detail/{id}")
+                } else {
+                    String::new()
+                },
+                id: id,
+                description: vul.description.to_string(),
+                recommendation: vul.solution.to_string(),
+                severity: vul.severity.to_string(),
+                cvss_score: vul.cvss_score,
+                cvss_vector: vul.cvss_vector.to_string(),
+                cwes: vul.cwes.clone(),
+                refs: vec![bom_ref],
+                properties: properties,
+            });
         }
-        
 
-        for (k, cached_map) in cached_result.iter() {
-            let mut vec: Vec<SCAVul> = cached_map.values().map(|v|v.clone()).collect();
-            vec.sort_by(|a, b| a.dependency_name.cmp(&b.dependency_name));
-            result.insert(k.to_string(), vec);
+        let serial = format!("{}/{}/{}", CI_PROJECT_ID.as_str(), CI_PIPELINE_ID.as_str(), CI_COMMIT_SHA.as_str());
+        let doc = self.sbom.to_value(&vex, CI_PROJECT_URL.as_str(), serial.as_str());
+        match std::fs::write(fpath, serde_json::to_string_pretty(&doc).unwrap_or_default()) {
+            Ok(_) => {
+                info!("sbom written: {fpath}, {} components, {} vex", self.sbom.components.len(), vex.len());
+                true
+            }
+            Err(e) => {
+                error!("write sbom failed: {fpath}, {e}");
+                false
+            }
+        }
+    }
+
+    /// spdx 2.3 json document of the dependencies, namespace and creators from the ci settings
+    pub fn write_spdx(self: &mut Self, fpath: &str) -> bool {
+        for vul in self.vuls.iter() {
+            SCAReport::component_ref(&mut self.sbom, vul);
         }
 
-        self.merged_vuls_map.extend(result.clone());
+        let project = [CI_PROJECT_PATH.as_str(), CI_PROJECT_ID.as_str(), "project"]
+            .into_iter()
+            .find(|p| !p.is_empty())
+            .unwrap_or_default()
+            .to_string();
+        let base = if CI_PROJECT_URL.is_empty() {
+            String::from("https://spdx.org/spdxdocs")
+        } else {
+            format!("{}/-/sbom", CI_PROJECT_URL.as_str())
+        };
+        let seed = format!("{}/{}/{}", CI_PROJECT_ID.as_str(), CI_PIPELINE_ID.as_str(), CI_COMMIT_SHA.as_str());
+        let namespace = format!(
+            "{base}/{}-{}",
+            project.replace('/', "-"),
+            cyclonedx::uuid(seed.as_str())
+        );
+        let mut creators: Vec<String> = vec![format!(
+            "Tool: {}-{}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        )];
+        if !CI_PROJECT_NAMESPACE.is_empty() {
+            creators.push(format!("Organization: {}", CI_PROJECT_NAMESPACE.as_str()));
+        }
+        if !SPDX_CREATOR.is_empty() {
+            creators.push(SPDX_CREATOR.to_string());
+        }
 
-        result
+        let doc = spdx_export::document(&self.sbom, project.as_str(), namespace.as_str(), &creators);
+        match std::fs::write(fpath, serde_json::to_string_pretty(&doc).unwrap_or_default()) {
+            Ok(_) => {
+                info!("spdx written: {fpath}, {} packages", self.sbom.components.len());
+                true
+            }
+            Err(e) => {
+                error!("write spdx failed: {fpath}, {e}");
+                false
+            }
+        }
+    }
+
+    /// lowest version fixing every vul of the dependency (same manifest, name and version)
+    pub fn safe_version(self: &Self, vul: &SCAVul) -> Option<String> {
+        let mut seen: Vec<&str> = vec![];
+        let mut fixes: Vec<Vec<String>> = vec![];
+        for other in self.vuls.iter() {
+            if other.location != vul.location
+                || other.dependency_name != vul.dependency_name
+                || other.dependency_version != vul.dependency_version
+            {
+                continue;
+            }
+            // the same advisory may be reported by several analyzers
+            let key = other.identifiers.first().unwrap_or(&other.id).as_str();
+            if !key.is_empty() && seen.contains(&key) {
+                continue;
+            }
+            seen.push(key);
+            fixes.push(other.fixed_versions.clone());
+        }
+
+        version::minimal_safe_version(vul.scheme, vul.dependency_version.as_str(), &fixes)
+    }
+
+    /// one upgrade per fixable dependency (manifest, name, version), with the cves it fixes
+    pub fn upgrades(self: &Self) -> Vec<Upgrade> {
+        let mut upgrades: Vec<Upgrade> = vec![];
+        for vul in self.vuls.iter() {
+            let existing = upgrades.iter_mut().find(|u| {
+                u.location == vul.location && u.name == vul.dependency_name && u.from == vul.dependency_version
+            });
+            if let Some(upgrade) = existing {
+                if !vul.cve.is_empty() && !upgrade.cves.contains(&vul.cve) {
+                    upgrade.cves.push(vul.cve.to_string());
+                }
+                continue;
+            }
+            let Some(to) = self.safe_version(vul) else {
+                continue;
+            };
+            upgrades.push(Upgrade {
+                location: vul.location.to_string(),
+                name: vul.dependency_name.to_string(),
+                from: vul.dependency_version.to_string(),
+                to: to,
+                scheme: vul.scheme,
+                cves: if vul.cve.is_empty() { vec![] } else { vec![vul.cve.to_string()] },
+            });
+        }
+
+        upgrades
     }
 
     pub fn group_issue(self: &mut Self) -> HashMap<String, Vec<SCAVul>> {
@@ -226,252 +1100,331 @@ impl SCAReport {
         result
     }
 
-    fn get_prompt(self: &Self, content: &str) -> String {
-        // let prompt = "
-        //     目的：合并优化text标签中的内容，
-        //     输入格式: 每一行均为|dependency|risk|cve|location|solution|description|，代表组件风险以及整改方案
-        //     输出格式: 参照表头|dependency|risk|cve|location|solution|description|进行数据填充
-        //     输出要求：
-        //     1. 每个dependency字段只允许在markdown表格中出现一条记录
-        //     2. 内容简单扼要
-        //     3. 必须保持示例中输出内容格式
-        //     4. 不要额外添加信息
-        //     5. 尽可能保持语言简洁，内容不要过长
-        //     6. 不需要输出表头
-        //     7. dependency字段保持完整，禁止对dependency删减修改
-
-        //     步骤：
-        //     1. 从上到下逐行读取
-        //     2. 每行解析组件风险，根据dependency字段检查是否为已记录信息，若无记录，则记录相关信息并跳转1执行
-        //     3. 若通过dependency查询到为已记录信息，将其中的关键内容（风险(description)与解决方案(solution)）进行合并，并将合并的结果更新到已有的记录里
-        //     4. 检查是否已经完全处理所有行，若无，跳到1继续
-        //     5. 整理以上内容
-        //     6. 反思以上前面步骤生成的内容是否符合要求，如不符合则进行改进再进行输出
-
-        //     示例如下：
-        //     输入
-        //     |net.minidev/json-smart:1.0|critical|cve-1|pom.xml|upgrade to 1.2|sql inject|
-        //     |net.minidev/json-smart:1.0|medium|cve-2|pom.xml|upgrade to 1.3|dos|
-        //     |dep2:v1.2|medium|cve-3|pom.xml|upgrade to 1.3|dos|
-
-        //     输出
-        //     |net.minidev/json-smart:1.0|critical|cve-2、cve-1|pom.xml|upgrade to 1.3|exist sql inject、dos|
-        //     |dep2:v1.2|medium|cve-3|pom.xml|upgrade to 1.3|dos|
-
-        // ";
-        let prompt = "
-                目的：合并优化text标签中的内容，将同一个组件中的不同风险等级合并，原则为低风险合并到高风险中,修复方案如果有版本高低,原则上保留更新的版本. 操作前需清空上下文
-                步骤：
-                1. markdown表格输出记录命名为output_table
-                2. 自上而下逐行读取输入内容记录，记为org_record
-                3. 解析org_record的组件风险，记为pro_record，记录dependency字段
-                4. 检查output_table中是否有与pro_record相同的dependency记录。
-                - 若无，将pro_record记录加入output_table，跳到步骤1
-                5. 若有，将output_table中的记录记为mk_record
-                - 合并pro_record与mk_record中的风险描述(description)和解决方案(solution)，更新mk_record
-                - 若有多个risk记录，将低风险合并到高风险，新的记录添加到output_table
-                6. 检查所有行是否处理完毕，若未完毕，跳到步骤1
-                7. 整理合并结果为markdown表格
-                8. 输出前检查markdown表格记录，确保每个dependency字段唯一
-                - 若有多条记录，将生成的markdown记录作为新输入，清空上下文后跳到步骤1再次合并
-
-                输入格式: 每行均为markdown表格记录，格式为|dependency|risk|cve|location|solution|description|, 其中risk代表风险等级
-                输出格式: 参照表头|dependency|risk|cve|location|solution|description|格式填充数据, solution和description尽量翻译为中文
-                输出约束：
-                1. 每个dependency字段只允许在markdown表格中出现一条记录
-                2. 内容简单扼要
-                3. 必须保持示例中输出内容格式
-                4. 不要额外添加信息
-                5. 尽可能保持语言简洁，内容不要过长
-                6. 不需要输出表头
-                7. dependency字段保持完整，禁止对dependency删减修改
-
-                示例输入:
-                |net.minidev/json-smart:1.0|critical|cve-1|pom.xml|upgrade to 1.2|sql inject|
-                |net.minidev/json-smart:1.0|medium|cve-2|pom.xml|upgrade to 1.3|dos|
-                |dep2:v1.2|medium|cve-3|pom.xml|upgrade to 1.3|dos|
-
-                示例输出:
-                |net.minidev/json-smart:1.0|critical|cve-2、cve-1|pom.xml|upgrade to 1.3|exist sql inject、dos|
-                |dep2:v1.2|medium|cve-3|pom.xml|upgrade to 1.3|dos|
-            ";
-        format!("<text>{content}</text>, {}", prompt)
-    }
-
-    pub fn merge_issues_local(self: &mut Self) -> HashMap<String, Vec<SCAVul>>{
-        println!("merge_issues_local...");
-        let issues_map: HashMap<String, Vec<SCAVul>>= HashMap::new();
-        let mut cached_issues_map: HashMap<String, SCAVul>= HashMap::new();
+    /// deterministic merge of the vuls of each dependency (manifest, name, version) into one row
+    ///
+    /// the row keeps the highest severity, every cve ordered by cvss, the minimal safe upgrade
+    /// (or the union of the solutions) and the number of manifests using the same package version
+    pub fn aggregate(self: &mut Self) -> Vec<(String, Vec<SCAVul>)> {
+        let mut keys: Vec<(String, String, String)> = vec![];
+        let mut groups: HashMap<(String, String, String), Vec<&SCAVul>> = HashMap::new();
+        let mut manifests: HashMap<(String, String), HashSet<String>> = HashMap::new();
         for vul in self.vuls.iter() {
-            println!("{}", String::from("-").repeat(0x32));
-            let mut new_vul;
-            if let Some(cached_vul)= cached_issues_map.get(vul.dependency_name.as_str()) {
-                // 已有key，更新合并
-                println!("发现已有缓存组件：{}({}), risk: {}", cached_vul.dependency_name.to_string(), cached_vul.get_risk_num(), cached_vul.get_risk_cn());
-                println!("待合并处理组件：{}({}), detail: {}", vul.get_risk_cn(), vul.get_risk_num(), vul.to_issue_record());
-                let mut cve = cached_vul.cve.to_string();
-                new_vul = cached_vul.clone();
-
-                if vul.get_risk_num() >= cached_vul.get_risk_num() { 
-                    // println!("{}, {}", cached_vul.to_issue_record(), vul.to_issue_record());
-                    println!("发现更高风险，更新risk等级:{}({}) -> {}({})", cached_vul.get_risk_cn(), cached_vul.get_risk_num(), vul.get_risk_cn(), vul.get_risk_num());
-                    new_vul.severity = vul.severity.to_string();
-                    let mut cached_cves: Vec<String> = cached_vul.cve.to_string().split(",").map(|x|x.to_string()).collect();
-
-                    if !vul.cve.is_empty() { 
-                        println!("更新cve: {}; {}", vul.cve, cached_vul.cve);
-                        match vul.cve.cmp(&cached_cves[0]) {
-                            Ordering::Greater => {
-                                let solution = vul.solution.to_string();
-                                if let Some(_solution) = solution.get(19..solution.len()-9) {
-                                    println!("更新解决方案: {}", _solution.trim());
-                                    new_vul.solution = format!("建议升级至{}或更高版本", _solution.trim().to_string());
-                                }
-                                cached_cves.insert(0, vul.cve.to_string());
-                            },
-                            Ordering::Equal=> {
-                                println!("重复组件跳过, 忽略合并...");
-                                continue;
-                            },
-                            _ => {
-                                cached_cves.push(vul.cve.to_string());
-                            },
-                        } 
-                    } 
-                    if cached_cves.len() > 3 {
-                        cached_cves.sort();
-                        println!("cve数量过多，裁剪至3个");
-                        cve = cached_cves[0..3].join(",");
-                    } else {
-                        cve = cached_cves.join(",");
-                    } 
-                    new_vul.cve = cve;
-                    // new_vul.description = format!("{}风险组件, 详情可查看", vul.get_risk_cn());
-                    new_vul.description =  format!("{}风险组件, 详情可查看[{}](https://avd.aliyun.com/detail/{}/)", vul.get_risk_cn(), vul.cve, vul.cve);
-                }                 
-            } else {
-                println!("插入新组件: {}, risk: {}, cve: {}", vul.dependency_name, vul.get_risk_cn(), vul.cve);
-                let mut solution = String::from("升级到稳定修复版本");
-                if let Some(_solution) = vul.solution.get(19..vul.solution.len()-9) {
-                    println!("更新解决方案: {}", _solution.trim());
-                    solution = format!("建议升级至{}或更高版本", _solution.trim().to_string());
-                }
-                // map中不存在key
-                new_vul = SCAVul{
-                    message: vul.message.to_string(),
-                    description: format!("{}风险组件, 详情可查看[{}](https://www.cvedetails.com/cve/{}/)", vul.get_risk_cn(), vul.cve, vul.cve),
-                    severity: vul.severity.to_string(),
-                    cve: vul.cve.to_string(),
-                    location: String::from("位置预留，导出报告时填充"),
-                    solution: solution,
-                    dependency_name: vul.dependency_name.to_string(),
-                    dependency_version: vul.dependency_version.to_string(),
+            let key = (
+                vul.location.to_string(),
+                vul.dependency_name.to_string(),
+                vul.dependency_version.to_string(),
+            );
+            if !groups.contains_key(&key) {
+                keys.push(key.clone());
+            }
+            groups.entry(key).or_default().push(vul);
+            manifests
+                .entry((vul.dependency_name.to_string(), vul.dependency_version.to_string()))
+                .or_default()
+                .insert(vul.location.to_string());
+        }
+
+        let mut result: Vec<(String, Vec<SCAVul>)> = vec![];
+        for key in keys.iter() {
+            let mut vuls = groups.get(key).unwrap().clone();
+            vuls.sort_by(|a, b| {
+                b.cvss_score
+                    .unwrap_or(-1.0)
+                    .total_cmp(&a.cvss_score.unwrap_or(-1.0))
+                    .then(b.get_risk_num().cmp(&a.get_risk_num()))
+                    .then(a.cve.cmp(&b.cve))
+            });
+            let top = vuls
+                .iter()
+                .max_by_key(|vul| vul.get_risk_num())
+                .unwrap();
+
+            let mut cves: Vec<String> = vec![];
+            let mut names: Vec<String> = vec![];
+            let mut solutions: Vec<String> = vec![];
+            let mut identifiers: Vec<String> = vec![];
+            let mut cwes: Vec<String> = vec![];
+            for vul in vuls.iter() {
+                let cve = if vul.cve.is_empty() {
+                    vul.identifiers.first().unwrap_or(&vul.id).to_string()
+                } else {
+                    vul.cve.to_string()
                 };
+                for (list, value) in [
+                    (&mut cves, cve),
+                    (&mut names, vul.message.to_string()),
+                    (&mut solutions, vul.solution.trim().to_string()),
+                ] {
+                    if !value.is_empty() && !list.contains(&value) {
+                        list.push(value);
+                    }
+                }
+                for id in vul.identifiers.iter() {
+                    if !identifiers.contains(id) {
+                        identifiers.push(id.to_string());
+                    }
+                }
+                for cwe in vul.cwes.iter() {
+                    if !cwes.contains(cwe) {
+                        cwes.push(cwe.to_string());
+                    }
+                }
+            }
+
+            let solution = match self.safe_version(top) {
+                Some(version) => i18n::tf("sca.solution.upgrade", &[("version", version.as_str())]),
+                None if solutions.is_empty() => i18n::t("sca.solution.default"),
+                None => solutions.join("; "),
+            };
+            let reference = cves
+                .first()
+                .filter(|cve| cve.starts_with("CVE-"))
+                .map(|cve| (cve.to_string(), format!("https://www.cvedetails.com/cve/{cve}/")));
+
+            let row = SCAVul {
+                severity: top.severity.to_string(),
+                cve: cves.join(", "),
+                description: names.join("; "),
+                solution: solution,
+                reference: reference,
+                identifiers: identifiers,
+                cvss_score: vuls.iter().filter_map(|vul| vul.cvss_score).reduce(f64::max),
+                cvss_vector: vuls.first().map(|vul| vul.cvss_vector.to_string()).unwrap_or_default(),
+                cwes: cwes,
+                published: vuls.first().map(|vul| vul.published.to_string()).unwrap_or_default(),
+                epss: vuls.iter().filter_map(|vul| vul.epss).reduce(f64::max),
+                kev: vuls.iter().any(|vul| vul.kev),
+                priority: vuls.iter().map(|vul| vul.priority).fold(0.0, f64::max),
+                affected_locations: manifests
+                    .get(&(key.1.to_string(), key.2.to_string()))
+                    .map(|m| m.len())
+                    .unwrap_or(1),
+                ..(*top).clone()
+            };
+            match result.iter_mut().find(|(location, _)| *location == key.0) {
+                Some((_, rows)) => rows.push(row),
+                None => result.push((key.0.to_string(), vec![row])),
             }
-            cached_issues_map.insert(vul.dependency_name.to_string(), new_vul.clone());
         }
 
-        for (dep, vul) in cached_issues_map.iter() {
-            println!("{}", vul.to_issue_record());
+        for (_, rows) in result.iter_mut() {
+            rows.sort_by(|a, b| {
+                b.kev
+                    .cmp(&a.kev)
+                    .then(b.priority.total_cmp(&a.priority))
+                    .then(b.get_risk_num().cmp(&a.get_risk_num()))
+                    .then(a.dependency_name.cmp(&b.dependency_name))
+            });
         }
-        self.cached_vuls_map.extend(cached_issues_map.clone());
 
-        issues_map
-    }
+        self.merged_vuls = result.iter().flat_map(|(_, rows)| rows.iter()).cloned().collect();
+        self.merged_vuls_map = result.clone();
 
-    pub async fn merge_issues(
-        self: &mut Self,
-        vulMap: HashMap<String, Vec<SCAVul>>,
-    ) -> HashMap<String, Vec<SCAVul>> {
-        let mut result: HashMap<String, Vec<SCAVul>> = HashMap::new();
-        let mut risk_cache_issues: HashMap<String, String> = HashMap::new();
-        debug!("merge_issues....");
-        for (location, vuls) in vulMap {
-            let tmp: Vec<&str> = location.split("/").collect();
-            let module: &str = tmp[0];
-            let mut tmp_vuls: HashMap<String, Vec<SCAVul>> = HashMap::new();
+        result
+    }
 
-            println!(
-                "----------------------------------{}------------------------------------------",
-                location
-            );
-            // 合并为完整信息并提交ai合并
-            let mut issue: String = String::new();
-            let mut _issues = vec![];
-            for vul in vuls.iter() {
-                // let key = format!("{}:{}", vul.dependency_name, vul.dependency_version);
-                // issue = format!("{issue}\n{}", vul.to_issue_record());
-                _issues.push(vul.to_issue_record());
-            }
-            _issues.sort();
-            issue = _issues.join("\n");
-
-            let content: String = self.get_prompt(issue.as_str());
-
-            let mut resp: String = llm::ask_hg_ai_new(content.as_str()).await.unwrap();
-            resp = llm::ask_hg_ai_new(
-                self.get_prompt(resp.trim_matches('"').replace("\\n", "\n").as_str())
-                    .as_str(),
-            )
-            .await
-            .unwrap();  //// 仅调用一次AI
-            debug!("input: {content}");
-            println!("resp: {resp}");
-
-            let mut merged_vuls: Vec<SCAVul> = vec![];
-            for line in resp.trim_matches('"').split(r"\n") {
-                // let [dependency, severity, cve, location, solution, description]: [&str; 6] = line.trim_matches('|').split("|").collect::<Vec<&str>>().as_slice().try_into().unwrap();
-                let [_, dependency, severity, cve, location, solution, description, _]: [&str; 8] =
-                    line.split("|")
-                        .collect::<Vec<&str>>()
-                        .as_slice()
-                        .try_into()
-                        .unwrap();
-                // println!("{dependency}; {severity}; {cve}; {location}; {solution}; {description}");
-
-                let [dependency_name, dependency_version] = dependency
-                    .split(":")
-                    .collect::<Vec<&str>>()
-                    .as_slice()
-                    .try_into()
-                    .unwrap();
-                let _vul = SCAVul {
-                    message: String::new(),
-                    description: description.to_owned(),
-                    severity: severity.to_owned(),
-                    cve: cve.to_owned(),
-                    location: location.to_owned(),
-                    solution: solution.to_owned(),
-                    dependency_name: dependency_name.to_owned(),
-                    dependency_version: dependency_version.to_owned(),
+    /// optional llm pass shortening the aggregated descriptions, failures keep the original text
+    pub async fn decorate(self: &mut Self) {
+        for (_, rows) in self.merged_vuls_map.iter_mut() {
+            for row in rows.iter_mut() {
+                let prompt = i18n::tf("llm.simplify", &[("content", row.description.as_str())]);
+                let Ok(body) = llm::ask_ai(prompt.as_str()).await else {
+                    warn!("llm not available, keep original description");
+                    return;
                 };
-                merged_vuls.push(_vul.clone());
-                self.merged_vuls.push(_vul.clone());
+                let resp: Value = serde_json::from_str(body.as_str()).unwrap_or_default();
+                let desc = resp["response"]
+                    .as_str()
+                    .or(resp["choices"][0]["message"]["content"].as_str())
+                    .unwrap_or("")
+                    .trim()
+                    .to_string();
+                if !desc.is_empty() && desc.len() < row.description.len() {
+                    row.description = desc;
+                }
             }
-            result.insert(location.to_owned(), merged_vuls.clone());
-            self.merged_vuls_map
-                .insert(location.to_owned(), merged_vuls.clone());
-
-            debug!("-----------------------------------------------------------------------------------------");
         }
-
-        result
     }
 
     // 
     pub async fn opt_issue(self: &Self, issue: &str) -> String {
-        let body = format!("{}, 上述内容为一个markdown格式的sca报告，使用markdown逐行解析记录，分析合并其中相同的组件及其风险，descriptioin可以使用中文优化一下，尽量做到比原始description内容短，并将优化后的markdown文件返回给我, 要求不改变原文件格式，仅优化其中的内容", issue);
+        let body = i18n::tf("llm.sca_optimize", &[("content", issue)]);
         llm::ask_ai(body.as_str()).await.unwrap()
     }
 }
 
+impl SCAReport {
+    pub fn issue_context(self: &Self) -> Value {
+        const COLS: [&str; 9] = [
+            "id",
+            "dependency",
+            "severity",
+            "priority",
+            "cve",
+            "location",
+            "affected",
+            "solution",
+            "description",
+        ];
+
+        let to_values = |vuls: &Vec<SCAVul>| -> Vec<Value> {
+            vuls.iter()
+                .enumerate()
+                .map(|(idx, vul)| {
+                    template::finding_value(
+                        idx + 1,
+                        &vul.to_finding(self.engine.as_str()),
+                        vul.to_issue_row(),
+                    )
+                })
+                .collect()
+        };
+
+        let groups: Vec<Value> = self
+            .merged_vuls_map
+            .iter()
+            .map(|(loc, vuls)| json!({ "location": loc, "findings": to_values(vuls) }))
+            .collect();
+
+        let kev = self.merged_vuls.iter().filter(|vul| vul.kev).count();
+        let kev_warning = if kev > 0 {
+            i18n::tf("sca.kev.warning", &[("count", kev.to_string().as_str())])
+        } else {
+            String::new()
+        };
+
+        template::context(
+            self.engine.as_str(),
+            to_values(&self.vuls),
+            json!({
+                "header": i18n::table_header(&COLS),
+                "intro": i18n::t("report.intro"),
+                "kev_warning": kev_warning,
+                "grouped": !self.merged_vuls_map.is_empty(),
+                "groups": groups,
+            }),
+        )
+    }
+}
+
 impl BaseParser<SCAVul> for SCAReport {
+    // npm audit v2 reads the versions from the lockfile next to the report
+    fn parse_file(self: &mut Self, content: &str, fpath: &str) -> &Vec<SCAVul> {
+        if let Ok(report) = serde_json::from_str::<Value>(content) {
+            if npm_audit::is_npm_audit(&report) {
+                let dir = Path::new(fpath).parent().map(|p| p.to_string_lossy().to_string());
+                return self.parse_npm_audit(&report, dir.filter(|d| !d.is_empty()).as_deref().unwrap_or("."));
+            }
+        }
+
+        self.parse(content)
+    }
+
     fn parse(self: &mut Self, content: &str) -> &Vec<SCAVul> {
+        if depcheck::is_depcheck_xml(content) {
+            match depcheck::from_xml(content) {
+                Some(report) => return self.parse_depcheck(&report),
+                None => {
+                    warn!("unreadable dependency-check xml report");
+                    return &self.vuls;
+                }
+            }
+        }
         let report: Value = serde_json::from_str(content).expect("Failed to parse JSON");
+        if Bom::is_cyclonedx(&report) {
+            return self.parse_cyclonedx(&report);
+        }
+        if trivy::is_trivy(&report) {
+            return self.parse_trivy(&report);
+        }
+        if grype::is_grype(&report) {
+            return self.parse_grype(&report);
+        }
+        if depcheck::is_depcheck(&report) {
+            return self.parse_depcheck(&report);
+        }
+        if npm_audit::is_npm_audit(&report) {
+            // no report path, the lockfile of the scanned project
+            return self.parse_npm_audit(&report, TARGET.as_str());
+        }
+        if cargo_audit::is_cargo_audit(&report) {
+            return self.parse_cargo_audit(&report);
+        }
+
+        // manifest -> package manager
+        let mut schemes: HashMap<String, Scheme> = HashMap::new();
+        for file in report["dependency_files"].as_array().unwrap_or(&vec![]) {
+            if let (Some(path), Some(pm)) = (file["path"].as_str(), file["package_manager"].as_str()) {
+                let scheme = Scheme::from_package_manager(pm);
+                schemes.insert(path.to_string(), scheme);
+                // iid is unique within the manifest only
+                let mut iids: HashMap<u64, String> = HashMap::new();
+                let deps = file["dependencies"].as_array().cloned().unwrap_or_default();
+                for dep in deps.iter() {
+                    let name = dep["package"]["name"].as_str().unwrap_or("");
+                    let version = dep["version"].as_str().unwrap_or("");
+                    let bom_ref = match self.sbom.find(path, name, version) {
+                        Some(component) => component.bom_ref.to_string(),
+                        None => self.sbom.add_component(Component {
+                            name: name.to_string(),
+                            version: version.to_string(),
+                            purl: cyclonedx::purl(scheme, name, version),
+                            location: path.to_string(),
+                            ..Default::default()
+                        }),
+                    };
+                    if let Some(iid) = dep["iid"].as_u64() {
+                        iids.insert(iid, bom_ref.to_string());
+                    }
+                    if dep["direct"].as_bool() == Some(true) && !self.sbom.direct.contains(&bom_ref) {
+                        self.sbom.direct.push(bom_ref);
+                    }
+                }
+                // dependency_path: ancestors from a direct dependency down to the immediate parent
+                for dep in deps.iter() {
+                    let Some(bom_ref) = dep["iid"].as_u64().and_then(|iid| iids.get(&iid)) else {
+                        continue;
+                    };
+                    let mut chain: Vec<String> = dep["dependency_path"]
+                        .as_array()
+                        .unwrap_or(&vec![])
+                        .iter()
+                        .filter_map(|p| p["iid"].as_u64().and_then(|iid| iids.get(&iid)).cloned())
+                        .collect();
+                    chain.push(bom_ref.to_string());
+                    for pair in chain.windows(2) {
+                        self.sbom.add_dependency(pair[0].as_str(), &[pair[1].to_string()]);
+                    }
+                }
+            }
+        }
+        // vul id -> fixed versions named by the remediation
+        let mut remediations: HashMap<String, Vec<String>> = HashMap::new();
+        for remediation in report["remediations"].as_array().unwrap_or(&vec![]) {
+            let versions = version::parse_fixed_versions(remediation["summary"].as_str().unwrap_or(""));
+            for fix in remediation["fixes"].as_array().unwrap_or(&vec![]) {
+                if let Some(id) = fix["id"].as_str().or(fix["cve"].as_str()) {
+                    remediations.entry(id.to_string()).or_default().extend(versions.clone());
+                }
+            }
+        }
+
         if let Some(vuls) = report["vulnerabilities"].as_array() {
             for vul in vuls {
                 println!("{}", vul.to_string());
-                let _vul: SCAVul = SCAVul::from(vul);
+                let mut _vul: SCAVul = SCAVul::from(vul);
+                if let Some(scheme) = schemes.get(&_vul.location) {
+                    if *scheme != Scheme::Generic {
+                        _vul.scheme = *scheme;
+                    }
+                }
+                if let Some(versions) = remediations.get(&_vul.id) {
+                    for v in versions {
+                        if !_vul.fixed_versions.contains(v) {
+                            _vul.fixed_versions.push(v.to_string());
+                        }
+                    }
+                }
                 debug!("{:?}", _vul);
                 self.vuls.push(_vul);
             }
@@ -480,72 +1433,37 @@ impl BaseParser<SCAVul> for SCAReport {
         &self.vuls
     }
 
-    fn export(self: &Self, excel: &str) -> bool {
-        todo!();
-    }
-
-    fn to_issue(self: &Self) -> Issue {
-        const COLS: [&str; 7] = [
-            "id",
-            "dependency",
-            "severity",
-            "cve",
-            "location",
-            "solution",
-            "description",
-        ];
+    fn merge(self: &mut Self, findings: &[Finding]) -> &Vec<SCAVul> {
+        for finding in findings.iter() {
+            self.push_unique(SCAVul::from(finding));
+        }
 
-        let title = format!("|{}|", COLS.join("|"));
-        // let title = "|title|severity||"
-        let sep = format!("|{}|", ["--"; COLS.len()].join("|"));
-        let header: String = format!("{title}\n{sep}");
-        let mut desc: String = String::new();
-        let mut idx = 1;
-        let mut vuls: &Vec<SCAVul> = &self.vuls;
-        if self.merged_vuls_map.len() > 1 {
-            //   vuls = &self.merged_vuls;
-            // }
-            
-            for (loc, vuls) in &self.merged_vuls_map {
-                idx = 1;
-                let sep_start = format!("----------------------------------{loc}------------------------------------------");
-                desc = format!("{desc}\n{sep_start}\n{header}");
-                for vul in vuls.iter() {
-                    desc = format!(
-                        "{desc}\n|{idx}{}",
-                        vul.to_issue_record()
-                            .trim()
-                            .replace("\\n", "<br>")
-                            .replace("\n", "<br>")
-                    );
-                    idx += 1;
-                }
+        &self.vuls
+    }
 
-                desc = format!("{desc}\n\n");
-            }
-        } else {
-            for vul in vuls.iter() {
-                desc = format!(
-                    "{desc}\n|{idx}{}",
-                    vul.to_issue_record()
-                        .trim()
-                        .replace("\\n", "<br>")
-                        .replace("\n", "<br>")
-                );
-                idx += 1;
-            }
-        }
+    fn export(self: &Self, fpath: &str) -> bool {
+        export::write(&self.findings(), fpath)
+    }
 
+    fn to_issue(self: &Self) -> Issue {
+        let ctx = self.issue_context();
         let mut issue: Issue = Issue::new();
         issue.engine = self.engine.to_string();
         issue.project_id = CI_PROJECT_ID.to_string();
-        issue.title = format!("{} scan report", self.engine);
-        issue.description = format!("<br>报告详情（如果表格中存在重复内容请忽略）如下：<br>\n{desc}");
+        issue.title = template::issue_title(&ctx);
+        issue.description = template::issue_description(&ctx, ISSUE_TEMPLATE);
 
         debug!("desc: {}", issue.description);
         issue
     }
 
+    fn findings(self: &Self) -> Vec<Finding> {
+        self.vuls
+            .iter()
+            .map(|vul| vul.to_finding(self.engine.as_str()))
+            .collect()
+    }
+
     async fn filter(self: &mut Self) -> Vec<SCAVul> {
         self.vuls.clone()
     }
@@ -554,8 +1472,22 @@ impl BaseParser<SCAVul> for SCAReport {
 impl BaseReport<SCAVul> for SCAReport {
     async fn report(self: &mut Self) {
         println!("sca report...");
+        // lockfile findings have no line, the mr security widget shows them instead of code quality
         self.filter().await;
+        self.enrich();
+        self.prioritize();
+        self.resolve_paths();
         self.count_vuln();
+        if !SBOM_OUTPUT.is_empty() {
+            self.write_sbom(SBOM_OUTPUT.as_str());
+        }
+        if !SPDX_OUTPUT.is_empty() {
+            self.write_spdx(SPDX_OUTPUT.as_str());
+        }
+        for fpath in export::export_paths(self.engine.as_str()) {
+            self.export(fpath.as_str());
+        }
+        gate::evaluate(&self.findings());
 
         if CI_MERGE_REQUEST_IID.as_str() == "" {
             log::error!("no merge request, skip create issue...");
@@ -563,12 +1495,12 @@ impl BaseReport<SCAVul> for SCAReport {
         }
         
 
-        //// 用于合并组件风险
-        self.merge_issues_local(); // 本地合并
-
-        let mut m = self.group_issue_local();
+        //// 按组件合并风险
+        let m = self.aggregate();
+        if *SCA_LLM_DECORATE {
+            self.decorate().await; // 可选, ai精简描述
+        }
 
-        // m = self.merge_issues(m).await; // 调用ai合并分支
         for (g, vs) in m.iter() {
             println!(
                 "----------------------------------{g}------------------------------------------"
@@ -615,7 +1547,12 @@ impl BaseReport<SCAVul> for SCAReport {
         // let res = self.opt_issue(&self.to_issue().description.as_str()).await;
         // println!("--------------------------------------------------------------------------------------------------------------------------------------------\nafter issue: {}", res);
 
-        let _ = gitlab::new_issue(&self.to_issue()).await;
+        let mut issue = self.to_issue();
+        let notes = template::split_issue(&mut issue, &self.issue_context());
+        let _ = gitlab::new_issue_with_notes(&issue, &notes).await;
+        if let Some(note) = template::mr_note(&self.issue_context()) {
+            let _ = gitlab::new_mr_comment(note).await;
+        }
 
         //// ai结果上报
         // let issue = Issue {
@@ -1,18 +1,41 @@
 use crate::{
-    conf::setting::{CI_MERGE_REQUEST_IID, CI_PROJECT_ID, CI_PROJECT_URL, GITLAB_USER_ID},
-    utils::gitlab::{self, Issue},
+    conf::setting::{CI_MERGE_REQUEST_IID, CI_PROJECT_ID, GITLAB_USER_ID},
+    utils::{
+        codequality, export, gate,
+        gitlab::{self, Issue},
+        i18n,
+        mask::mask,
+        render, template,
+    },
 };
 
-use super::base::{BaseParser, BaseReport};
-use serde_json::Value;
+use super::base::{fingerprint_or, severity_name, BaseParser, BaseReport, Finding};
+use super::sarif::{self, SarifResult};
+use super::trivy;
+use serde_json::{json, Value};
 
-#[derive(Debug, Clone)]
+const ISSUE_TEMPLATE: &str = "{{{header}}}{{#each findings}}\n|{{id}}{{{record}}}{{/each}}";
+
+#[derive(Debug, Clone, Default)]
 pub struct SecVul {
     message: String,
     description: String,
     severity: String,
     cve: String,
     location: String,
+    href: String,
+    file: String,
+    line: u64,
+    identifiers: Vec<String>,
+    // git history scans (gitleaks, trufflehog): where and by whom the secret was committed
+    commit: String,
+    author: String,
+    date: String,
+    entropy: Option<f64>,
+    // trufflehog checks the secret against the provider, None when not checked
+    verified: Option<bool>,
+    // stable id given by the scanner, computed from the fields when empty
+    fingerprint: String,
     // solution: String,
     // owner: RiskOwner,
 }
@@ -20,14 +43,63 @@ pub struct SecVul {
 impl SecVul {
     fn to_issue_record(self: &Self) -> String {
         let mut values: Vec<String> = Vec::new();
-        values.push(self.message.to_string());
-        values.push(self.severity.to_string());
-        values.push(self.cve.to_string());
-        values.push(self.location.to_string());
+        values.push(render::cell(self.message.as_str(), 200));
+        values.push(render::cell(self.severity.as_str(), 20));
+        values.push(render::code(self.cve.as_str()));
+        values.push(render::link(self.location.as_str(), self.href.as_str()));
         // values.push(self.solution.to_string());
-        values.push(self.description.to_string());
+        let mut description = render::cell(self.description.as_str(), render::MAX_CELL_LEN);
+        let mut meta: Vec<String> = vec![];
+        match self.verified {
+            Some(true) => meta.push(i18n::t("secret.verified")),
+            Some(false) => meta.push(i18n::t("secret.unverified")),
+            None => {}
+        }
+        if !self.commit.is_empty() {
+            meta.push(self.commit.chars().take(8).collect());
+        }
+        if !self.author.is_empty() {
+            meta.push(self.author.to_string());
+        }
+        if !self.date.is_empty() {
+            meta.push(self.date.chars().take(10).collect());
+        }
+        if let Some(entropy) = self.entropy {
+            meta.push(format!("entropy {entropy:.2}"));
+        }
+        if !meta.is_empty() {
+            description = format!("{description} {}", render::code(meta.join(", ").as_str()));
+        }
+        values.push(description);
+
+        render::row(&values)
+    }
+
+    // history findings link to the commit that introduced them
+    fn link(file: &str, line: u64, commit: &str) -> String {
+        if commit.is_empty() {
+            gitlab::blob_url(file, line)
+        } else {
+            gitlab::blob_url_at(commit, file, line)
+        }
+    }
+}
 
-        format!("|{}|", values.join("|"))
+impl From<&Finding> for SecVul {
+    fn from(finding: &Finding) -> Self {
+        SecVul {
+            message: finding.name.to_string(),
+            description: finding.description.to_string(),
+            severity: severity_name(finding.severity.as_str()),
+            cve: finding.identifiers.first().cloned().unwrap_or_default(),
+            location: format!("{}:{}", finding.file, finding.line),
+            href: gitlab::blob_url(finding.file.as_str(), finding.line),
+            file: finding.file.to_string(),
+            line: finding.line,
+            identifiers: finding.identifiers.clone(),
+            fingerprint: finding.fingerprint.to_string(),
+            ..Default::default()
+        }
     }
 }
 
@@ -47,14 +119,19 @@ impl From<&Value> for SecVul {
         let location_lineno = value["location"]["start_line"].to_string().replace("\"", "");
         // let solution = value["solution"].to_string().replace("\"", "");
 
-        let location_href = format!(
-            "{}/-/blob/develop/{}",
-            CI_PROJECT_URL.as_str(),
-            location_fpath
-        );
+        let line = value["location"]["start_line"].as_u64().unwrap_or(0);
+        let location_href = gitlab::blob_url(location_fpath.as_str(), line);
 
-        let location: String = format!("[{location_fpath}:{location_lineno}]({location_href})");
+        let location: String = format!("{location_fpath}:{location_lineno}");
         println!("location: {}", location);
+        let mut identifiers: Vec<String> = vec![];
+        if let Some(ids) = value["identifiers"].as_array() {
+            for id in ids {
+                if let Some(name) = id["name"].as_str() {
+                    identifiers.push(name.to_string());
+                }
+            }
+        }
 
         SecVul {
             message: message,
@@ -62,8 +139,130 @@ impl From<&Value> for SecVul {
             severity: severity,
             cve: cve,
             location: location,
+            href: location_href,
+            file: location_fpath,
+            line: line,
+            identifiers: identifiers,
             // solution: solution,
             // owner: todo!(),
+            ..Default::default()
+        }
+    }
+}
+
+impl SecVul {
+    /// secret of a trivy `Results[].Secrets` entry, `Match` is already redacted by trivy
+    fn from_trivy(secret: &Value, target: &str) -> Self {
+        let rule = secret["RuleID"].as_str().unwrap_or("").to_string();
+        let line = secret["StartLine"].as_u64().unwrap_or(0);
+
+        SecVul {
+            message: secret["Title"].as_str().unwrap_or(rule.as_str()).to_string(),
+            description: format!(
+                "{}: {}",
+                secret["Category"].as_str().unwrap_or(""),
+                secret["Match"].as_str().unwrap_or("").trim()
+            ),
+            severity: severity_name(secret["Severity"].as_str().unwrap_or("")),
+            cve: rule.to_string(),
+            location: format!("{target}:{line}"),
+            href: gitlab::blob_url(target, line),
+            file: target.to_string(),
+            line: line,
+            identifiers: vec![rule],
+            ..Default::default()
+        }
+    }
+
+    /// finding of a gitleaks json report, the raw `Secret` is masked
+    fn from_gitleaks(leak: &Value) -> Self {
+        let rule = leak["RuleID"].as_str().unwrap_or("").to_string();
+        let file = leak["File"].as_str().unwrap_or("").to_string();
+        let line = leak["StartLine"].as_u64().unwrap_or(0);
+        let commit = leak["Commit"].as_str().unwrap_or("").to_string();
+        let secret = leak["Secret"].as_str().unwrap_or("");
+        let matched = leak["Match"].as_str().unwrap_or("");
+        let matched = if secret.is_empty() { mask(matched) } else { matched.replace(secret, mask(secret).as_str()) };
+        let author = match (leak["Author"].as_str().unwrap_or(""), leak["Email"].as_str().unwrap_or("")) {
+            (name, "") => name.to_string(),
+            (name, email) => format!("{name} <{email}>"),
+        };
+
+        SecVul {
+            message: leak["Description"].as_str().filter(|d| !d.is_empty()).unwrap_or(rule.as_str()).to_string(),
+            description: matched.trim().to_string(),
+            // gitleaks has no severity, a leaked credential is treated as critical like gitlab does
+            severity: String::from("Critical"),
+            cve: rule.to_string(),
+            location: format!("{file}:{line}"),
+            href: SecVul::link(file.as_str(), line, commit.as_str()),
+            file: file,
+            line: line,
+            identifiers: vec![rule],
+            commit: commit,
+            author: author.trim().to_string(),
+            date: leak["Date"].as_str().unwrap_or("").to_string(),
+            entropy: leak["Entropy"].as_f64().filter(|e| *e > 0.0),
+            verified: None,
+            fingerprint: String::new(),
+        }
+    }
+
+    /// result of a sarif log (gitleaks `--report-format sarif`), commit info in `partialFingerprints`
+    fn from_sarif(result: &SarifResult) -> Self {
+        let prints = &result.result.map_or(&Value::Null, |r| r)["partialFingerprints"];
+        let commit = prints["commitSha"].as_str().unwrap_or("").to_string();
+        let author = match (prints["author"].as_str().unwrap_or(""), prints["email"].as_str().unwrap_or("")) {
+            (name, "") => name.to_string(),
+            (name, email) => format!("{name} <{email}>"),
+        };
+
+        SecVul {
+            message: result.rule_name.to_string(),
+            description: mask(result.snippet.as_str()),
+            severity: String::from("Critical"),
+            cve: result.rule_id.to_string(),
+            location: format!("{}:{}", result.file, result.line),
+            href: SecVul::link(result.file.as_str(), result.line, commit.as_str()),
+            file: result.file.to_string(),
+            line: result.line,
+            identifiers: vec![result.rule_id.to_string()],
+            commit: commit,
+            author: author.trim().to_string(),
+            date: prints["date"].as_str().unwrap_or("").to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// finding of trufflehog v3 `--json` output, git or filesystem source
+    fn from_trufflehog(finding: &Value) -> Self {
+        let data = &finding["SourceMetadata"]["Data"];
+        // the source kind is the only key, e.g. `Git`, `Filesystem`, `Gitlab`
+        let source = data.as_object().and_then(|m| m.values().next()).unwrap_or(&Value::Null);
+        let detector = finding["DetectorName"].as_str().unwrap_or("").to_string();
+        let file = source["file"].as_str().unwrap_or("").trim_start_matches("./").to_string();
+        let line = source["line"].as_u64().unwrap_or(0);
+        let commit = source["commit"].as_str().unwrap_or("").to_string();
+        let verified = finding["Verified"].as_bool();
+        let redacted = finding["Redacted"].as_str().filter(|r| !r.is_empty());
+
+        SecVul {
+            message: i18n::tf("secret.detector", &[("detector", detector.as_str())]),
+            description: redacted.map_or(mask(finding["Raw"].as_str().unwrap_or("")), |r| mask(r)),
+            // unverified results are often test data or rotated keys
+            severity: String::from(if verified == Some(true) { "Critical" } else { "High" }),
+            cve: detector.to_string(),
+            location: format!("{file}:{line}"),
+            href: SecVul::link(file.as_str(), line, commit.as_str()),
+            file: file,
+            line: line,
+            identifiers: vec![detector],
+            commit: commit,
+            author: source["email"].as_str().unwrap_or("").to_string(),
+            date: source["timestamp"].as_str().unwrap_or("").to_string(),
+            entropy: None,
+            verified: verified,
+            fingerprint: String::new(),
         }
     }
 }
@@ -81,61 +280,123 @@ impl SecretReport {
             engine: String::from("Secret"),
         }
     }
+
+    pub fn issue_context(self: &Self) -> Value {
+        const COLS: [&str; 6] = [
+            "id",
+            "title",
+            "severity",
+            "cve",
+            "location",
+            // "solution",
+            "description",
+        ];
+        let findings: Vec<Value> = self
+            .findings()
+            .iter()
+            .zip(self.vuls.iter())
+            .enumerate()
+            .map(|(idx, (finding, vul))| {
+                template::finding_value(idx + 1, finding, vul.to_issue_record())
+            })
+            .collect();
+
+        template::context(
+            self.engine.as_str(),
+            findings,
+            json!({ "header": i18n::table_header(&COLS) }),
+        )
+    }
 }
 
 impl BaseParser<SecVul> for SecretReport {
     fn parse(self: &mut Self, content: &str) -> &Vec<SecVul> {
-        let report: Value = serde_json::from_str(content).unwrap();
+        // trufflehog prints one json object per line
+        let Ok(report) = serde_json::from_str::<Value>(content) else {
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<Value>(line) {
+                    Ok(finding) if finding["DetectorName"].is_string() => {
+                        self.vuls.push(SecVul::from_trufflehog(&finding));
+                    }
+                    _ => log::warn!("not a trufflehog finding, skip: {}", render::truncate(line, 80)),
+                }
+            }
+            return &self.vuls;
+        };
+        if report["DetectorName"].is_string() {
+            self.vuls.push(SecVul::from_trufflehog(&report));
+        }
+        if let Some(leaks) = report.as_array() {
+            for leak in leaks.iter().filter(|l| l["RuleID"].is_string()) {
+                self.vuls.push(SecVul::from_gitleaks(leak));
+            }
+        }
+        if sarif::is_sarif(&report) {
+            for result in sarif::results(&report) {
+                self.vuls.push(SecVul::from_sarif(&result));
+            }
+        }
+        if trivy::is_trivy(&report) {
+            for (target, secret) in trivy::results(&report, "Secrets") {
+                self.vuls.push(SecVul::from_trivy(secret, target));
+            }
+        }
         if let Some(vulnerabilities) = report["vulnerabilities"].as_array() {
             for vul in vulnerabilities {
                 let _vul = SecVul::from(vul);
                 self.vuls.push(_vul);
             }
         }
-        println!("{:?}", self.vuls);
+        log::debug!("vul(secret) count: {}", self.vuls.len());
         &self.vuls
     }
 
-    fn export(self: &Self, excel: &str) -> bool {
-        todo!();
+    fn merge(self: &mut Self, findings: &[Finding]) -> &Vec<SecVul> {
+        self.vuls.extend(findings.iter().map(SecVul::from));
+
+        &self.vuls
+    }
 
-        true
+    fn export(self: &Self, fpath: &str) -> bool {
+        export::write(&self.findings(), fpath)
     }
 
     fn to_issue(self: &Self) -> Issue {
-        const COLS: [&str; 6] = [
-            "id",
-            "title",
-            "severity",
-            "cve",
-            "location",
-            // "solution",
-            "description",
-        ];
-        let title = format!("|{}|", COLS.join("|"));
-        // let title = "|title|severity||"
-        let sep = format!("|{}|", ["--"; COLS.len()].join("|"));
-        let mut desc: String = format!("{title}\n{sep}");
-
-        let mut idx = 1;
-        for vul in self.vuls.iter() {
-            desc = format!(
-                "{desc}\n|{idx}{}",
-                vul.to_issue_record()
-                    .trim()
-                    .replace("\\n", "<br>")
-                    .replace("\n", "<br>")
-            );
-            idx+=1;
-        }
+        let ctx = self.issue_context();
         let mut issue: Issue = Issue::new();
         issue.engine = self.engine.to_string();
         issue.project_id = CI_PROJECT_ID.to_string();
-        issue.title = format!("{} scan report", self.engine);
-        issue.description = desc;
+        issue.title = template::issue_title(&ctx);
+        issue.description = template::issue_description(&ctx, ISSUE_TEMPLATE);
         issue
     }
 
+    fn findings(self: &Self) -> Vec<Finding> {
+        self.vuls
+            .iter()
+            .map(|vul| {
+                let line = vul.line.to_string();
+                // a leaked secret is tied to its exact line, and to its commit in history scans
+                let mut parts = vec![self.engine.as_str(), vul.cve.as_str(), vul.file.as_str(), line.as_str()];
+                if !vul.commit.is_empty() {
+                    parts.push(vul.commit.as_str());
+                }
+                Finding {
+                    engine: self.engine.to_string(),
+                    name: vul.message.to_string(),
+                    description: vul.description.to_string(),
+                    severity: vul.severity.to_string(),
+                    identifiers: vul.identifiers.clone(),
+                    file: vul.file.to_string(),
+                    line: vul.line,
+                    dependency: String::new(),
+                    fingerprint: fingerprint_or(vul.fingerprint.as_str(), &parts),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
     async fn filter(self: &mut Self) -> Vec<SecVul> {
         self.vuls.clone()
     }
@@ -143,7 +404,12 @@ impl BaseParser<SecVul> for SecretReport {
 
 impl BaseReport<SecVul> for SecretReport {
     async fn report(self: &mut Self) {
+        codequality::write_report(&self.findings());
         self.filter().await;
+        for fpath in export::export_paths(self.engine.as_str()) {
+            self.export(fpath.as_str());
+        }
+        gate::evaluate(&self.findings());
 
         if self.vuls.len() < 1 {
             return;
@@ -171,6 +437,11 @@ impl BaseReport<SecVul> for SecretReport {
             return;
         }
 
-        let _ = gitlab::new_issue(&self.to_issue()).await;
+        let mut issue = self.to_issue();
+        let notes = template::split_issue(&mut issue, &self.issue_context());
+        let _ = gitlab::new_issue_with_notes(&issue, &notes).await;
+        if let Some(note) = template::mr_note(&self.issue_context()) {
+            let _ = gitlab::new_mr_comment(note).await;
+        }
     }
 }
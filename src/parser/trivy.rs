@@ -0,0 +1,56 @@
+//! trivy json report (`trivy fs|image|config --format json`), shared by the engines
+
+use serde_json::Value;
+
+use crate::utils::{cvss, version::Scheme};
+
+/// trivy report, `Results` is missing when nothing was found
+pub fn is_trivy(report: &Value) -> bool {
+    report["SchemaVersion"].is_number() && report["ArtifactName"].is_string()
+}
+
+/// `(target, item)` of every `Results[].{key}` entry, e.g. `Vulnerabilities`, `Secrets`
+pub fn results<'a>(report: &'a Value, key: &str) -> Vec<(&'a str, &'a Value)> {
+    let mut items: Vec<(&str, &Value)> = vec![];
+    for result in report["Results"].as_array().into_iter().flatten() {
+        let target = result["Target"].as_str().unwrap_or("");
+        for item in result[key].as_array().into_iter().flatten() {
+            items.push((target, item));
+        }
+    }
+
+    items
+}
+
+/// package manager of a result `Type`, e.g. `gomod`, `pom`, `pipenv`
+pub fn scheme(kind: &str, target: &str) -> Scheme {
+    let scheme = match kind {
+        "gomod" | "gobinary" => Scheme::Go,
+        "pom" | "jar" | "gradle" | "sbt" => Scheme::Maven,
+        "gemspec" => Scheme::Gem,
+        "dotnet-core" | "packages-props" => Scheme::Nuget,
+        "python-pkg" => Scheme::Pypi,
+        "node-pkg" => Scheme::Npm,
+        _ => Scheme::from_package_manager(kind),
+    };
+    if scheme == Scheme::Generic {
+        Scheme::from_file(target)
+    } else {
+        scheme
+    }
+}
+
+/// highest `(score, vector)` of the `CVSS` sources (nvd, ghsa, redhat, ...), v3 first
+pub fn cvss(vuln: &Value) -> Option<(f64, String)> {
+    let mut best: Option<(f64, String)> = None;
+    for source in vuln["CVSS"].as_object().into_iter().flat_map(|m| m.values()) {
+        let vector = source["V3Vector"].as_str().or(source["V2Vector"].as_str()).unwrap_or("");
+        if let Some(parsed) = cvss::parse(vector) {
+            if best.as_ref().map_or(true, |(score, _)| parsed.score > *score) {
+                best = Some((parsed.score, parsed.vector));
+            }
+        }
+    }
+
+    best
+}
@@ -0,0 +1,84 @@
+//! gitlab code quality (codeclimate) report
+
+use std::{collections::HashSet, fs};
+
+use serde_json::{json, Value};
+
+use crate::{conf::setting::CODE_QUALITY_REPORT, parser::base::Finding};
+
+// gitlab severity -> codeclimate severity
+fn get_severity(severity: &str) -> &'static str {
+    match severity.to_lowercase().as_str() {
+        "critical" => "blocker",
+        "high" => "critical",
+        "medium" => "major",
+        "low" => "minor",
+        _ => "info",
+    }
+}
+
+pub fn to_code_quality(findings: &[Finding]) -> Vec<Value> {
+    findings
+        .iter()
+        .map(|finding| {
+            json!({
+                "type": "issue",
+                "check_name": finding.identifiers.first().unwrap_or(&finding.name),
+                "description": format!("[{}] {}", finding.engine, finding.name),
+                "categories": ["Security"],
+                "severity": get_severity(finding.severity.as_str()),
+                "fingerprint": finding.fingerprint,
+                "location": {
+                    "path": finding.file,
+                    "lines": {
+                        "begin": begin_line(finding.line),
+                        "end": begin_line(finding.line.max(finding.end_line)),
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+// codeclimate lines start at 1
+fn begin_line(line: u64) -> u64 {
+    if line < 1 {
+        1
+    } else {
+        line
+    }
+}
+
+/// write findings into `CODE_QUALITY_REPORT`, entries from previous engines are kept
+///
+/// engines call it first, before `filter()`, then export and gate: the mr widget compares all
+/// findings with the target branch
+pub fn write_report(findings: &[Finding]) -> bool {
+    let fpath = CODE_QUALITY_REPORT.as_str();
+    let mut issues = to_code_quality(findings);
+
+    let fingerprints: HashSet<String> = issues
+        .iter()
+        .map(|issue| issue["fingerprint"].as_str().unwrap_or("").to_string())
+        .collect();
+    if let Ok(cont) = fs::read_to_string(fpath) {
+        if let Ok(Value::Array(olds)) = serde_json::from_str::<Value>(cont.as_str()) {
+            for old in olds {
+                if !fingerprints.contains(old["fingerprint"].as_str().unwrap_or("")) {
+                    issues.push(old);
+                }
+            }
+        }
+    }
+
+    match fs::write(fpath, Value::Array(issues).to_string()) {
+        Ok(_) => {
+            log::info!("code quality report saved: {fpath}, {} issues", findings.len());
+            true
+        }
+        Err(e) => {
+            log::error!("failed to write code quality report {fpath}: {e}");
+            false
+        }
+    }
+}
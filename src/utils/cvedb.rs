@@ -0,0 +1,449 @@
+//! offline cve enrichment from a local osv / nvd json dump
+//!
+//! the dump (`CVE_DB_PATH`, a file or a directory of json files) is converted once into an
+//! on-disk cache under `CVE_CACHE_DIR`: `records.jsonl` holds one normalized record per line
+//! and `index.json` maps every id and alias to the byte offsets of its records.
+//! a vuln listed by several dumps (e.g. a ghsa in osv and its cve in nvd) is merged on lookup.
+//! the cache is rebuilt when the dump is newer than the index.
+//!
+//! supported dumps:
+//! - osv: one vuln per file, or a json array of vulns
+//! - nvd json feed 1.1 (`CVE_Items`)
+//! - nvd api 2.0 (`vulnerabilities[].cve`)
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde_json::{json, Value};
+
+use crate::utils::cvss;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FixedRange {
+    pub package: String,
+    pub introduced: String,
+    pub fixed: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CveRecord {
+    pub id: String,
+    pub aliases: Vec<String>,
+    pub cvss_vector: String,
+    pub cvss_score: Option<f64>,
+    pub cwes: Vec<String>,
+    pub published: String,
+    pub fixed: Vec<FixedRange>,
+}
+
+impl CveRecord {
+    fn to_value(self: &Self) -> Value {
+        json!({
+            "id": self.id,
+            "aliases": self.aliases,
+            "cvss_vector": self.cvss_vector,
+            "cvss_score": self.cvss_score,
+            "cwes": self.cwes,
+            "published": self.published,
+            "fixed": self.fixed.iter().map(|r| json!({
+                "package": r.package,
+                "introduced": r.introduced,
+                "fixed": r.fixed,
+            })).collect::<Vec<Value>>(),
+        })
+    }
+
+    // the same vuln from another dump: union of the lists, highest cvss, earliest date
+    fn merge(self: &mut Self, other: CveRecord) {
+        for alias in [other.id].into_iter().chain(other.aliases) {
+            if alias != self.id && !self.aliases.contains(&alias) {
+                self.aliases.push(alias);
+            }
+        }
+        if other.cvss_score.map_or(false, |score| self.cvss_score.map_or(true, |s| score > s)) {
+            self.cvss_score = other.cvss_score;
+            self.cvss_vector = other.cvss_vector;
+        }
+        for cwe in other.cwes {
+            if !self.cwes.contains(&cwe) {
+                self.cwes.push(cwe);
+            }
+        }
+        if self.published.is_empty() || (!other.published.is_empty() && other.published < self.published) {
+            self.published = other.published;
+        }
+        for range in other.fixed {
+            if !self.fixed.contains(&range) {
+                self.fixed.push(range);
+            }
+        }
+    }
+
+    // keep the highest scored cvss vector
+    fn set_cvss(self: &mut Self, vector: &str) {
+        if let Some(parsed) = cvss::parse(vector) {
+            if self.cvss_score.map_or(true, |score| parsed.score > score) {
+                self.cvss_score = Some(parsed.score);
+                self.cvss_vector = parsed.vector;
+            }
+        }
+    }
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .unwrap_or(&vec![])
+        .iter()
+        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+        .collect()
+}
+
+impl From<&Value> for CveRecord {
+    fn from(obj: &Value) -> Self {
+        let mut record = CveRecord {
+            id: obj["id"].as_str().unwrap_or("").to_string(),
+            aliases: strings(&obj["aliases"]),
+            cwes: strings(&obj["cwes"]),
+            published: obj["published"].as_str().unwrap_or("").to_string(),
+            ..Default::default()
+        };
+        record.cvss_vector = obj["cvss_vector"].as_str().unwrap_or("").to_string();
+        record.cvss_score = obj["cvss_score"].as_f64();
+        for r in obj["fixed"].as_array().unwrap_or(&vec![]) {
+            record.fixed.push(FixedRange {
+                package: r["package"].as_str().unwrap_or("").to_string(),
+                introduced: r["introduced"].as_str().unwrap_or("").to_string(),
+                fixed: r["fixed"].as_str().unwrap_or("").to_string(),
+            });
+        }
+
+        record
+    }
+}
+
+fn from_osv(obj: &Value) -> CveRecord {
+    let mut record = CveRecord {
+        id: obj["id"].as_str().unwrap_or("").to_string(),
+        aliases: strings(&obj["aliases"]),
+        published: obj["published"].as_str().unwrap_or("").to_string(),
+        cwes: strings(&obj["database_specific"]["cwe_ids"]),
+        ..Default::default()
+    };
+    for severity in obj["severity"].as_array().unwrap_or(&vec![]) {
+        record.set_cvss(severity["score"].as_str().unwrap_or(""));
+    }
+    for affected in obj["affected"].as_array().unwrap_or(&vec![]) {
+        let package = affected["package"]["name"].as_str().unwrap_or("").to_string();
+        for range in affected["ranges"].as_array().unwrap_or(&vec![]) {
+            let mut introduced = String::new();
+            for event in range["events"].as_array().unwrap_or(&vec![]) {
+                if let Some(v) = event["introduced"].as_str() {
+                    introduced = v.to_string();
+                }
+                if let Some(v) = event["fixed"].as_str() {
+                    record.fixed.push(FixedRange {
+                        package: package.to_string(),
+                        introduced: introduced.to_string(),
+                        fixed: v.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    record
+}
+
+// nvd feed 1.1
+fn from_nvd_item(item: &Value) -> CveRecord {
+    let mut record = CveRecord {
+        id: item["cve"]["CVE_data_meta"]["ID"].as_str().unwrap_or("").to_string(),
+        published: item["publishedDate"].as_str().unwrap_or("").to_string(),
+        ..Default::default()
+    };
+    for problem in item["cve"]["problemtype"]["problemtype_data"]
+        .as_array()
+        .unwrap_or(&vec![])
+    {
+        for desc in problem["description"].as_array().unwrap_or(&vec![]) {
+            if let Some(cwe) = desc["value"].as_str().filter(|v| v.starts_with("CWE-")) {
+                record.cwes.push(cwe.to_string());
+            }
+        }
+    }
+    record.set_cvss(
+        item["impact"]["baseMetricV3"]["cvssV3"]["vectorString"]
+            .as_str()
+            .unwrap_or(""),
+    );
+    if record.cvss_score.is_none() {
+        record.set_cvss(
+            item["impact"]["baseMetricV2"]["cvssV2"]["vectorString"]
+                .as_str()
+                .unwrap_or(""),
+        );
+    }
+    for node in item["configurations"]["nodes"].as_array().unwrap_or(&vec![]) {
+        nvd_fixed(&node["cpe_match"], "cpe23Uri", &mut record);
+    }
+
+    record
+}
+
+// nvd api 2.0
+fn from_nvd_cve(cve: &Value) -> CveRecord {
+    let mut record = CveRecord {
+        id: cve["id"].as_str().unwrap_or("").to_string(),
+        published: cve["published"].as_str().unwrap_or("").to_string(),
+        ..Default::default()
+    };
+    for weakness in cve["weaknesses"].as_array().unwrap_or(&vec![]) {
+        for desc in weakness["description"].as_array().unwrap_or(&vec![]) {
+            if let Some(cwe) = desc["value"].as_str().filter(|v| v.starts_with("CWE-")) {
+                if !record.cwes.contains(&cwe.to_string()) {
+                    record.cwes.push(cwe.to_string());
+                }
+            }
+        }
+    }
+    for key in ["cvssMetricV31", "cvssMetricV30", "cvssMetricV2"] {
+        for metric in cve["metrics"][key].as_array().unwrap_or(&vec![]) {
+            record.set_cvss(metric["cvssData"]["vectorString"].as_str().unwrap_or(""));
+        }
+        if record.cvss_score.is_some() {
+            break;
+        }
+    }
+    for config in cve["configurations"].as_array().unwrap_or(&vec![]) {
+        for node in config["nodes"].as_array().unwrap_or(&vec![]) {
+            nvd_fixed(&node["cpeMatch"], "criteria", &mut record);
+        }
+    }
+
+    record
+}
+
+// cpe:2.3:a:vendor:product:... with versionEndExcluding is the first fixed version
+fn nvd_fixed(matches: &Value, cpe_key: &str, record: &mut CveRecord) {
+    for m in matches.as_array().unwrap_or(&vec![]) {
+        let Some(fixed) = m["versionEndExcluding"].as_str() else {
+            continue;
+        };
+        let package = m[cpe_key].as_str().unwrap_or("").split(':').nth(4).unwrap_or("");
+        record.fixed.push(FixedRange {
+            package: package.to_string(),
+            introduced: m["versionStartIncluding"].as_str().unwrap_or("").to_string(),
+            fixed: fixed.to_string(),
+        });
+    }
+}
+
+/// records of one json document of the dump
+pub fn parse_dump(doc: &Value) -> Vec<CveRecord> {
+    if let Some(items) = doc["CVE_Items"].as_array() {
+        return items.iter().map(from_nvd_item).collect();
+    }
+    if let Some(vulns) = doc["vulnerabilities"].as_array() {
+        return vulns.iter().map(|v| from_nvd_cve(&v["cve"])).collect();
+    }
+    if let Some(vulns) = doc.as_array() {
+        return vulns.iter().map(from_osv).collect();
+    }
+    if doc["id"].is_string() {
+        return vec![from_osv(doc)];
+    }
+
+    vec![]
+}
+
+fn walk(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = fs::read_dir(path)
+            .map(|dir| dir.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+            .unwrap_or_default();
+        entries.sort();
+        for entry in entries {
+            walk(entry.as_path(), files);
+        }
+    } else if path.extension().map_or(false, |ext| ext == "json") {
+        files.push(path.to_path_buf());
+    }
+}
+
+fn mtime(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+// bumped when the layout of the cache changes
+const CACHE_VERSION: u64 = 2;
+
+pub struct CveDb {
+    records: PathBuf,
+    index: HashMap<String, Vec<u64>>,
+}
+
+impl CveDb {
+    /// open the cache of `source` under `cache_dir`, (re)building it when stale
+    pub fn open(source: &str, cache_dir: &str) -> std::io::Result<Self> {
+        let cache_dir = Path::new(cache_dir);
+        let records = cache_dir.join("records.jsonl");
+        let index_path = cache_dir.join("index.json");
+
+        let mut files: Vec<PathBuf> = vec![];
+        walk(Path::new(source), &mut files);
+        let source_mtime = files.iter().map(|f| mtime(f)).max().unwrap_or(0);
+
+        if let Ok(cont) = fs::read_to_string(&index_path) {
+            let index: Value = serde_json::from_str(cont.as_str()).unwrap_or_default();
+            if index["source"].as_str() == Some(source)
+                && index["version"].as_u64() == Some(CACHE_VERSION)
+                && index["mtime"].as_u64().unwrap_or(0) >= source_mtime
+                && records.exists()
+            {
+                let ids: HashMap<String, Vec<u64>> = index["ids"]
+                    .as_object()
+                    .map(|ids| {
+                        ids.iter()
+                            .map(|(k, v)| {
+                                let offsets = v.as_array().unwrap_or(&vec![]).iter().filter_map(|o| o.as_u64()).collect();
+                                (k.to_string(), offsets)
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                log::info!("cve cache loaded: {} ids", ids.len());
+                return Ok(CveDb { records, index: ids });
+            }
+        }
+
+        log::info!("building cve cache from {source}, {} files", files.len());
+        fs::create_dir_all(cache_dir)?;
+        let mut writer = BufWriter::new(File::create(&records)?);
+        let mut offset: u64 = 0;
+        let mut ids: HashMap<String, Vec<u64>> = HashMap::new();
+        for file in files.iter() {
+            let Ok(cont) = fs::read_to_string(file) else {
+                continue;
+            };
+            let Ok(doc) = serde_json::from_str::<Value>(cont.as_str()) else {
+                log::warn!("skip invalid json: {}", file.display());
+                continue;
+            };
+            for record in parse_dump(&doc) {
+                if record.id.is_empty() {
+                    continue;
+                }
+                let line = format!("{}\n", record.to_value());
+                writer.write_all(line.as_bytes())?;
+                for id in [&record.id].into_iter().chain(record.aliases.iter()) {
+                    let offsets = ids.entry(id.to_uppercase()).or_default();
+                    if !offsets.contains(&offset) {
+                        offsets.push(offset);
+                    }
+                }
+                offset += line.len() as u64;
+            }
+        }
+        writer.flush()?;
+
+        let index = json!({ "source": source, "version": CACHE_VERSION, "mtime": source_mtime, "ids": ids });
+        fs::write(&index_path, index.to_string())?;
+        log::info!("cve cache built: {} ids", ids.len());
+
+        Ok(CveDb { records, index: ids })
+    }
+
+    fn read(self: &Self, file: &mut File, offset: u64) -> Option<CveRecord> {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut line = String::new();
+        BufReader::new(&*file).read_line(&mut line).ok()?;
+        let obj: Value = serde_json::from_str(line.as_str()).ok()?;
+
+        Some(CveRecord::from(&obj))
+    }
+
+    pub fn get(self: &Self, id: &str) -> Option<CveRecord> {
+        self.lookup(&[id.to_string()])
+    }
+
+    /// every record of any of the ids or their aliases merged into one, the first found keeps its id
+    pub fn lookup(self: &Self, ids: &[String]) -> Option<CveRecord> {
+        let mut file = File::open(&self.records).ok()?;
+        let mut queue: VecDeque<String> = ids.iter().map(|id| id.to_uppercase()).collect();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut offsets: Vec<u64> = vec![];
+        let mut merged: Option<CveRecord> = None;
+        while let Some(id) = queue.pop_front() {
+            if !seen.insert(id.to_string()) {
+                continue;
+            }
+            for offset in self.index.get(&id).into_iter().flatten() {
+                if offsets.contains(offset) {
+                    continue;
+                }
+                offsets.push(*offset);
+                let Some(record) = self.read(&mut file, *offset) else {
+                    continue;
+                };
+                queue.extend([&record.id].into_iter().chain(record.aliases.iter()).map(|a| a.to_uppercase()));
+                match merged.as_mut() {
+                    Some(known) => known.merge(record),
+                    None => merged = Some(record),
+                }
+            }
+        }
+
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_in_two_dumps() {
+        let dir = std::env::temp_dir().join(format!("cvedb-test-{}", std::process::id()));
+        let source = dir.join("dump");
+        fs::create_dir_all(&source).unwrap();
+        let osv = json!({
+            "id": "GHSA-xxxx-yyyy-zzzz",
+            "aliases": ["CVE-2024-0001"],
+            "published": "2024-02-01T00:00:00Z",
+            "affected": [{ "package": { "name": "lodash" }, "ranges": [{ "events": [{ "introduced": "0" }, { "fixed": "4.17.21" }] }] }],
+        });
+        let nvd = json!({ "vulnerabilities": [{ "cve": {
+            "id": "CVE-2024-0001",
+            "published": "2024-01-15T00:00:00",
+            "weaknesses": [{ "description": [{ "value": "CWE-1321" }] }],
+            "metrics": { "cvssMetricV31": [{ "cvssData": { "vectorString": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H" } }] },
+        } }] });
+        fs::write(source.join("a-osv.json"), osv.to_string()).unwrap();
+        fs::write(source.join("b-nvd.json"), nvd.to_string()).unwrap();
+
+        let db = CveDb::open(source.to_str().unwrap(), dir.join("cache").to_str().unwrap()).unwrap();
+        for id in ["CVE-2024-0001", "ghsa-xxxx-yyyy-zzzz"] {
+            let record = db.get(id).unwrap();
+            assert_eq!(record.id, "GHSA-xxxx-yyyy-zzzz");
+            assert_eq!(record.aliases, vec![String::from("CVE-2024-0001")]);
+            assert_eq!(record.cwes, vec![String::from("CWE-1321")]);
+            assert!(record.cvss_score.is_some());
+            assert_eq!(record.published, "2024-01-15T00:00:00");
+            assert_eq!(record.fixed.len(), 1);
+        }
+        // the cached index keeps both records
+        let db = CveDb::open(source.to_str().unwrap(), dir.join("cache").to_str().unwrap()).unwrap();
+        assert!(db.get("CVE-2024-0001").unwrap().cvss_score.is_some());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
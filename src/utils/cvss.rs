@@ -0,0 +1,150 @@
+//! cvss base score from a vector string, v3.x and v2
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Cvss {
+    pub version: String,
+    pub vector: String,
+    pub score: f64,
+}
+
+fn metrics(vector: &str) -> Vec<(&str, &str)> {
+    vector
+        .split('/')
+        .filter_map(|metric| metric.split_once(':'))
+        .collect()
+}
+
+fn get<'a>(metrics: &[(&'a str, &'a str)], name: &str) -> Option<&'a str> {
+    metrics.iter().find(|(k, _)| *k == name).map(|(_, v)| *v)
+}
+
+// cvss 3.1 roundup, avoids floating point artifacts like 4.000000001 -> 4.1
+fn roundup(value: f64) -> f64 {
+    let int_input = (value * 100000.0).round() as i64;
+    if int_input % 10000 == 0 {
+        int_input as f64 / 100000.0
+    } else {
+        ((int_input / 10000) + 1) as f64 / 10.0
+    }
+}
+
+fn score_v3(vector: &str) -> Option<f64> {
+    let m = metrics(vector);
+    let changed = get(&m, "S")? == "C";
+    let av = match get(&m, "AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let ac = match get(&m, "AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let pr = match (get(&m, "PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let ui = match get(&m, "UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let cia = |name: &str| -> Option<f64> {
+        match get(&m, name)? {
+            "H" => Some(0.56),
+            "L" => Some(0.22),
+            "N" => Some(0.0),
+            _ => None,
+        }
+    };
+    let iss = 1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+    } else {
+        6.42 * iss
+    };
+    let exploitability = 8.22 * av * ac * pr * ui;
+
+    if impact <= 0.0 {
+        Some(0.0)
+    } else if changed {
+        Some(roundup((1.08 * (impact + exploitability)).min(10.0)))
+    } else {
+        Some(roundup((impact + exploitability).min(10.0)))
+    }
+}
+
+fn score_v2(vector: &str) -> Option<f64> {
+    let m = metrics(vector);
+    let av = match get(&m, "AV")? {
+        "L" => 0.395,
+        "A" => 0.646,
+        "N" => 1.0,
+        _ => return None,
+    };
+    let ac = match get(&m, "AC")? {
+        "H" => 0.35,
+        "M" => 0.61,
+        "L" => 0.71,
+        _ => return None,
+    };
+    let au = match get(&m, "Au")? {
+        "M" => 0.45,
+        "S" => 0.56,
+        "N" => 0.704,
+        _ => return None,
+    };
+    let cia = |name: &str| -> Option<f64> {
+        match get(&m, name)? {
+            "N" => Some(0.0),
+            "P" => Some(0.275),
+            "C" => Some(0.660),
+            _ => None,
+        }
+    };
+    let impact = 10.41 * (1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?));
+    let exploitability = 20.0 * av * ac * au;
+    let f = if impact == 0.0 { 0.0 } else { 1.176 };
+
+    Some((((0.6 * impact) + (0.4 * exploitability) - 1.5) * f * 10.0).round() / 10.0)
+}
+
+/// parse a vector like `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H` or `AV:N/AC:L/Au:N/C:P/I:P/A:P`
+pub fn parse(vector: &str) -> Option<Cvss> {
+    let vector = vector.trim();
+    if let Some(rest) = vector.strip_prefix("CVSS:") {
+        let version = rest.split('/').next().unwrap_or("").to_string();
+        if !version.starts_with('3') {
+            return None;
+        }
+        return score_v3(vector).map(|score| Cvss {
+            version,
+            vector: vector.to_string(),
+            score,
+        });
+    }
+
+    score_v2(vector.trim_start_matches('(').trim_end_matches(')')).map(|score| Cvss {
+        version: String::from("2.0"),
+        vector: vector.to_string(),
+        score,
+    })
+}
+
+/// qualitative rating of a base score, e.g. 7.5 is High
+pub fn severity(score: f64) -> &'static str {
+    match score {
+        s if s >= 9.0 => "Critical",
+        s if s >= 7.0 => "High",
+        s if s >= 4.0 => "Medium",
+        s if s > 0.0 => "Low",
+        _ => "Info",
+    }
+}
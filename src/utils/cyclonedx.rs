@@ -0,0 +1,500 @@
+//! cyclonedx 1.4 / 1.5 json sboms: components, purls, dependency graph and vulnerabilities
+//!
+//! several sboms (e.g. one `gl-sbom-*.cdx.json` per manifest) are merged into one [`Bom`],
+//! which is written back as a single cyclonedx 1.5 document with the findings as vex entries
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::parser::base::fingerprint;
+use crate::utils::version::Scheme;
+
+pub const SPEC_VERSION: &str = "1.5";
+// gitlab records the manifest of the sbom as a metadata property
+const INPUT_FILE_PROPERTY: &str = "gitlab:dependency_scanning:input_file:path";
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Component {
+    pub bom_ref: String,
+    pub group: String,
+    pub name: String,
+    pub version: String,
+    pub purl: String,
+    pub location: String,
+    pub licenses: Vec<String>,
+}
+
+impl Component {
+    /// package name the way gitlab reports it, `group/name` for maven and scoped npm packages
+    pub fn package_name(self: &Self) -> String {
+        if self.group.is_empty() {
+            self.name.to_string()
+        } else {
+            format!("{}/{}", self.group, self.name)
+        }
+    }
+
+    pub fn scheme(self: &Self) -> Scheme {
+        Scheme::from_purl(self.purl.as_str())
+    }
+
+    fn to_value(self: &Self) -> Value {
+        let mut obj = json!({
+            "type": "library",
+            "bom-ref": self.bom_ref,
+            "name": self.name,
+            "version": self.version,
+        });
+        if !self.group.is_empty() {
+            obj["group"] = json!(self.group);
+        }
+        if !self.purl.is_empty() {
+            obj["purl"] = json!(self.purl);
+        }
+        if !self.licenses.is_empty() {
+            obj["licenses"] = Value::Array(
+                self.licenses
+                    .iter()
+                    .map(|l| {
+                        if l.contains(' ') {
+                            json!({ "expression": l })
+                        } else {
+                            json!({ "license": { "id": l } })
+                        }
+                    })
+                    .collect(),
+            );
+        }
+        if !self.location.is_empty() {
+            obj["properties"] = json!([{ "name": INPUT_FILE_PROPERTY, "value": self.location }]);
+        }
+
+        obj
+    }
+}
+
+/// package url of a dependency reported without one
+pub fn purl(scheme: Scheme, name: &str, version: &str) -> String {
+    let name = match scheme {
+        // gemnasium reports maven packages as group/artifact
+        Scheme::Maven => name.replace(':', "/"),
+        // the scope `@` is percent encoded in purls
+        Scheme::Npm => name.replacen('@', "%40", 1),
+        _ => name.to_string(),
+    };
+    if version.is_empty() {
+        format!("pkg:{}/{}", scheme.purl_type(), name)
+    } else {
+        format!("pkg:{}/{}@{}", scheme.purl_type(), name, version)
+    }
+}
+
+/// a finding as a cyclonedx vulnerability, `refs` are the bom-refs of the affected components
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Vex {
+    pub id: String,
+    pub source_url: String,
+    pub description: String,
+    pub recommendation: String,
+    pub severity: String,
+    pub cvss_score: Option<f64>,
+    pub cvss_vector: String,
+    pub cwes: Vec<String>,
+    pub refs: Vec<String>,
+    pub properties: Vec<(String, String)>,
+}
+
+impl Vex {
+    pub fn to_value(self: &Self) -> Value {
+        let mut rating = json!({ "severity": self.severity.to_lowercase() });
+        if let Some(score) = self.cvss_score {
+            rating["score"] = json!(score);
+        }
+        if !self.cvss_vector.is_empty() {
+            let method = if self.cvss_vector.starts_with("CVSS:3.1") {
+                "CVSSv31"
+            } else if self.cvss_vector.starts_with("CVSS:3") {
+                "CVSSv3"
+            } else {
+                "CVSSv2"
+            };
+            rating["method"] = json!(method);
+            rating["vector"] = json!(self.cvss_vector);
+        }
+
+        let mut obj = json!({
+            "bom-ref": format!("vuln-{}", fingerprint(&[self.id.as_str()])),
+            "id": self.id,
+            "ratings": [rating],
+            "description": self.description,
+            "recommendation": self.recommendation,
+            // the scanner found it, nobody confirmed or dismissed it yet
+            "analysis": { "state": "in_triage" },
+            "affects": self.refs.iter().map(|r| json!({ "ref": r })).collect::<Vec<Value>>(),
+        });
+        if !self.source_url.is_empty() {
+            obj["source"] = json!({ "url": self.source_url });
+        }
+        let cwes: Vec<u64> = self
+            .cwes
+            .iter()
+            .filter_map(|cwe| cwe.trim_start_matches("CWE-").parse().ok())
+            .collect();
+        if !cwes.is_empty() {
+            obj["cwes"] = json!(cwes);
+        }
+        if !self.properties.is_empty() {
+            obj["properties"] = Value::Array(
+                self.properties
+                    .iter()
+                    .map(|(name, value)| json!({ "name": name, "value": value }))
+                    .collect(),
+            );
+        }
+
+        obj
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Bom {
+    pub components: Vec<Component>,
+    // bom-ref -> bom-refs it depends on
+    pub dependencies: Vec<(String, Vec<String>)>,
+    // bom-refs of the direct dependencies of the project, when the input tells them
+    pub direct: Vec<String>,
+    // raw vulnerabilities of the parsed sboms
+    pub vulnerabilities: Vec<Value>,
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .unwrap_or(&vec![])
+        .iter()
+        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+        .collect()
+}
+
+impl Bom {
+    pub fn is_cyclonedx(doc: &Value) -> bool {
+        doc["bomFormat"].as_str() == Some("CycloneDX")
+    }
+
+    pub fn parse(doc: &Value) -> Self {
+        let spec = doc["specVersion"].as_str().unwrap_or("");
+        if !["1.4", "1.5"].contains(&spec) {
+            log::warn!("cyclonedx spec version {spec} not tested, parse anyway");
+        }
+        let mut location = String::new();
+        for property in doc["metadata"]["properties"].as_array().unwrap_or(&vec![]) {
+            if property["name"].as_str() == Some(INPUT_FILE_PROPERTY) {
+                location = property["value"].as_str().unwrap_or("").to_string();
+            }
+        }
+
+        let mut bom = Bom::default();
+        let mut stack: Vec<&Value> = doc["components"]
+            .as_array()
+            .map(|c| c.iter().collect())
+            .unwrap_or_default();
+        // nested components are flattened
+        while let Some(obj) = stack.pop() {
+            if let Some(nested) = obj["components"].as_array() {
+                stack.extend(nested.iter());
+            }
+            let mut licenses: Vec<String> = vec![];
+            for license in obj["licenses"].as_array().unwrap_or(&vec![]) {
+                let value = license["expression"]
+                    .as_str()
+                    .or(license["license"]["id"].as_str())
+                    .or(license["license"]["name"].as_str());
+                if let Some(value) = value {
+                    licenses.push(value.to_string());
+                }
+            }
+            let mut component = Component {
+                bom_ref: obj["bom-ref"].as_str().unwrap_or("").to_string(),
+                group: obj["group"].as_str().unwrap_or("").to_string(),
+                name: obj["name"].as_str().unwrap_or("").to_string(),
+                version: obj["version"].as_str().unwrap_or("").to_string(),
+                purl: obj["purl"].as_str().unwrap_or("").to_string(),
+                location: location.to_string(),
+                licenses: licenses,
+            };
+            for property in obj["properties"].as_array().unwrap_or(&vec![]) {
+                if property["name"].as_str() == Some(INPUT_FILE_PROPERTY) {
+                    component.location = property["value"].as_str().unwrap_or("").to_string();
+                }
+            }
+            bom.add_component(component);
+        }
+        bom.components.sort_by(|a, b| a.bom_ref.cmp(&b.bom_ref));
+
+        // the metadata component is the project, what it depends on is direct
+        let root = doc["metadata"]["component"]["bom-ref"].as_str().unwrap_or("");
+        for dep in doc["dependencies"].as_array().unwrap_or(&vec![]) {
+            if let Some(r) = dep["ref"].as_str() {
+                let depends_on = strings(&dep["dependsOn"]);
+                if r == root {
+                    bom.direct.extend(depends_on.iter().cloned());
+                } else {
+                    bom.add_dependency(r, &depends_on);
+                }
+            }
+        }
+        bom.vulnerabilities = doc["vulnerabilities"].as_array().cloned().unwrap_or_default();
+
+        bom
+    }
+
+    /// add a component unless its bom-ref is known for the same manifest, returns the bom-ref
+    ///
+    /// the same package of another manifest gets its own node, its bom-ref suffixed with the manifest
+    pub fn add_component(self: &mut Self, mut component: Component) -> String {
+        if component.bom_ref.is_empty() {
+            component.bom_ref = if component.purl.is_empty() {
+                format!("{}@{}", component.package_name(), component.version)
+            } else {
+                component.purl.to_string()
+            };
+        }
+        let same_manifest = |c: &Component, location: &str| {
+            c.location == location || c.location.is_empty() || location.is_empty()
+        };
+        if self
+            .components
+            .iter()
+            .any(|c| c.bom_ref == component.bom_ref && !same_manifest(c, component.location.as_str()))
+        {
+            component.bom_ref = format!("{}#{}", component.bom_ref, component.location);
+        }
+        let bom_ref = component.bom_ref.to_string();
+        match self
+            .components
+            .iter_mut()
+            .find(|c| c.bom_ref == bom_ref && same_manifest(c, component.location.as_str()))
+        {
+            Some(known) => {
+                if known.location.is_empty() {
+                    known.location = component.location;
+                }
+                for license in component.licenses {
+                    if !known.licenses.contains(&license) {
+                        known.licenses.push(license);
+                    }
+                }
+            }
+            None => self.components.push(component),
+        }
+
+        bom_ref
+    }
+
+    pub fn add_dependency(self: &mut Self, bom_ref: &str, depends_on: &[String]) {
+        let idx = match self.dependencies.iter().position(|(r, _)| r == bom_ref) {
+            Some(idx) => idx,
+            None => {
+                self.dependencies.push((bom_ref.to_string(), vec![]));
+                self.dependencies.len() - 1
+            }
+        };
+        for dep in depends_on {
+            if !self.dependencies[idx].1.contains(dep) {
+                self.dependencies[idx].1.push(dep.to_string());
+            }
+        }
+    }
+
+    pub fn merge(self: &mut Self, other: Bom) {
+        // bom-refs of `other` renamed because another manifest already uses them
+        let mut renamed: HashMap<String, String> = HashMap::new();
+        for component in other.components {
+            let bom_ref = component.bom_ref.to_string();
+            let added = self.add_component(component);
+            if added != bom_ref {
+                renamed.insert(bom_ref, added);
+            }
+        }
+        let rename = |r: &String| renamed.get(r).unwrap_or(r).to_string();
+        for (bom_ref, depends_on) in other.dependencies.iter() {
+            let depends_on: Vec<String> = depends_on.iter().map(rename).collect();
+            self.add_dependency(rename(bom_ref).as_str(), &depends_on);
+        }
+        for bom_ref in other.direct.iter().map(rename) {
+            if !self.direct.contains(&bom_ref) {
+                self.direct.push(bom_ref);
+            }
+        }
+        self.vulnerabilities.extend(other.vulnerabilities);
+    }
+
+    /// bom-ref -> bom-refs depending on it
+    pub fn parents(self: &Self) -> HashMap<&str, Vec<&str>> {
+        let mut parents: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (bom_ref, depends_on) in self.dependencies.iter() {
+            for dep in depends_on.iter() {
+                parents.entry(dep.as_str()).or_default().push(bom_ref.as_str());
+            }
+        }
+
+        parents
+    }
+
+    /// shortest chain from a direct dependency down to `bom_ref`, both ends included
+    ///
+    /// a component is direct when the input flags it so, or when nothing depends on it.
+    /// empty when `bom_ref` is not part of the graph
+    pub fn shortest_path(self: &Self, parents: &HashMap<&str, Vec<&str>>, bom_ref: &str) -> Vec<String> {
+        let is_direct = |r: &str| {
+            self.direct.iter().any(|d| d == r) || parents.get(r).map_or(true, |p| p.is_empty())
+        };
+        // breadth first up the parents, the first direct one found is the closest
+        let mut child: HashMap<&str, &str> = HashMap::new();
+        let mut seen: HashSet<&str> = HashSet::from([bom_ref]);
+        let mut queue: VecDeque<&str> = VecDeque::from([bom_ref]);
+        while let Some(current) = queue.pop_front() {
+            if is_direct(current) {
+                let mut path = vec![current.to_string()];
+                let mut node = current;
+                while let Some(next) = child.get(node) {
+                    path.push(next.to_string());
+                    node = next;
+                }
+                return path;
+            }
+            for parent in parents.get(current).into_iter().flatten() {
+                if seen.insert(parent) {
+                    child.insert(parent, current);
+                    queue.push_back(parent);
+                }
+            }
+        }
+
+        vec![]
+    }
+
+    pub fn component(self: &Self, bom_ref: &str) -> Option<&Component> {
+        self.components.iter().find(|c| c.bom_ref == bom_ref)
+    }
+
+    /// component of a package reported by name, gitlab style or cyclonedx style, in the manifest at `location`
+    ///
+    /// a component without a manifest matches any location, one of the same manifest is preferred
+    pub fn find(self: &Self, location: &str, name: &str, version: &str) -> Option<&Component> {
+        self.components
+            .iter()
+            .filter(|c| c.version == version && (c.package_name() == name || c.name == name))
+            .filter(|c| c.location == location || c.location.is_empty() || location.is_empty())
+            .min_by_key(|c| c.location != location)
+    }
+
+    /// cyclonedx 1.5 document of the merged components with `vex` as its vulnerabilities
+    pub fn to_value(self: &Self, vex: &[Vex], project: &str, serial: &str) -> Value {
+        let mut metadata = Map::new();
+        metadata.insert("timestamp".to_string(), json!(utc_now()));
+        metadata.insert(
+            "tools".to_string(),
+            json!({ "components": [{
+                "type": "application",
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            }] }),
+        );
+        if !project.is_empty() {
+            metadata.insert(
+                "component".to_string(),
+                json!({ "type": "application", "bom-ref": project, "name": project }),
+            );
+        }
+
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": SPEC_VERSION,
+            "serialNumber": format!("urn:uuid:{}", uuid(serial)),
+            "version": 1,
+            "metadata": metadata,
+            "components": self.components.iter().map(|c| c.to_value()).collect::<Vec<Value>>(),
+            "dependencies": self
+                .dependencies
+                .iter()
+                .map(|(r, deps)| json!({ "ref": r, "dependsOn": deps }))
+                .collect::<Vec<Value>>(),
+            "vulnerabilities": vex.iter().map(|v| v.to_value()).collect::<Vec<Value>>(),
+        })
+    }
+}
+
+/// stable uuid shaped id from a seed, the same pipeline keeps the same serial number
+pub fn uuid(seed: &str) -> String {
+    let hex = format!("{}{}", fingerprint(&[seed]), fingerprint(&[seed, "sbom"]));
+    format!(
+        "{}-{}-4{}-8{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[13..16],
+        &hex[17..20],
+        &hex[20..32]
+    )
+}
+
+/// rfc 3339 utc timestamp, days to civil date after howard hinnant
+pub fn utc_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs()) as i64;
+    let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(name: &str, location: &str) -> Component {
+        Component {
+            name: name.to_string(),
+            version: String::from("1.0.0"),
+            purl: format!("pkg:npm/{name}@1.0.0"),
+            location: location.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_one_graph_per_manifest() {
+        let mut bom = Bom::default();
+        let a_top = bom.add_component(component("top", "a/package-lock.json"));
+        let a_shared = bom.add_component(component("shared", "a/package-lock.json"));
+        bom.add_dependency(a_top.as_str(), &[a_shared.to_string()]);
+        let b_shared = bom.add_component(component("shared", "b/package-lock.json"));
+        assert_ne!(a_shared, b_shared);
+        assert_eq!(bom.add_component(component("shared", "b/package-lock.json")), b_shared);
+
+        let found = bom.find("b/package-lock.json", "shared", "1.0.0").unwrap();
+        assert_eq!(found.bom_ref, b_shared);
+        let parents = bom.parents();
+        assert_eq!(bom.shortest_path(&parents, found.bom_ref.as_str()), vec![b_shared.to_string()]);
+        assert_eq!(bom.shortest_path(&parents, a_shared.as_str()), vec![a_top, a_shared]);
+        assert!(bom.find("c/package-lock.json", "shared", "1.0.0").is_none());
+    }
+}
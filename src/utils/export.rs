@@ -0,0 +1,128 @@
+//! csv / jsonl export, one row per finding
+
+use std::fs;
+
+use serde_json::{json, Value};
+
+use crate::{conf::setting::*, parser::base::Finding};
+
+/// export columns, the order is part of the format and must only be appended to
+///
+/// - `project_id`: `CI_PROJECT_ID`
+/// - `pipeline_id`: `CI_PIPELINE_ID`
+/// - `commit_sha`: `CI_COMMIT_SHA`
+/// - `ref`: `CI_COMMIT_REF_NAME`
+/// - `engine`: SAST / SCA / Secret ...
+/// - `severity`: severity reported by the analyzer
+/// - `name`: vul title
+/// - `identifiers`: cve / cwe / rule ids, joined by `;`
+/// - `file`: source file or manifest
+/// - `line`: start line, 0 when unknown
+/// - `dependency`: `name:version` for dependency findings, empty otherwise
+/// - `fingerprint`: stable id of the finding
+pub const COLUMNS: [&str; 12] = [
+    "project_id",
+    "pipeline_id",
+    "commit_sha",
+    "ref",
+    "engine",
+    "severity",
+    "name",
+    "identifiers",
+    "file",
+    "line",
+    "dependency",
+    "fingerprint",
+];
+
+fn to_row(finding: &Finding) -> Vec<String> {
+    vec![
+        CI_PROJECT_ID.to_string(),
+        CI_PIPELINE_ID.to_string(),
+        CI_COMMIT_SHA.to_string(),
+        CI_COMMIT_REF_NAME.to_string(),
+        finding.engine.to_string(),
+        finding.severity.to_string(),
+        finding.name.to_string(),
+        finding.identifiers.join(";"),
+        finding.file.to_string(),
+        finding.line.to_string(),
+        finding.dependency.to_string(),
+        finding.fingerprint.to_string(),
+    ]
+}
+
+// rfc 4180 quoting
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn to_csv(findings: &[Finding]) -> String {
+    let mut lines: Vec<String> = vec![COLUMNS.join(",")];
+    for finding in findings {
+        let row: Vec<String> = to_row(finding).iter().map(|v| csv_field(v)).collect();
+        lines.push(row.join(","));
+    }
+
+    lines.join("\r\n") + "\r\n"
+}
+
+pub fn to_jsonl(findings: &[Finding]) -> String {
+    let mut lines: Vec<String> = vec![];
+    for finding in findings {
+        let mut obj = json!({});
+        for (col, value) in COLUMNS.iter().zip(to_row(finding)) {
+            obj[*col] = match *col {
+                "line" => json!(finding.line),
+                "identifiers" => json!(finding.identifiers),
+                _ => Value::String(value),
+            };
+        }
+        lines.push(obj.to_string());
+    }
+
+    lines.iter().map(|l| format!("{l}\n")).collect()
+}
+
+/// write findings to `fpath`, the format is taken from the extension (`.csv` or `.jsonl`)
+pub fn write(findings: &[Finding], fpath: &str) -> bool {
+    let cont = if fpath.ends_with(".csv") {
+        to_csv(findings)
+    } else if fpath.ends_with(".jsonl") {
+        to_jsonl(findings)
+    } else {
+        log::error!("not support export format: {fpath}");
+        return false;
+    };
+
+    match fs::write(fpath, cont) {
+        Ok(_) => {
+            log::info!("exported {} findings to {fpath}", findings.len());
+            true
+        }
+        Err(e) => {
+            log::error!("failed to export {fpath}: {e}");
+            false
+        }
+    }
+}
+
+/// export paths configured by `EXPORT_FORMAT`, e.g. `$EXPORT_DIR/sast-findings.csv`
+pub fn export_paths(engine: &str) -> Vec<String> {
+    EXPORT_FORMAT
+        .split(",")
+        .map(|fmt| fmt.trim())
+        .filter(|fmt| !fmt.is_empty())
+        .map(|fmt| {
+            format!(
+                "{}/{}-findings.{fmt}",
+                EXPORT_DIR.as_str(),
+                engine.to_lowercase()
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,131 @@
+//! quality gate, fails the job (non-zero exit) when findings break the configured policy
+//!
+//! - `GATE_SEVERITY`: fail on findings at or above this severity (critical, high, medium, low or
+//!   info); unset or any other value disables it
+//! - `GATE_FAIL_ON_KEV`: fail on cisa kev listed findings regardless of their severity
+
+use std::sync::{Mutex, Once};
+
+use crate::{
+    conf::setting::{GATE_FAIL_ON_KEV, GATE_SEVERITY},
+    parser::base::Finding,
+};
+
+// violations of every engine run in this process
+static VIOLATIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static VALIDATE: Once = Once::new();
+
+fn level(severity: &str) -> i32 {
+    match severity.to_lowercase().as_str() {
+        "critical" => 4,
+        "high" => 3,
+        "medium" => 2,
+        "low" => 1,
+        _ => 0,
+    }
+}
+
+// level of a `GATE_SEVERITY` value, None when it names no severity
+fn threshold(min_severity: &str) -> Option<i32> {
+    match min_severity.trim().to_lowercase().as_str() {
+        "critical" | "high" | "medium" | "low" => Some(level(min_severity.trim())),
+        "info" => Some(0),
+        _ => None,
+    }
+}
+
+/// violations of the policy, one line per finding
+pub fn check(findings: &[Finding], min_severity: &str, fail_on_kev: bool) -> Vec<String> {
+    let min_level = threshold(min_severity);
+    let mut violations: Vec<String> = vec![];
+    for finding in findings.iter() {
+        let id = finding.identifiers.first().unwrap_or(&finding.name);
+        let location = if finding.dependency.is_empty() {
+            finding.file.to_string()
+        } else {
+            format!("{} ({})", finding.dependency, finding.file)
+        };
+        if fail_on_kev && finding.kev {
+            violations.push(format!("[{}] kev listed {id}: {location}", finding.engine));
+        } else if min_level.map_or(false, |min| level(finding.severity.as_str()) >= min) {
+            violations.push(format!(
+                "[{}] {} {id}: {location}",
+                finding.engine,
+                finding.severity.to_lowercase()
+            ));
+        }
+    }
+
+    violations
+}
+
+/// check the findings against the configured policy and remember the violations
+pub fn evaluate(findings: &[Finding]) {
+    VALIDATE.call_once(|| {
+        if !GATE_SEVERITY.is_empty() && threshold(GATE_SEVERITY.as_str()).is_none() {
+            log::error!("invalid GATE_SEVERITY: {}, the severity rule is disabled", GATE_SEVERITY.as_str());
+        }
+    });
+    let violations = check(findings, GATE_SEVERITY.as_str(), *GATE_FAIL_ON_KEV);
+    for violation in violations.iter() {
+        log::error!("quality gate: {violation}");
+    }
+    VIOLATIONS.lock().unwrap().extend(violations);
+}
+
+/// process exit code, 1 when any evaluated engine broke the policy
+pub fn exit_code() -> i32 {
+    let violations = VIOLATIONS.lock().unwrap();
+    if violations.is_empty() {
+        0
+    } else {
+        log::error!("quality gate failed: {} violations", violations.len());
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(severity: &str, kev: bool) -> Finding {
+        Finding {
+            engine: String::from("sca"),
+            name: format!("{severity} vul"),
+            severity: severity.to_string(),
+            identifiers: vec![format!("CVE-{severity}")],
+            file: String::from("package-lock.json"),
+            dependency: String::from("lodash:4.17.20"),
+            kev: kev,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_severity_threshold() {
+        let findings = vec![finding("Critical", false), finding("High", false), finding("Low", false), finding("Unknown", false)];
+        assert_eq!(check(&findings, "HIGH", false).len(), 2);
+        assert_eq!(check(&findings, "low", false).len(), 3);
+        assert_eq!(check(&findings, "info", false).len(), 4);
+        assert_eq!(
+            check(&findings, "critical", false),
+            vec![String::from("[sca] critical CVE-Critical: lodash:4.17.20 (package-lock.json)")]
+        );
+    }
+
+    #[test]
+    fn test_invalid_severity_disables_the_rule() {
+        let findings = vec![finding("Critical", false), finding("Low", false)];
+        assert!(check(&findings, "", false).is_empty());
+        assert!(check(&findings, "hgih", false).is_empty());
+    }
+
+    #[test]
+    fn test_kev() {
+        let findings = vec![finding("Low", true), finding("Low", false)];
+        assert_eq!(check(&findings, "", true), vec![String::from("[sca] kev listed CVE-Low: lodash:4.17.20 (package-lock.json)")]);
+        assert!(check(&findings, "", false).is_empty());
+        // listed once even when the severity breaks the policy too
+        assert_eq!(check(&findings, "low", true).len(), 2);
+    }
+}
@@ -38,6 +38,28 @@ impl Display for Issue {
 
 const EXCLUDED_USER_IDS: [&str; 3] = ["1", "61", "113"];
 
+/// web link to a repository file at the scanned commit, falls back to the branch
+pub fn blob_url(fpath: &str, line: u64) -> String {
+    let ref_ = if !CI_COMMIT_SHA.is_empty() {
+        CI_COMMIT_SHA.as_str()
+    } else if !CI_COMMIT_REF_NAME.is_empty() {
+        CI_COMMIT_REF_NAME.as_str()
+    } else {
+        "HEAD"
+    };
+    blob_url_at(ref_, fpath, line)
+}
+
+/// web link to a repository file at `ref_`, e.g. the commit a history scan found a secret in
+pub fn blob_url_at(ref_: &str, fpath: &str, line: u64) -> String {
+    let url = format!("{}/-/blob/{ref_}/{}", CI_PROJECT_URL.as_str(), fpath.trim_start_matches("./"));
+    if line > 0 {
+        format!("{url}#L{line}")
+    } else {
+        url
+    }
+}
+
 lazy_static! {
     static ref DEFAULT_HEADERS: HeaderMap = {
         let mut m = HeaderMap::new();
@@ -69,15 +91,61 @@ pub async fn new_issue(issue: &Issue) -> Result<String, Error> {
     println!("new_issue url: {url}, body: {body}, {}", body.len());
     // return Ok(String::new());
 
-    let cont = GITLAB_CLIENT
-        .post(format!("{url}"))
+    let resp = GITLAB_CLIENT.post(format!("{url}")).body(body).send().await?;
+    let status = resp.status();
+    let cont = resp.text().await?;
+
+    if status.is_success() {
+        println!("new_issue resp: {cont}");
+    } else {
+        log::error!("new_issue failed: {status}, {cont}");
+    }
+    Ok(cont)
+}
+
+// issue评论
+pub async fn new_issue_note<T>(project_id: &T, issue_iid: &T, note: &str) -> Result<String, Error>
+where
+    T: ?Sized + std::fmt::Debug,
+{
+    let url = format!(
+        "{}/projects/{:?}/issues/{:?}/notes",
+        GITLAB_URL_PREFIX.as_str(),
+        project_id,
+        issue_iid
+    )
+    .replace('"', "");
+
+    let body = serde_urlencoded::to_string([("body", note)]).unwrap();
+    let resp = GITLAB_CLIENT
+        .post(url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
         .body(body)
         .send()
-        .await?
-        .text()
         .await?;
+    let status = resp.status();
+    let cont = resp.text().await?;
+
+    if !status.is_success() {
+        log::error!("new_issue_note failed: {status}, {cont}");
+    }
+    Ok(cont)
+}
+
+/// create the issue and append `notes` to it in order, for reports too large for one description
+pub async fn new_issue_with_notes(issue: &Issue, notes: &[String]) -> Result<String, Error> {
+    let cont = new_issue(issue).await?;
+    let json: Value = serde_json::from_str(cont.as_str()).unwrap_or_default();
+    let Some(iid) = json["iid"].as_u64() else {
+        log::error!("no issue iid in response, skip {} notes", notes.len());
+        return Ok(cont);
+    };
+
+    for (idx, note) in notes.iter().enumerate() {
+        println!("new issue note {}/{}...", idx + 1, notes.len());
+        new_issue_note(issue.project_id.as_str(), iid.to_string().as_str(), note.as_str()).await?;
+    }
 
-    println!("new_issue resp: {cont}");
     Ok(cont)
 }
 
@@ -278,9 +346,11 @@ pub async fn new_mr_comment(comment: String) -> Result<String, Error> {
     )
     .replace('\"', "");
 
+    let body = serde_urlencoded::to_string([("body", comment)]).unwrap();
     let resp = GITLAB_CLIENT
         .post(url)
-        .body(comment)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(body)
         .send()
         .await?
         .text()
@@ -288,3 +358,125 @@ pub async fn new_mr_comment(comment: String) -> Result<String, Error> {
 
     Ok(resp)
 }
+
+/// commit `files` (path, new content) to `branch` in one commit, a non empty `start_sha` rebuilds the
+/// branch on that commit and creates it when missing
+pub async fn new_commit(
+    project_id: &str,
+    branch: &str,
+    message: &str,
+    files: &[(String, String)],
+    start_sha: &str,
+) -> Result<String, Error> {
+    let url = format!(
+        "{}/projects/{}/repository/commits",
+        GITLAB_URL_PREFIX.as_str(),
+        project_id
+    );
+
+    let actions: Vec<Value> = files
+        .iter()
+        .map(|(path, content)| {
+            serde_json::json!({ "action": "update", "file_path": path, "content": content })
+        })
+        .collect();
+    let mut body = serde_json::json!({
+        "branch": branch,
+        "commit_message": message,
+        "actions": actions,
+    });
+    if !start_sha.is_empty() {
+        body["start_sha"] = Value::from(start_sha);
+        body["force"] = Value::from(true);
+    }
+    let resp = GITLAB_CLIENT.post(url).json(&body).send().await?;
+    let status = resp.status();
+    let cont = resp.text().await?;
+
+    if !status.is_success() {
+        log::error!("new_commit failed: {status}, {cont}");
+    }
+    Ok(cont)
+}
+
+// 新建mr
+pub async fn new_merge_request(
+    project_id: &str,
+    source_branch: &str,
+    target_branch: &str,
+    title: &str,
+    description: &str,
+) -> Result<String, Error> {
+    let url = format!(
+        "{}/projects/{}/merge_requests",
+        GITLAB_URL_PREFIX.as_str(),
+        project_id
+    );
+
+    let body = serde_urlencoded::to_string([
+        ("source_branch", source_branch),
+        ("target_branch", target_branch),
+        ("title", title),
+        ("description", description),
+        ("labels", "sec,SCA"),
+        ("remove_source_branch", "true"),
+    ])
+    .unwrap();
+    let resp = GITLAB_CLIENT
+        .post(url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(body)
+        .send()
+        .await?;
+    let status = resp.status();
+    let cont = resp.text().await?;
+
+    if !status.is_success() {
+        log::error!("new_merge_request failed: {status}, {cont}");
+    }
+    Ok(cont)
+}
+
+// 打开的mr
+pub async fn list_merge_requests(project_id: &str, source_branch: &str, target_branch: &str) -> Result<String, Error> {
+    let query = serde_urlencoded::to_string([
+        ("state", "opened"),
+        ("source_branch", source_branch),
+        ("target_branch", target_branch),
+    ])
+    .unwrap();
+    let url = format!(
+        "{}/projects/{}/merge_requests?{}",
+        GITLAB_URL_PREFIX.as_str(),
+        project_id,
+        query
+    );
+    let body = GITLAB_CLIENT.get(url).send().await?.text().await?;
+
+    Ok(body)
+}
+
+// 更新mr
+pub async fn update_merge_request(project_id: &str, iid: u64, title: &str, description: &str) -> Result<String, Error> {
+    let url = format!(
+        "{}/projects/{}/merge_requests/{}",
+        GITLAB_URL_PREFIX.as_str(),
+        project_id,
+        iid
+    );
+
+    let body = serde_urlencoded::to_string([("title", title), ("description", description)]).unwrap();
+    let resp = GITLAB_CLIENT
+        .put(url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(body)
+        .send()
+        .await?;
+    let status = resp.status();
+    let cont = resp.text().await?;
+
+    if !status.is_success() {
+        log::error!("update_merge_request failed: {status}, {cont}");
+    }
+    Ok(cont)
+}
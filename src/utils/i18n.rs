@@ -0,0 +1,209 @@
+//! message catalogs for report text, selected by env `SEC_LANG` or `--lang`
+//!
+//! placeholders are written as `{name}` and filled by [`tf`]
+//!
+//! without a lang the report reads as it always did: the title and the table headers in english,
+//! everything else in zh-CN
+
+use crate::conf::setting::LANG;
+
+pub const ZH_CN: &str = "zh-CN";
+pub const EN: &str = "en";
+
+const ZH_CN_MESSAGES: &[(&str, &str)] = &[
+    ("report.title", "{engine} 扫描报告"),
+    ("report.intro", "报告详情（如果表格中存在重复内容请忽略）如下："),
+    ("col.id", "编号"),
+    ("col.title", "标题"),
+    ("col.severity", "风险等级"),
+    ("col.cve", "漏洞编号"),
+    ("col.location", "位置"),
+    ("col.description", "描述"),
+    ("col.dependency", "组件"),
+    ("col.solution", "修复方案"),
+    ("col.findings", "问题数"),
+    ("col.affected", "影响清单数"),
+    ("col.other", "其他"),
+    ("col.priority", "优先级"),
+    ("col.license", "许可证"),
+    ("col.decision", "结论"),
+    ("col.from", "当前版本"),
+    ("col.to", "目标版本"),
+    ("col.param", "参数"),
+    ("col.evidence", "证据"),
+    ("col.cis", "CIS 基线"),
+    ("report.split", "报告内容过大，已按位置拆分到下方评论中，完整报告见：{url}"),
+    ("report.split.part", "（{n}/{total}）"),
+    ("severity.critical", "严重"),
+    ("severity.high", "高"),
+    ("severity.medium", "中"),
+    ("severity.low", "低"),
+    ("severity.info", "可忽略"),
+    ("sca.solution.upgrade", "建议升级至{version}或更高版本"),
+    ("sca.solution.default", "升级到稳定修复版本"),
+    ("sca.path", "引入路径：{path}"),
+    ("license.allow", "允许"),
+    ("license.review", "需评审"),
+    ("license.deny", "禁止使用"),
+    ("license.unknown", "未知许可证"),
+    ("secret.detector", "{detector} 密钥泄露"),
+    ("secret.verified", "已验证有效"),
+    ("secret.unverified", "未验证"),
+    ("dast.scanned", "共扫描 {count} 个资源，{hosts} 个主机（{methods}）"),
+    ("container.base_image", "建议升级基础镜像："),
+    ("container.base_image.upgrade", "升级基础镜像至 {image}"),
+    ("container.solution.default", "升级基础镜像或系统软件包到修复版本"),
+    ("remediate.mr.title", "升级 {count} 个存在漏洞的组件"),
+    ("remediate.mr.intro", "以下组件已升级至修复全部已知漏洞的最低版本，请确认兼容性后合并："),
+    ("remediate.mr.skipped", "以下组件未能自动升级（间接依赖或未在清单中声明），需手动处理："),
+    ("remediate.note", "已创建组件升级合并请求：{url}"),
+    ("remediate.mr.related", "关联 #{iid}"),
+    ("sca.kev.warning", "⚠️ {count} 个组件存在 CISA KEV 已知被在野利用的漏洞（标记为 KEV），请最优先修复"),
+    ("llm.system", ZH_SYSTEM),
+    (
+        "llm.simplify",
+        "{content}, 简化但不丢失这句话的意思，要求输出内容长度最大化缩小, 输出内容长度必须小于原输入内容长度",
+    ),
+    (
+        "llm.sca_optimize",
+        "{content}, 上述内容为一个markdown格式的sca报告，使用markdown逐行解析记录，分析合并其中相同的组件及其风险，descriptioin可以使用中文优化一下，尽量做到比原始description内容短，并将优化后的markdown文件返回给我, 要求不改变原文件格式，仅优化其中的内容",
+    ),
+];
+
+const EN_MESSAGES: &[(&str, &str)] = &[
+    ("report.title", "{engine} scan report"),
+    ("report.intro", "Report details (please ignore duplicated rows):"),
+    ("col.id", "id"),
+    ("col.title", "title"),
+    ("col.severity", "severity"),
+    ("col.cve", "cve"),
+    ("col.location", "location"),
+    ("col.description", "description"),
+    ("col.dependency", "dependency"),
+    ("col.solution", "solution"),
+    ("col.findings", "findings"),
+    ("col.affected", "affected manifests"),
+    ("col.other", "other"),
+    ("col.priority", "priority"),
+    ("col.license", "license"),
+    ("col.decision", "decision"),
+    ("col.from", "from"),
+    ("col.to", "to"),
+    ("col.param", "parameter"),
+    ("col.evidence", "evidence"),
+    ("col.cis", "CIS benchmark"),
+    ("report.split", "The report is too large, details are split into the comments below by location. Full report: {url}"),
+    ("report.split.part", " ({n}/{total})"),
+    ("severity.critical", "Critical"),
+    ("severity.high", "High"),
+    ("severity.medium", "Medium"),
+    ("severity.low", "Low"),
+    ("severity.info", "Info"),
+    ("sca.solution.upgrade", "Upgrade to {version} or later"),
+    ("sca.solution.default", "Upgrade to a stable fixed version"),
+    ("sca.path", "via: {path}"),
+    ("license.allow", "allowed"),
+    ("license.review", "needs review"),
+    ("license.deny", "denied"),
+    ("license.unknown", "unknown license"),
+    ("secret.detector", "{detector} secret"),
+    ("secret.verified", "verified"),
+    ("secret.unverified", "unverified"),
+    ("dast.scanned", "{count} resources scanned on {hosts} hosts ({methods})"),
+    ("container.base_image", "Suggested base image upgrades:"),
+    ("container.base_image.upgrade", "Upgrade the base image to {image}"),
+    ("container.solution.default", "Upgrade the base image or the os package to a fixed version"),
+    ("remediate.mr.title", "Bump {count} vulnerable dependencies"),
+    ("remediate.mr.intro", "The dependencies below are upgraded to the lowest version fixing every known vulnerability, check compatibility before merging:"),
+    ("remediate.mr.skipped", "These dependencies could not be bumped automatically (transitive or not declared in the manifest), fix them manually:"),
+    ("remediate.note", "Dependency bump merge request opened: {url}"),
+    ("remediate.mr.related", "Related to #{iid}"),
+    ("sca.kev.warning", "⚠️ {count} dependencies have vulnerabilities known to be exploited in the wild (CISA KEV, marked KEV), fix them first"),
+    ("llm.system", EN_SYSTEM),
+    (
+        "llm.simplify",
+        "{content}, simplify this sentence without losing its meaning, the output must be as short as possible and shorter than the input",
+    ),
+    (
+        "llm.sca_optimize",
+        "{content}, the content above is an sca report in markdown. Parse it row by row, merge identical dependencies and their risks, make the description shorter than the original, and return the optimized markdown. Keep the format unchanged and only optimize the content",
+    ),
+];
+
+const ZH_SYSTEM: &str = "你是一名安全专家Qwen，精通安全的各个方向，包括代码审计、渗透测试、数据安全、安全事件、安全溯源等。你可以深入理解提问问题，并根据问题进行答复，在进行真正应答前，根据提问评估答复内容匹配程度，如果匹配不佳，结合评估的结果重复生成并继续评估和优化，最多重复5轮，以匹配度最高的作为答复真正进行应答。要求使用中文答复，内容简洁无多余空白字符，禁止添加多余内容。";
+
+const EN_SYSTEM: &str = "You are Qwen, a security expert skilled in every area of security, including code audit, penetration testing, data security, incident response and forensics. Understand the question in depth before answering. Before giving the real answer, rate how well your draft matches the question; if the match is poor, regenerate and refine based on that rating, at most 5 rounds, and answer with the best match. Answer in English, keep it concise without extra whitespace and never add unrelated content.";
+
+/// normalize a locale like `en_US.UTF-8` or `zh` to a supported catalog name
+pub fn normalize(lang: &str) -> &'static str {
+    let lang = lang.to_lowercase().replace('_', "-");
+    if lang.starts_with("en") {
+        EN
+    } else {
+        if !lang.is_empty() && !lang.starts_with("zh") {
+            log::warn!("not support lang: {lang}, fallback to {ZH_CN}");
+        }
+        ZH_CN
+    }
+}
+
+pub fn set_lang(lang: &str) {
+    *LANG.lock().unwrap() = normalize(lang).to_string();
+}
+
+pub fn lang() -> &'static str {
+    normalize(LANG.lock().unwrap().as_str())
+}
+
+// keys kept in english when no lang is set
+fn english_by_default(key: &str) -> bool {
+    LANG.lock().unwrap().is_empty() && (key == "report.title" || key.starts_with("col."))
+}
+
+fn lookup(messages: &[(&str, &'static str)], key: &str) -> Option<&'static str> {
+    messages.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+/// message of `key` in the current lang, falls back to zh-CN and then to the key itself
+pub fn t(key: &str) -> String {
+    let messages = match lang() {
+        EN => EN_MESSAGES,
+        _ if english_by_default(key) => EN_MESSAGES,
+        _ => ZH_CN_MESSAGES,
+    };
+    lookup(messages, key)
+        .or(lookup(ZH_CN_MESSAGES, key))
+        .unwrap_or(key)
+        .to_string()
+}
+
+/// [`t`] with `{name}` placeholders replaced
+pub fn tf(key: &str, args: &[(&str, &str)]) -> String {
+    let mut msg = t(key);
+    for (name, value) in args {
+        msg = msg.replace(format!("{{{name}}}").as_str(), value);
+    }
+
+    msg
+}
+
+/// localized severity name
+pub fn severity(severity: &str) -> String {
+    match severity.to_lowercase().as_str() {
+        "critical" | "high" | "medium" | "low" => {
+            t(format!("severity.{}", severity.to_lowercase()).as_str())
+        }
+        _ => t("severity.info"),
+    }
+}
+
+/// localized markdown table header, `cols` are catalog keys without the `col.` prefix
+pub fn table_header(cols: &[&str]) -> String {
+    let title: Vec<String> = cols
+        .iter()
+        .map(|col| t(format!("col.{col}").as_str()))
+        .collect();
+    let sep = ["--"].repeat(cols.len());
+
+    format!("|{}|\n|{}|", title.join("|"), sep.join("|"))
+}
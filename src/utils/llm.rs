@@ -8,6 +8,7 @@ use serde_json::json;
 use gradio::{PredictionInput, ClientOptions};
 
 use crate::conf::setting::{AI_HOST, AI_TOKEN, HG_AI_CLIENT};
+use crate::utils::i18n;
 
 
 // for huggingface 在线模型
@@ -35,7 +36,8 @@ lazy_static! {
 }
 
 // const PROMPT: &'static str =  "as a secruity experter, you can analyze source code, verify and check vuln. when i give you a risk report which formatted as markdown table and part of source code, you must read every row, and check where the risk exists, if yes, you can simplify the risk info and translate to chinese, and append a new col named solution which is a well-done suggestion for processing the risk";
-const PROMPT: &'static str = "你是一名安全专家Qwen，精通安全的各个方向，包括代码审计、渗透测试、数据安全、安全事件、安全溯源等。你可以深入理解提问问题，并根据问题进行答复，在进行真正应答前，根据提问评估答复内容匹配程度，如果匹配不佳，结合评估的结果重复生成并继续评估和优化，最多重复5轮，以匹配度最高的作为答复真正进行应答。要求使用中文答复，内容简洁无多余空白字符，禁止添加多余内容。";
+// moved to i18n catalog `llm.system`
+// const PROMPT: &'static str = "你是一名安全专家Qwen，精通安全的各个方向，包括代码审计、渗透测试、数据安全、安全事件、安全溯源等。你可以深入理解提问问题，并根据问题进行答复，在进行真正应答前，根据提问评估答复内容匹配程度，如果匹配不佳，结合评估的结果重复生成并继续评估和优化，最多重复5轮，以匹配度最高的作为答复真正进行应答。要求使用中文答复，内容简洁无多余空白字符，禁止添加多余内容。";
 // const MODEL: &'static str = "gemma2:2b";
 const MODEL: &'static str = "qwen2.5:3b-instruct";
 // const MODEL: &'static str = "phi3.5:3.8b-mini-instruct-q4_0";
@@ -101,7 +103,7 @@ pub async fn ask_hg_ai(content: &str) -> Result<String, std::fmt::Error> {
       "data": [
         content,
         [],
-        PROMPT
+        i18n::t("llm.system")
       ],
       "event_data":null,
       "fn_index":0,
@@ -155,10 +157,10 @@ pub async fn ask_ai(content: &str) -> Result<String, std::fmt::Error> {
         .body(serde_json::to_string(&data).unwrap())
         .send()
         .await
-        .unwrap()
+        .map_err(|_| Error)?
         .text()
         .await
-        .unwrap();
+        .map_err(|_| Error)?;
     log::debug!("response: {resp}");
 
     Ok(resp)
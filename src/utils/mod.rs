@@ -1,3 +1,18 @@
 pub mod gitlab;
 // pub mod xlsx;
 pub mod llm;
+pub mod codequality;
+pub mod export;
+pub mod template;
+pub mod i18n;
+pub mod render;
+pub mod mask;
+pub mod version;
+pub mod cvss;
+pub mod cvedb;
+pub mod threat;
+pub mod gate;
+pub mod spdx;
+pub mod spdx_export;
+pub mod cyclonedx;
+pub mod remediate;
@@ -0,0 +1,533 @@
+//! dependency bump merge requests for fixable sca findings
+//!
+//! the manifests in `TARGET` (pom.xml, package.json, requirements*.txt, go.mod) are rewritten
+//! in place to the minimal safe version, only the version text changes so formatting survives.
+//! the result is pushed as a branch, commit and merge request, or printed as a patch on dry run
+
+use std::{cmp::Ordering, fs, path::Path};
+
+use serde_json::Value;
+
+use crate::{
+    conf::setting::*,
+    utils::{
+        gitlab, i18n, render,
+        version::{self, Scheme},
+    },
+};
+
+/// a dependency with a known minimal safe version
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Upgrade {
+    pub location: String,
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    pub scheme: Scheme,
+    pub cves: Vec<String>,
+}
+
+/// rewritten manifest, `path` relative to `TARGET`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Patch {
+    pub path: String,
+    pub old: String,
+    pub new: String,
+    pub upgrades: Vec<Upgrade>,
+}
+
+/// manifest declaring the dependencies of a reported location (often a lock file)
+pub fn manifest_for(location: &str) -> Option<String> {
+    let (dir, name) = match location.rsplit_once('/') {
+        Some((dir, name)) => (format!("{dir}/"), name),
+        None => (String::new(), location),
+    };
+    let lower = name.to_lowercase();
+    let manifest = match lower.as_str() {
+        "package.json" | "package-lock.json" | "npm-shrinkwrap.json" | "yarn.lock"
+        | "pnpm-lock.yaml" => "package.json",
+        "pom.xml" => "pom.xml",
+        "go.mod" | "go.sum" => "go.mod",
+        _ if lower.starts_with("requirements") && lower.ends_with(".txt") => name,
+        _ => return None,
+    };
+
+    Some(format!("{dir}{manifest}"))
+}
+
+// plain version text, no ranges, placeholders or tags like `latest`
+fn is_version(text: &str) -> bool {
+    text.trim_start_matches('v').chars().next().map_or(false, |c| c.is_ascii_digit())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ['.', '-', '+', '_'].contains(&c))
+}
+
+fn is_older(scheme: Scheme, current: &str, to: &str) -> bool {
+    is_version(current) && version::compare(scheme, current, to) == Ordering::Less
+}
+
+/// `"name": "^1.0.0"` entries of package.json, the range operator is kept
+pub fn bump_package_json(content: &str, name: &str, to: &str) -> Option<String> {
+    let key = format!("\"{name}\"");
+    let mut out = String::new();
+    let mut rest = content;
+    let mut changed = false;
+    while let Some(idx) = rest.find(key.as_str()) {
+        let (head, tail) = rest.split_at(idx + key.len());
+        out.push_str(head);
+        rest = tail;
+
+        let after_key = tail.trim_start();
+        let Some(after_colon) = after_key.strip_prefix(':').map(|s| s.trim_start()) else {
+            continue;
+        };
+        let Some(spec) = after_colon.strip_prefix('"') else {
+            continue;
+        };
+        let Some(len) = spec.find('"') else {
+            continue;
+        };
+        let spec = &spec[..len];
+        let start = tail.len() - after_colon.len() + 1;
+        let operator: String = spec.chars().take_while(|c| "^~=v ".contains(*c)).collect();
+        if is_older(Scheme::Npm, &spec[operator.len()..], to) {
+            out.push_str(&tail[..start]);
+            out.push_str(operator.as_str());
+            out.push_str(to);
+            rest = &tail[start + len..];
+            changed = true;
+        }
+    }
+    out.push_str(rest);
+
+    changed.then_some(out)
+}
+
+// byte range of the trimmed text inside the first `<tag>...</tag>` of `text`
+fn tag_range(text: &str, tag: &str) -> Option<(usize, usize)> {
+    let open = format!("<{tag}>");
+    let start = text.find(open.as_str())? + open.len();
+    let end = start + text[start..].find(format!("</{tag}>").as_str())?;
+    let inner = &text[start..end];
+    let lead = inner.len() - inner.trim_start().len();
+
+    Some((start + lead, start + lead + inner.trim().len()))
+}
+
+/// `<dependency>` version of `group/artifact` in pom.xml, following `${property}` versions
+pub fn bump_pom(content: &str, name: &str, to: &str) -> Option<String> {
+    let (group, artifact) = name
+        .split_once('/')
+        .or(name.split_once(':'))
+        .unwrap_or(("", name));
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    let mut pos = 0;
+    while let Some(idx) = content[pos..].find("<dependency>") {
+        let start = pos + idx;
+        let Some(len) = content[start..].find("</dependency>") else {
+            break;
+        };
+        let block = &content[start..start + len];
+        pos = start + len;
+
+        let value = |tag: &str| tag_range(block, tag).map(|(s, e)| &block[s..e]);
+        if value("artifactId") != Some(artifact) || (!group.is_empty() && value("groupId") != Some(group)) {
+            continue;
+        }
+        let Some((s, e)) = tag_range(block, "version") else {
+            // managed by a parent or a bom, nothing to change here
+            continue;
+        };
+        let current = &block[s..e];
+        if let Some(property) = current.strip_prefix("${").and_then(|p| p.strip_suffix('}')) {
+            if let Some((ps, pe)) = tag_range(content, property) {
+                ranges.push((ps, pe));
+            }
+        } else {
+            ranges.push((start + s, start + e));
+        }
+    }
+
+    ranges.sort();
+    ranges.dedup();
+    let mut out = content.to_string();
+    let mut changed = false;
+    // back to front so the earlier ranges stay valid
+    for (s, e) in ranges.into_iter().rev() {
+        if is_older(Scheme::Maven, &content[s..e], to) {
+            out.replace_range(s..e, to);
+            changed = true;
+        }
+    }
+
+    changed.then_some(out)
+}
+
+// pep 503 normalized name
+fn normalize_pypi(name: &str) -> String {
+    name.trim().to_lowercase().replace(['_', '.'], "-")
+}
+
+/// `name==1.0` (also `>=`, `~=`) lines of requirements.txt, extras and markers are kept
+pub fn bump_requirements(content: &str, name: &str, to: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut changed = false;
+    for line in content.split_inclusive('\n') {
+        // environment markers after `;` may compare versions too
+        let code = line.split(['#', ';']).next().unwrap_or("");
+        let end = code.find(|c: char| "=<>~!;[ \t\r\n".contains(c)).unwrap_or(code.len());
+        let version_at = ["==", "~=", ">="].iter().find_map(|op| code.find(op).map(|i| i + op.len()));
+        match version_at {
+            Some(start) if end > 0 && normalize_pypi(&code[..end]) == normalize_pypi(name) => {
+                let len = code[start..]
+                    .find(|c: char| " ,;\t\r\n".contains(c))
+                    .unwrap_or(code.len() - start);
+                let current = code[start..start + len].trim();
+                if is_older(Scheme::Pypi, current, to) {
+                    out.push_str(&line[..start]);
+                    out.push_str(to);
+                    out.push_str(&line[start + len..]);
+                    changed = true;
+                    continue;
+                }
+                out.push_str(line);
+            }
+            _ => out.push_str(line),
+        }
+    }
+
+    changed.then_some(out)
+}
+
+/// `module vX.Y.Z` requirements of go.mod, single line and block form.
+/// `replace` and `exclude` directives are left alone
+pub fn bump_go_mod(content: &str, name: &str, to: &str) -> Option<String> {
+    let to = if to.starts_with('v') { to.to_string() } else { format!("v{to}") };
+    let mut out = String::new();
+    let mut changed = false;
+    // directive of the `directive (` block the line is in
+    let mut block: Option<&str> = None;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let body = match block {
+            Some(_) if trimmed.starts_with(')') => {
+                block = None;
+                None
+            }
+            Some(directive) => (directive == "require").then_some(trimmed),
+            None => match trimmed.split_once('(') {
+                Some((directive, rest)) if rest.trim().is_empty() && !directive.trim().contains(' ') => {
+                    block = Some(directive.trim());
+                    None
+                }
+                _ => trimmed.strip_prefix("require ").map(|rest| rest.trim_start()),
+            },
+        };
+        let Some(body) = body else {
+            out.push_str(line);
+            continue;
+        };
+        let mut tokens = body.split_whitespace();
+        match (tokens.next(), tokens.next()) {
+            (Some(module), Some(current)) if module == name && is_older(Scheme::Go, current, to.as_str()) => {
+                let at = line.len() - body.len() + module.len();
+                let start = at + line[at..].find(current).unwrap_or(0);
+                out.push_str(&line[..start]);
+                out.push_str(to.as_str());
+                out.push_str(&line[start + current.len()..]);
+                changed = true;
+            }
+            _ => out.push_str(line),
+        }
+    }
+
+    changed.then_some(out)
+}
+
+fn bump(manifest: &str, content: &str, upgrade: &Upgrade) -> Option<String> {
+    let name = manifest.rsplit('/').next().unwrap_or(manifest).to_lowercase();
+    match name.as_str() {
+        "package.json" => bump_package_json(content, upgrade.name.as_str(), upgrade.to.as_str()),
+        "pom.xml" => bump_pom(content, upgrade.name.as_str(), upgrade.to.as_str()),
+        "go.mod" => bump_go_mod(content, upgrade.name.as_str(), upgrade.to.as_str()),
+        _ => bump_requirements(content, upgrade.name.as_str(), upgrade.to.as_str()),
+    }
+}
+
+/// patches of the manifests under `target`, and the upgrades that could not be applied
+pub fn plan(target: &str, upgrades: &[Upgrade]) -> (Vec<Patch>, Vec<(Upgrade, String)>) {
+    let mut patches: Vec<Patch> = vec![];
+    let mut skipped: Vec<(Upgrade, String)> = vec![];
+    for upgrade in upgrades.iter() {
+        let Some(manifest) = manifest_for(upgrade.location.as_str()) else {
+            skipped.push((upgrade.clone(), String::from("unsupported manifest")));
+            continue;
+        };
+        let idx = match patches.iter().position(|p| p.path == manifest) {
+            Some(idx) => idx,
+            None => match fs::read_to_string(Path::new(target).join(manifest.as_str())) {
+                Ok(cont) => {
+                    patches.push(Patch {
+                        path: manifest.to_string(),
+                        old: cont.to_string(),
+                        new: cont,
+                        upgrades: vec![],
+                    });
+                    patches.len() - 1
+                }
+                Err(e) => {
+                    skipped.push((upgrade.clone(), format!("{manifest}: {e}")));
+                    continue;
+                }
+            },
+        };
+        match bump(manifest.as_str(), patches[idx].new.as_str(), upgrade) {
+            Some(new) => {
+                patches[idx].new = new;
+                patches[idx].upgrades.push(upgrade.clone());
+            }
+            // transitive, or pinned by something we do not rewrite (lock files, parents, boms)
+            None => skipped.push((upgrade.clone(), format!("not declared in {manifest}"))),
+        }
+    }
+    patches.retain(|p| p.old != p.new);
+
+    (patches, skipped)
+}
+
+/// unified diff of a patch, the rewrites never add or remove lines
+pub fn diff(patch: &Patch) -> String {
+    const CONTEXT: usize = 2;
+    let old: Vec<&str> = patch.old.lines().collect();
+    let new: Vec<&str> = patch.new.lines().collect();
+    let changed: Vec<usize> = (0..old.len().min(new.len())).filter(|&i| old[i] != new[i]).collect();
+
+    let mut out = format!("--- a/{0}\n+++ b/{0}\n", patch.path);
+    let mut idx = 0;
+    while idx < changed.len() {
+        // merge changes whose context overlaps into one hunk
+        let mut last = idx;
+        while last + 1 < changed.len() && changed[last + 1] - changed[last] <= CONTEXT * 2 {
+            last += 1;
+        }
+        let start = changed[idx].saturating_sub(CONTEXT);
+        let end = (changed[last] + CONTEXT + 1).min(old.len());
+        out.push_str(format!("@@ -{0},{1} +{0},{1} @@\n", start + 1, end - start).as_str());
+        for i in start..end {
+            if changed[idx..=last].contains(&i) {
+                out.push_str(format!("-{}\n+{}\n", old[i], new[i]).as_str());
+            } else {
+                out.push_str(format!(" {}\n", old[i]).as_str());
+            }
+        }
+        idx = last + 1;
+    }
+
+    out
+}
+
+fn description(patches: &[Patch], skipped: &[(Upgrade, String)], issue_iid: Option<u64>) -> String {
+    const COLS: [&str; 5] = ["location", "dependency", "from", "to", "cve"];
+    let mut lines: Vec<String> = vec![i18n::t("remediate.mr.intro"), String::new()];
+    lines.push(i18n::table_header(&COLS).trim_start().to_string());
+    for patch in patches.iter() {
+        for upgrade in patch.upgrades.iter() {
+            lines.push(render::row(&[
+                render::cell(patch.path.as_str(), 200),
+                render::code(upgrade.name.as_str()),
+                render::code(upgrade.from.as_str()),
+                render::code(upgrade.to.as_str()),
+                render::cell(upgrade.cves.join(", ").as_str(), 200),
+            ]));
+        }
+    }
+    if !skipped.is_empty() {
+        lines.push(String::new());
+        lines.push(i18n::t("remediate.mr.skipped"));
+        for (upgrade, reason) in skipped.iter() {
+            lines.push(format!(
+                "- {} {} -> {}: {}",
+                render::code(upgrade.name.as_str()),
+                render::code(upgrade.from.as_str()),
+                render::code(upgrade.to.as_str()),
+                render::escape(reason.as_str())
+            ));
+        }
+    }
+    if let Some(iid) = issue_iid {
+        lines.push(String::new());
+        lines.push(i18n::tf("remediate.mr.related", &[("iid", iid.to_string().as_str())]));
+    }
+
+    lines.join("\n")
+}
+
+// latest open sca issue of the project
+async fn sca_issue_iid() -> Option<u64> {
+    let body = gitlab::list_issues(CI_PROJECT_ID.as_str(), "SCA").await.ok()?;
+    let issues: Value = serde_json::from_str(body.as_str()).ok()?;
+    issues.as_array()?.first()?["iid"].as_u64()
+}
+
+// open merge request of the remediation branch, updated instead of opening another one
+async fn open_merge_request(project: &str, branch: &str) -> Option<u64> {
+    let body = gitlab::list_merge_requests(project, branch, CI_COMMIT_REF_NAME.as_str()).await.ok()?;
+    let mrs: Value = serde_json::from_str(body.as_str()).ok()?;
+    mrs.as_array()?.first()?["iid"].as_u64()
+}
+
+/// rewrite the manifests for `upgrades`, print the patch on `dry_run`, otherwise open a merge
+/// request (or update the open one of the branch) and link it from the sca issue. returns the merge request url
+pub async fn run(target: &str, upgrades: &[Upgrade], dry_run: bool) -> Option<String> {
+    let (patches, skipped) = plan(target, upgrades);
+    for (upgrade, reason) in skipped.iter() {
+        log::warn!("skip {}:{} -> {}, {reason}", upgrade.name, upgrade.from, upgrade.to);
+    }
+    if patches.is_empty() {
+        println!("nothing to remediate, {} fixable, {} skipped", upgrades.len(), skipped.len());
+        return None;
+    }
+
+    if dry_run {
+        for patch in patches.iter() {
+            print!("{}", diff(patch));
+        }
+        return None;
+    }
+
+    let branch = if REMEDIATE_BRANCH.is_empty() {
+        format!("sec/remediate-{}", CI_COMMIT_REF_NAME.as_str())
+    } else {
+        REMEDIATE_BRANCH.to_string()
+    };
+    let project = CI_PROJECT_ID.as_str();
+
+    let files: Vec<(String, String)> = patches.iter().map(|p| (p.path.to_string(), p.new.to_string())).collect();
+    let count = patches.iter().map(|p| p.upgrades.len()).sum::<usize>().to_string();
+    let title = i18n::tf("remediate.mr.title", &[("count", count.as_str())]);
+    // the branch is rebuilt on the scanned commit, the manifests were read from it
+    let cont = gitlab::new_commit(project, branch.as_str(), title.as_str(), &files, CI_COMMIT_SHA.as_str())
+        .await
+        .ok()?;
+    let commit: Value = serde_json::from_str(cont.as_str()).unwrap_or_default();
+    if commit["id"].as_str().is_none() {
+        log::error!("remediation commit failed, skip merge request");
+        return None;
+    }
+
+    let issue_iid = sca_issue_iid().await;
+    let desc = description(&patches, &skipped, issue_iid);
+    let open = open_merge_request(project, branch.as_str()).await;
+    let cont = match open {
+        Some(iid) => gitlab::update_merge_request(project, iid, title.as_str(), desc.as_str()).await.ok()?,
+        None => gitlab::new_merge_request(
+            project,
+            branch.as_str(),
+            CI_COMMIT_REF_NAME.as_str(),
+            title.as_str(),
+            desc.as_str(),
+        )
+        .await
+        .ok()?,
+    };
+    let mr: Value = serde_json::from_str(cont.as_str()).unwrap_or_default();
+    let url = mr["web_url"].as_str()?.to_string();
+    println!("remediation merge request: {url}");
+
+    // the issue links the merge request once, updates keep the same url
+    if let (Some(iid), None) = (issue_iid, open) {
+        let note = i18n::tf("remediate.note", &[("url", url.as_str())]);
+        let _ = gitlab::new_issue_note(project, iid.to_string().as_str(), note.as_str()).await;
+    }
+
+    Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_package_json_ranges() {
+        let content = "{\n  \"dependencies\": {\n    \"lodash\": \"^4.17.15\",\n    \"minimist\": \"~1.2.0\",\n    \"left-pad\": \"1.3.0\"\n  },\n  \"devDependencies\": { \"lodash\": \"4.17.10\" }\n}\n";
+        assert_eq!(
+            bump_package_json(content, "lodash", "4.17.21").unwrap(),
+            "{\n  \"dependencies\": {\n    \"lodash\": \"^4.17.21\",\n    \"minimist\": \"~1.2.0\",\n    \"left-pad\": \"1.3.0\"\n  },\n  \"devDependencies\": { \"lodash\": \"4.17.21\" }\n}\n"
+        );
+        assert!(bump_package_json(content, "minimist", "1.2.6").unwrap().contains("\"minimist\": \"~1.2.6\""));
+        // already safe, not declared, or a range we do not rewrite
+        assert_eq!(bump_package_json(content, "left-pad", "1.3.0"), None);
+        assert_eq!(bump_package_json(content, "express", "4.19.2"), None);
+        assert_eq!(bump_package_json("{ \"a\": \">=1.0.0 <2\" }", "a", "1.5.0"), None);
+        assert_eq!(bump_package_json("{ \"a\": \"latest\" }", "a", "1.5.0"), None);
+        // a key of the same name that is not a string value
+        assert_eq!(bump_package_json("{ \"lodash\": { \"version\": 1 } }", "lodash", "4.17.21"), None);
+    }
+
+    #[test]
+    fn test_bump_pom() {
+        let content = "<project>\n<properties>\n  <jackson.version>2.12.1</jackson.version>\n</properties>\n<dependencies>\n  <dependency>\n    <groupId>com.fasterxml.jackson.core</groupId>\n    <artifactId>jackson-databind</artifactId>\n    <version>${jackson.version}</version>\n  </dependency>\n  <dependency>\n    <groupId>org.yaml</groupId>\n    <artifactId>snakeyaml</artifactId>\n    <version> 1.30 </version>\n  </dependency>\n  <dependency>\n    <groupId>org.other</groupId>\n    <artifactId>snakeyaml</artifactId>\n    <version>1.0</version>\n  </dependency>\n  <dependency>\n    <groupId>org.slf4j</groupId>\n    <artifactId>slf4j-api</artifactId>\n  </dependency>\n</dependencies>\n</project>\n";
+        // the property is bumped, the reference kept
+        let out = bump_pom(content, "com.fasterxml.jackson.core/jackson-databind", "2.12.7.1").unwrap();
+        assert!(out.contains("<jackson.version>2.12.7.1</jackson.version>"));
+        assert!(out.contains("<version>${jackson.version}</version>"));
+        // group must match, whitespace around the version survives
+        let out = bump_pom(content, "org.yaml:snakeyaml", "2.0").unwrap();
+        assert!(out.contains("<version> 2.0 </version>"));
+        assert!(out.contains("<version>1.0</version>"));
+        // managed by a parent or bom, already safe, not declared
+        assert_eq!(bump_pom(content, "org.slf4j/slf4j-api", "2.0.0"), None);
+        assert_eq!(bump_pom(content, "org.yaml/snakeyaml", "1.30"), None);
+        assert_eq!(bump_pom(content, "org.apache/log4j", "2.17.1"), None);
+    }
+
+    #[test]
+    fn test_bump_requirements() {
+        let content = "# pinned\nDjango==3.2.1\nrequests[security]>=2.20.0 ; python_version >= \"3.6\"\nPyYAML~=5.3  # yaml\nflask\nurllib3==1.26.18\n";
+        let out = bump_requirements(content, "django", "3.2.25").unwrap();
+        assert!(out.contains("Django==3.2.25\n"));
+        // extras and markers are kept, the marker version is not touched
+        let out = bump_requirements(content, "requests", "2.31.0").unwrap();
+        assert!(out.contains("requests[security]>=2.31.0 ; python_version >= \"3.6\"\n"));
+        // normalized names, trailing comment kept
+        let out = bump_requirements(content, "pyyaml", "5.4").unwrap();
+        assert!(out.contains("PyYAML~=5.4  # yaml\n"));
+        // unpinned, already safe, not declared
+        assert_eq!(bump_requirements(content, "flask", "2.2.5"), None);
+        assert_eq!(bump_requirements(content, "urllib3", "1.26.18"), None);
+        assert_eq!(bump_requirements(content, "jinja2", "3.1.3"), None);
+    }
+
+    #[test]
+    fn test_bump_go_mod() {
+        let content = "module example.com/app\n\ngo 1.21\n\nrequire golang.org/x/net v0.7.0\n\nrequire (\n\tgithub.com/gin-gonic/gin v1.9.0\n\tgolang.org/x/text v0.3.7 // indirect\n)\n";
+        let out = bump_go_mod(content, "golang.org/x/net", "0.17.0").unwrap();
+        assert!(out.contains("require golang.org/x/net v0.17.0\n"));
+        let out = bump_go_mod(content, "golang.org/x/text", "v0.3.8").unwrap();
+        assert!(out.contains("\tgolang.org/x/text v0.3.8 // indirect\n"));
+        let out = bump_go_mod(content, "github.com/gin-gonic/gin", "1.9.1").unwrap();
+        assert!(out.contains("\tgithub.com/gin-gonic/gin v1.9.1\n"));
+        // already safe, not declared, only a prefix of another module
+        assert_eq!(bump_go_mod(content, "golang.org/x/net", "0.7.0"), None);
+        assert_eq!(bump_go_mod(content, "golang.org/x/crypto", "0.17.0"), None);
+        assert_eq!(bump_go_mod(content, "golang.org/x", "1.0.0"), None);
+    }
+
+    #[test]
+    fn test_bump_go_mod_keeps_replace_and_exclude() {
+        let content = "module example.com/app\n\nrequire (\n\tgolang.org/x/net v0.7.0\n)\n\nreplace (\n\tgolang.org/x/net v0.7.0 => ./fork\n)\n\nexclude (\n\tgolang.org/x/net v0.6.0\n)\n\nreplace golang.org/x/net v0.5.0 => ./old\n";
+        let out = bump_go_mod(content, "golang.org/x/net", "0.17.0").unwrap();
+        assert_eq!(out, content.replacen("\tgolang.org/x/net v0.7.0\n)", "\tgolang.org/x/net v0.17.0\n)", 1));
+        // only replaced, never required
+        assert_eq!(bump_go_mod("replace (\n\tgolang.org/x/net v0.7.0 => ./fork\n)\n", "golang.org/x/net", "0.17.0"), None);
+    }
+
+    #[test]
+    fn test_manifest_for() {
+        assert_eq!(manifest_for("web/package-lock.json").as_deref(), Some("web/package.json"));
+        assert_eq!(manifest_for("go.sum").as_deref(), Some("go.mod"));
+        assert_eq!(manifest_for("requirements-dev.txt").as_deref(), Some("requirements-dev.txt"));
+        assert_eq!(manifest_for("Gemfile.lock"), None);
+    }
+}
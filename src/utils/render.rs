@@ -0,0 +1,219 @@
+//! safe markdown rendering of finding text inside issue tables
+//!
+//! every value coming from a report is untrusted: it may contain pipes, backticks,
+//! html tags or newlines which break the table or inject markup into gitlab issues.
+
+/// default max chars of a table cell
+pub const MAX_CELL_LEN: usize = 500;
+
+const ELLIPSIS: &str = "…";
+
+/// cut `text` to at most `max` chars (not bytes), an ellipsis marks the cut
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut result: String = text.chars().take(max.saturating_sub(1)).collect();
+    result.push_str(ELLIPSIS);
+
+    result
+}
+
+// analyzers often double escape newlines, treat `\n` literals as line breaks
+fn normalize_newlines(text: &str) -> String {
+    text.replace("\\r\\n", "\n")
+        .replace("\\n", "\n")
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+}
+
+/// escape plain text so it renders literally inside a markdown table cell
+pub fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in normalize_newlines(text).trim().chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            // `@all`, `@group` would notify everyone
+            '@' => result.push_str("&#64;"),
+            '\n' => result.push_str("<br>"),
+            '|' | '\\' | '`' | '*' | '_' | '[' | ']' | '~' | '#' | '!' => {
+                result.push('\\');
+                result.push(c);
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// truncated and escaped table cell
+pub fn cell(text: &str, max: usize) -> String {
+    escape(truncate(normalize_newlines(text).trim(), max).as_str())
+}
+
+/// inline code span, the fence is longer than any backtick run inside the text
+pub fn code(text: &str) -> String {
+    let text = normalize_newlines(text)
+        .replace('\n', " ")
+        .replace('|', "\\|");
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    let fence = "`".repeat(longest + 1);
+    // a space keeps a leading/trailing backtick from merging with the fence
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{fence} {text} {fence}")
+    } else {
+        format!("{fence}{text}{fence}")
+    }
+}
+
+/// markdown link with escaped text, only http(s) urls are linked
+pub fn link(text: &str, url: &str) -> String {
+    let text = escape(text);
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return text;
+    }
+    let url = url
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('|', "%7C")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+        .replace('"', "%22");
+
+    format!("[{text}]({url})")
+}
+
+/// table row from already rendered cells
+pub fn row(cells: &[String]) -> String {
+    format!("|{}|", cells.join("|"))
+}
+
+/// split table rows into chunks that each fit in `max` chars, every chunk repeats `header`
+pub fn split_rows(header: &str, rows: &[String], max: usize) -> Vec<String> {
+    let mut chunks: Vec<String> = vec![];
+    let mut chunk = header.to_string();
+    let mut size = 0;
+    for row in rows {
+        if size > 0 && chunk.len() + row.len() + 1 > max {
+            chunks.push(chunk);
+            chunk = header.to_string();
+            size = 0;
+        }
+        chunk = format!("{chunk}\n{row}");
+        size += 1;
+    }
+    if size > 0 {
+        chunks.push(chunk);
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_pipes_and_newlines() {
+        assert_eq!(
+            cell("a | b\nc\\nd\r\ne", MAX_CELL_LEN),
+            "a \\| b<br>c<br>d<br>e"
+        );
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            cell("<img src=x onerror=alert(1)> & \"q\"", MAX_CELL_LEN),
+            "&lt;img src=x onerror=alert(1)&gt; &amp; &quot;q&quot;"
+        );
+        assert_eq!(
+            cell("</td></tr><script>alert(1)</script>", MAX_CELL_LEN),
+            "&lt;/td&gt;&lt;/tr&gt;&lt;script&gt;alert(1)&lt;/script&gt;"
+        );
+    }
+
+    #[test]
+    fn test_escape_markdown() {
+        assert_eq!(
+            cell("**bold** _it_ `code` [x](http://evil) ![img](y) ~~s~~ # h \\", MAX_CELL_LEN),
+            "\\*\\*bold\\*\\* \\_it\\_ \\`code\\` \\[x\\](http://evil) \\!\\[img\\](y) \\~\\~s\\~\\~ \\# h \\\\"
+        );
+    }
+
+    #[test]
+    fn test_mention_and_multibyte() {
+        assert_eq!(
+            cell("@all 严重 <漏洞>", MAX_CELL_LEN),
+            "&#64;all 严重 &lt;漏洞&gt;"
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("abcdef", 6), "abcdef");
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(truncate("严重漏洞说明", 3), "严重…");
+        // cut before escaping so entities are never split
+        assert_eq!(cell("<<<<<<", 3), "&lt;&lt;…");
+    }
+
+    #[test]
+    fn test_code() {
+        assert_eq!(code("eval(x)"), "`eval(x)`");
+        assert_eq!(code("a ` b"), "``a ` b``");
+        assert_eq!(code("`x`"), "`` `x` ``");
+        assert_eq!(code("a | b\nc"), "`a \\| b c`");
+    }
+
+    #[test]
+    fn test_link() {
+        assert_eq!(
+            link("src/a|b.java:1", "https://git/x y/(a)"),
+            "[src/a\\|b.java:1](https://git/x%20y/%28a%29)"
+        );
+        assert_eq!(link("x", "javascript:alert(1)"), "x");
+    }
+
+    #[test]
+    fn test_split_rows() {
+        let rows: Vec<String> = (1..=5).map(|i| format!("|{i}|")).collect();
+        assert_eq!(
+            split_rows("|h|", &rows, 100),
+            vec!["|h|\n|1|\n|2|\n|3|\n|4|\n|5|"]
+        );
+        assert_eq!(
+            split_rows("|h|", &rows, 12),
+            vec!["|h|\n|1|\n|2|", "|h|\n|3|\n|4|", "|h|\n|5|"]
+        );
+        assert!(split_rows("|h|", &[], 12).is_empty());
+    }
+
+    #[test]
+    fn test_row() {
+        let cells = vec![
+            cell("SQL | Injection", MAX_CELL_LEN),
+            cell("High", MAX_CELL_LEN),
+            code("find_sec_bugs.SQL_INJECTION|x"),
+            cell("line1\nline2 <b>", MAX_CELL_LEN),
+        ];
+        assert_eq!(
+            row(&cells),
+            "|SQL \\| Injection|High|`find_sec_bugs.SQL_INJECTION\\|x`|line1<br>line2 &lt;b&gt;|"
+        );
+    }
+}
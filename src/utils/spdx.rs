@@ -0,0 +1,266 @@
+//! spdx license expression evaluation against an allow / deny / review policy
+//!
+//! `OR` picks the most permissive branch (the licensee may choose), `AND` the most restrictive,
+//! `X WITH exception` is matched as `X WITH exception` first and then as `X`.
+//! policy patterns are case insensitive spdx ids, a trailing `*` matches a prefix (`AGPL-*`)
+
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Decision {
+    #[default]
+    Allow,
+    Review,
+    Deny,
+}
+
+impl FromStr for Decision {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "allow" => Ok(Decision::Allow),
+            "review" => Ok(Decision::Review),
+            "deny" => Ok(Decision::Deny),
+            other => Err(format!("unknown license decision: {other}")),
+        }
+    }
+}
+
+impl Decision {
+    pub fn as_str(self: &Self) -> &'static str {
+        match self {
+            Decision::Allow => "allow",
+            Decision::Review => "review",
+            Decision::Deny => "deny",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    pub review: Vec<String>,
+    // licenses in none of the lists
+    pub default: Decision,
+    // packages without any license information, allowed unless configured: most dependency
+    // scanning reports carry no license data at all
+    pub unknown: Decision,
+}
+
+fn patterns(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+// unset falls back silently, an invalid value is a policy mistake worth a warning
+fn decision(name: &str, value: &str, fallback: Decision) -> Decision {
+    if value.trim().is_empty() {
+        return fallback;
+    }
+    value.parse().unwrap_or_else(|err| {
+        log::warn!("{name}: {err}, using {}", fallback.as_str());
+        fallback
+    })
+}
+
+fn matches(patterns: &[String], id: &str) -> bool {
+    let id = id.to_lowercase();
+    patterns.iter().any(|p| match p.strip_suffix('*') {
+        Some(prefix) => id.starts_with(prefix),
+        None => *p == id,
+    })
+}
+
+impl Policy {
+    /// policy from comma separated lists, see `LICENSE_*` in conf::setting
+    pub fn new(allow: &str, deny: &str, review: &str, default: &str, unknown: &str) -> Self {
+        let allow = patterns(allow);
+        // with an allow list everything else needs a review
+        let fallback = if allow.is_empty() { Decision::Allow } else { Decision::Review };
+        Policy {
+            allow: allow,
+            deny: patterns(deny),
+            review: patterns(review),
+            default: decision("LICENSE_DEFAULT", default, fallback),
+            unknown: decision("LICENSE_UNKNOWN", unknown, Decision::Allow),
+        }
+    }
+
+    /// decision of a single license id, deny wins over review and review over allow
+    pub fn decide(self: &Self, id: &str) -> Decision {
+        let id = id.trim().trim_end_matches('+');
+        if matches(&self.deny, id) {
+            Decision::Deny
+        } else if matches(&self.review, id) {
+            Decision::Review
+        } else if matches(&self.allow, id) {
+            Decision::Allow
+        } else {
+            self.default
+        }
+    }
+
+    /// decision of an spdx expression, an unparsable expression is matched as a single license name
+    pub fn evaluate(self: &Self, expression: &str) -> Decision {
+        if expression.trim().is_empty() {
+            return self.unknown;
+        }
+        let tokens = tokenize(expression);
+        let mut pos = 0;
+        match self.or_expr(&tokens, &mut pos) {
+            Some(decision) if pos == tokens.len() => decision,
+            _ => self.decide(expression),
+        }
+    }
+
+    fn or_expr(self: &Self, tokens: &[String], pos: &mut usize) -> Option<Decision> {
+        let mut decision = self.and_expr(tokens, pos)?;
+        while tokens.get(*pos).map_or(false, |t| t.eq_ignore_ascii_case("OR")) {
+            *pos += 1;
+            decision = decision.min(self.and_expr(tokens, pos)?);
+        }
+        Some(decision)
+    }
+
+    fn and_expr(self: &Self, tokens: &[String], pos: &mut usize) -> Option<Decision> {
+        let mut decision = self.atom(tokens, pos)?;
+        while tokens.get(*pos).map_or(false, |t| t.eq_ignore_ascii_case("AND")) {
+            *pos += 1;
+            decision = decision.max(self.atom(tokens, pos)?);
+        }
+        Some(decision)
+    }
+
+    fn atom(self: &Self, tokens: &[String], pos: &mut usize) -> Option<Decision> {
+        let token = tokens.get(*pos)?;
+        *pos += 1;
+        if token == "(" {
+            let decision = self.or_expr(tokens, pos)?;
+            if tokens.get(*pos).map(|t| t.as_str()) != Some(")") {
+                return None;
+            }
+            *pos += 1;
+            return Some(decision);
+        }
+        if token == ")" || ["AND", "OR", "WITH"].iter().any(|op| token.eq_ignore_ascii_case(op)) {
+            return None;
+        }
+        if tokens.get(*pos).map_or(false, |t| t.eq_ignore_ascii_case("WITH")) {
+            let exception = tokens.get(*pos + 1)?;
+            *pos += 2;
+            let full = format!("{token} WITH {exception}");
+            if matches(&self.deny, &full) || matches(&self.review, &full) || matches(&self.allow, &full)
+            {
+                return Some(self.decide(&full));
+            }
+        }
+        Some(self.decide(token))
+    }
+}
+
+fn tokenize(expression: &str) -> Vec<String> {
+    expression
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .split_whitespace()
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// license ids referenced by an expression
+pub fn license_ids(expression: &str) -> Vec<String> {
+    let mut ids: Vec<String> = vec![];
+    for token in tokenize(expression) {
+        if token == "(" || token == ")" || ["AND", "OR", "WITH"].iter().any(|op| token.eq_ignore_ascii_case(op)) {
+            continue;
+        }
+        if !ids.contains(&token) {
+            ids.push(token);
+        }
+    }
+
+    ids
+}
+
+/// valid spdx expression made of license ids, `NOASSERTION` otherwise (e.g. free text names)
+pub fn declared_license(licenses: &[String]) -> String {
+    let valid = |expr: &str| {
+        let tokens = tokenize(expr);
+        let mut pos = 0;
+        tokens.iter().all(|t| {
+            t.chars()
+                .all(|c| c.is_ascii_alphanumeric() || ['.', '-', '+', ':', '(', ')'].contains(&c))
+        }) && Policy::default().or_expr(&tokens, &mut pos).is_some()
+            && pos == tokens.len()
+    };
+    if licenses.is_empty() || !licenses.iter().all(|l| valid(l)) {
+        return String::from("NOASSERTION");
+    }
+    if licenses.len() == 1 {
+        return licenses[0].to_string();
+    }
+    licenses
+        .iter()
+        .map(|l| if l.contains(' ') { format!("({l})") } else { l.to_string() })
+        .collect::<Vec<String>>()
+        .join(" AND ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deny_over_review_over_allow() {
+        let policy = Policy::new("MIT, GPL-3.0-only", "GPL-*", "gpl-3.0-only", "", "");
+        assert_eq!(policy.decide("GPL-3.0-only"), Decision::Deny);
+        let policy = Policy::new("MIT, LGPL-2.1-only", "", "lgpl-*", "", "");
+        assert_eq!(policy.decide("LGPL-2.1-only"), Decision::Review);
+        assert_eq!(policy.decide("mit"), Decision::Allow);
+        // with an allow list anything else needs a review
+        assert_eq!(policy.decide("Apache-2.0"), Decision::Review);
+        assert_eq!(Policy::new("", "", "", "", "").decide("Apache-2.0"), Decision::Allow);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let policy = Policy::new("MIT, Apache-2.0", "AGPL-*", "LGPL-2.1-only", "", "");
+        // OR takes the most permissive branch, AND the most restrictive
+        assert_eq!(policy.evaluate("MIT OR AGPL-3.0-only"), Decision::Allow);
+        assert_eq!(policy.evaluate("MIT AND AGPL-3.0-only"), Decision::Deny);
+        assert_eq!(policy.evaluate("MIT AND (LGPL-2.1-only OR AGPL-3.0-only)"), Decision::Review);
+        assert_eq!(policy.evaluate("(MIT OR AGPL-3.0-only) and Apache-2.0"), Decision::Allow);
+        // `+` is the same license id
+        assert_eq!(policy.evaluate("AGPL-3.0+"), Decision::Deny);
+        // unparsable, matched as a single name
+        assert_eq!(policy.evaluate("MIT AND"), Decision::Review);
+        assert_eq!(policy.evaluate(" "), Decision::Allow);
+    }
+
+    #[test]
+    fn test_evaluate_with_exception() {
+        let policy = Policy::new("Apache-2.0", "GPL-*", "", "", "");
+        assert_eq!(policy.evaluate("GPL-2.0-only WITH Classpath-exception-2.0"), Decision::Deny);
+        // the exception itself is listed
+        let policy = Policy::new("GPL-2.0-only WITH Classpath-exception-2.0", "GPL-2.0-only", "", "", "");
+        assert_eq!(policy.evaluate("GPL-2.0-only WITH Classpath-exception-2.0"), Decision::Allow);
+        assert_eq!(policy.evaluate("GPL-2.0-only WITH LLVM-exception"), Decision::Deny);
+        assert_eq!(policy.evaluate("GPL-2.0-only"), Decision::Deny);
+    }
+
+    #[test]
+    fn test_default_and_unknown() {
+        let policy = Policy::new("MIT", "", "", "deny", "review");
+        assert_eq!(policy.evaluate("Apache-2.0"), Decision::Deny);
+        assert_eq!(policy.evaluate(""), Decision::Review);
+        // an invalid value falls back
+        let policy = Policy::new("MIT", "", "", "block", "nope");
+        assert_eq!(policy.default, Decision::Review);
+        assert_eq!(policy.unknown, Decision::Allow);
+    }
+}
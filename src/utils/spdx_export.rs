@@ -0,0 +1,104 @@
+//! spdx 2.3 json sbom from the merged dependency data, license expressions see [`spdx`]
+
+use std::collections::HashSet;
+
+use serde_json::{json, Value};
+
+use crate::{
+    parser::base::fingerprint,
+    utils::{
+        cyclonedx::{self, Bom},
+        spdx,
+    },
+};
+
+// spdx ids only allow letters, numbers, `.` and `-`
+fn spdx_id(bom_ref: &str) -> String {
+    format!("SPDXRef-Package-{}", fingerprint(&[bom_ref]))
+}
+
+/// spdx 2.3 json document of the components and dependency graph of `bom`
+///
+/// `project` is the root package the document describes, `namespace` must be unique per document
+pub fn document(bom: &Bom, project: &str, namespace: &str, creators: &[String]) -> Value {
+    let root = String::from("SPDXRef-Project");
+    let mut packages: Vec<Value> = vec![json!({
+        "name": project,
+        "SPDXID": root,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": "NOASSERTION",
+        "copyrightText": "NOASSERTION",
+        "primaryPackagePurpose": "APPLICATION",
+    })];
+    for component in bom.components.iter() {
+        let mut package = json!({
+            "name": component.package_name(),
+            "SPDXID": spdx_id(component.bom_ref.as_str()),
+            "versionInfo": component.version,
+            "downloadLocation": "NOASSERTION",
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": spdx::declared_license(&component.licenses),
+            "copyrightText": "NOASSERTION",
+            "primaryPackagePurpose": "LIBRARY",
+        });
+        if !component.purl.is_empty() {
+            package["externalRefs"] = json!([{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": component.purl,
+            }]);
+        }
+        if !component.location.is_empty() {
+            package["sourceInfo"] = json!(format!("declared in {}", component.location));
+        }
+        packages.push(package);
+    }
+
+    let refs: HashSet<&str> = bom.components.iter().map(|c| c.bom_ref.as_str()).collect();
+    let direct: HashSet<&str> = bom.direct.iter().map(|d| d.as_str()).collect();
+    let known = |bom_ref: &str| refs.contains(bom_ref);
+    let mut relationships: Vec<Value> = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": root,
+    })];
+    let mut has_parent: HashSet<&str> = HashSet::new();
+    for (bom_ref, depends_on) in bom.dependencies.iter().filter(|(r, _)| known(r)) {
+        for dep in depends_on.iter().filter(|dep| known(dep)) {
+            has_parent.insert(dep.as_str());
+            relationships.push(json!({
+                "spdxElementId": spdx_id(bom_ref),
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(dep),
+            }));
+        }
+    }
+    // flagged direct dependencies and components nobody depends on hang off the project
+    for component in bom.components.iter() {
+        let bom_ref = component.bom_ref.as_str();
+        if direct.contains(bom_ref) || !has_parent.contains(bom_ref) {
+            relationships.push(json!({
+                "spdxElementId": root,
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(component.bom_ref.as_str()),
+            }));
+        }
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": project,
+        "documentNamespace": namespace,
+        "creationInfo": {
+            "created": cyclonedx::utc_now(),
+            "creators": creators,
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
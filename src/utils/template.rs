@@ -0,0 +1,445 @@
+//! minimal handlebars style template engine for issue titles, descriptions and mr notes
+//!
+//! supported syntax:
+//! - `{{ path.to.value }}`: value lookup, inner `#each` scopes first, then outer ones. the value is escaped
+//!   for markdown (see `render::escape`), `{{{ path }}}` inserts it raw, e.g. the prepared `header` and `record`
+//! - `{{#each list}}...{{else}}...{{/each}}`: loop, `{{this}}` is the item, `{{@index}}` starts from 0,
+//!   the else part renders when the list is empty
+//! - `{{#if value}}...{{else}}...{{/if}}`: false, null, "", 0, [] and {} are falsy
+//!
+//! context passed to issue templates:
+//! - `engine`, `title` (localized)
+//! - `project.id`, `project.url`, `mr.iid`, `pipeline.id`, `commit.sha`, `commit.ref`
+//! - `counts.total`, `counts.critical`, `counts.high`, `counts.medium`, `counts.low`, `counts.info`, `counts.unknown`
+//! - `findings[]`: `id` (from 1), `name`, `severity`, `severity_name` (localized), `description`, `identifiers`, `file`, `line`,
+//!   `dependency`, `fingerprint`, `record` (the default table row, rendered with `{{{record}}}`)
+//! - engine specific values, e.g. `header`, `intro`, `grouped` and `groups[]` (`location`, `findings[]`) for SCA
+//!   (`location` is the image for Container, which also has `base_images[]`, the endpoint for DAST and `file resource` for IaC)
+
+use std::fs;
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    conf::setting::*,
+    parser::base::Finding,
+    utils::{gitlab::Issue, i18n, render},
+};
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Var(String),
+    Raw(String),
+    Each(String, Vec<Node>, Vec<Node>),
+    If(String, Vec<Node>, Vec<Node>),
+}
+
+enum Token {
+    Text(String),
+    Var(String),
+    Raw(String),
+    Open(String, String),
+    Else,
+    Close(String),
+}
+
+fn tokenize(tpl: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut rest = tpl;
+    while let Some(start) = rest.find("{{") {
+        if rest[start..].starts_with("{{{") {
+            let Some(end) = rest[start..].find("}}}") else {
+                break;
+            };
+            if start > 0 {
+                tokens.push(Token::Text(rest[..start].to_string()));
+            }
+            tokens.push(Token::Raw(rest[start + 3..start + end].trim().to_string()));
+            rest = &rest[start + end + 3..];
+            continue;
+        }
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let tag = rest[start + 2..start + end].trim();
+        if let Some(open) = tag.strip_prefix('#') {
+            let (kind, arg) = open.split_once(' ').unwrap_or((open, ""));
+            tokens.push(Token::Open(kind.to_string(), arg.trim().to_string()));
+        } else if let Some(close) = tag.strip_prefix('/') {
+            tokens.push(Token::Close(close.trim().to_string()));
+        } else if tag == "else" {
+            tokens.push(Token::Else);
+        } else {
+            tokens.push(Token::Var(tag.to_string()));
+        }
+        rest = &rest[start + end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+
+    tokens
+}
+
+// parse until the closing tag of `block`, returns (body, else body)
+fn parse_nodes(tokens: &mut std::vec::IntoIter<Token>, block: &str) -> (Vec<Node>, Vec<Node>) {
+    let mut nodes: Vec<Node> = vec![];
+    let mut else_nodes: Vec<Node> = vec![];
+    let mut in_else = false;
+    while let Some(token) = tokens.next() {
+        let node = match token {
+            Token::Text(text) => Node::Text(text),
+            Token::Var(path) => Node::Var(path),
+            Token::Raw(path) => Node::Raw(path),
+            Token::Open(kind, arg) => {
+                let (body, otherwise) = parse_nodes(tokens, kind.as_str());
+                match kind.as_str() {
+                    "each" => Node::Each(arg, body, otherwise),
+                    "if" => Node::If(arg, body, otherwise),
+                    _ => {
+                        log::error!("not support template block: {kind}");
+                        continue;
+                    }
+                }
+            }
+            Token::Else => {
+                if block.is_empty() {
+                    log::error!("template {{{{else}}}} outside of a block, ignored");
+                } else {
+                    in_else = true;
+                }
+                continue;
+            }
+            // a stray closing tag at the top level is dropped, the rest still renders
+            Token::Close(kind) if block.is_empty() => {
+                log::error!("template block mismatch: {{{{/{kind}}}}} without an open block, ignored");
+                continue;
+            }
+            Token::Close(kind) => {
+                if kind != block {
+                    log::error!("template block mismatch: {{{{/{kind}}}}}, expect {{{{/{block}}}}}");
+                }
+                break;
+            }
+        };
+        if in_else {
+            else_nodes.push(node);
+        } else {
+            nodes.push(node);
+        }
+    }
+
+    (nodes, else_nodes)
+}
+
+fn lookup<'a>(scopes: &[&'a Value], path: &str) -> Option<&'a Value> {
+    for scope in scopes.iter().rev() {
+        if path == "this" || path == "." {
+            return Some(scope);
+        }
+        let path = path.strip_prefix("this.").unwrap_or(path);
+        let mut cur: Option<&Value> = Some(scope);
+        for key in path.split('.') {
+            cur = cur.and_then(|v| v.get(key));
+        }
+        if cur.is_some() {
+            return cur;
+        }
+    }
+
+    None
+}
+
+fn to_text(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.to_string(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| to_text(Some(item)))
+            .collect::<Vec<String>>()
+            .join(", "),
+        Some(v) => v.to_string(),
+    }
+}
+
+fn is_truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Number(n)) => n.as_f64().unwrap_or(0.0) != 0.0,
+        Some(Value::Array(items)) => !items.is_empty(),
+        Some(Value::Object(obj)) => !obj.is_empty(),
+    }
+}
+
+fn render_nodes(nodes: &[Node], scopes: &[&Value], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(path) => out.push_str(render::escape(to_text(lookup(scopes, path)).as_str()).as_str()),
+            Node::Raw(path) => out.push_str(to_text(lookup(scopes, path)).as_str()),
+            Node::If(path, body, otherwise) => {
+                if is_truthy(lookup(scopes, path)) {
+                    render_nodes(body, scopes, out);
+                } else {
+                    render_nodes(otherwise, scopes, out);
+                }
+            }
+            Node::Each(path, body, otherwise) => {
+                let items: Vec<Value> = match lookup(scopes, path) {
+                    Some(Value::Array(items)) => items.clone(),
+                    _ => vec![],
+                };
+                if items.is_empty() {
+                    render_nodes(otherwise, scopes, out);
+                }
+                for (idx, item) in items.iter().enumerate() {
+                    let index = json!({ "@index": idx });
+                    let mut inner: Vec<&Value> = scopes.to_vec();
+                    inner.push(&index);
+                    inner.push(item);
+                    render_nodes(body, &inner, out);
+                }
+            }
+        }
+    }
+}
+
+pub fn render(tpl: &str, ctx: &Value) -> String {
+    let mut tokens = tokenize(tpl).into_iter();
+    let (nodes, _) = parse_nodes(&mut tokens, "");
+    let mut out = String::new();
+    render_nodes(&nodes, &[ctx], &mut out);
+
+    out
+}
+
+/// template configured by env `name`, the value is a file path or the template itself
+pub fn load(name: &str) -> Option<String> {
+    let value = std::env::var(name).unwrap_or_default();
+    if value.is_empty() {
+        return None;
+    }
+    match fs::read_to_string(value.as_str()) {
+        Ok(cont) => Some(cont),
+        Err(_) => Some(value),
+    }
+}
+
+pub fn finding_value(id: usize, finding: &Finding, record: String) -> Value {
+    json!({
+        "id": id,
+        "name": finding.name,
+        "severity": finding.severity,
+        "severity_name": i18n::severity(finding.severity.as_str()),
+        "description": finding.description,
+        "identifiers": finding.identifiers,
+        "file": finding.file,
+        "line": finding.line,
+        "dependency": finding.dependency,
+        "fingerprint": finding.fingerprint,
+        "kev": finding.kev,
+        "priority": finding.priority,
+        "record": record,
+    })
+}
+
+/// common issue context, `extra` is merged on top
+pub fn context(engine: &str, findings: Vec<Value>, extra: Value) -> Value {
+    let mut counts: Map<String, Value> = Map::new();
+    for level in ["critical", "high", "medium", "low", "info", "unknown"] {
+        let n = findings
+            .iter()
+            .filter(|f| to_text(f.get("severity")).to_lowercase() == level)
+            .count();
+        counts.insert(level.to_string(), json!(n));
+    }
+    counts.insert("total".to_string(), json!(findings.len()));
+
+    let mut ctx = json!({
+        "engine": engine,
+        "title": i18n::tf("report.title", &[("engine", engine)]),
+        "project": { "id": CI_PROJECT_ID.as_str(), "url": CI_PROJECT_URL.as_str() },
+        "mr": { "iid": CI_MERGE_REQUEST_IID.as_str() },
+        "pipeline": { "id": CI_PIPELINE_ID.as_str() },
+        "commit": { "sha": CI_COMMIT_SHA.as_str(), "ref": CI_COMMIT_REF_NAME.as_str() },
+        "counts": counts,
+        "findings": findings,
+    });
+    if let (Some(obj), Value::Object(extra)) = (ctx.as_object_mut(), extra) {
+        obj.extend(extra);
+    }
+
+    ctx
+}
+
+pub fn issue_title(ctx: &Value) -> String {
+    let tpl = load("ISSUE_TITLE_TEMPLATE").unwrap_or(DEFAULT_ISSUE_TITLE_TEMPLATE.to_string());
+    render(tpl.as_str(), ctx)
+}
+
+pub fn issue_description(ctx: &Value, default: &str) -> String {
+    let tpl = load("ISSUE_TEMPLATE").unwrap_or(default.to_string());
+    render(tpl.as_str(), ctx)
+}
+
+/// mr note, only rendered when `MR_NOTE_TEMPLATE` is configured
+pub fn mr_note(ctx: &Value) -> Option<String> {
+    load("MR_NOTE_TEMPLATE").map(|tpl| render(tpl.as_str(), ctx))
+}
+
+/// split an issue whose description exceeds `ISSUE_MAX_LEN`, returns the notes to append
+///
+/// the description is replaced by a per location summary and a link to the report artifact,
+/// the finding tables move into notes, one or more per location (`groups`), or chunks of `findings`
+pub fn split_issue(issue: &mut Issue, ctx: &Value) -> Vec<String> {
+    if issue.description.len() <= *ISSUE_MAX_LEN {
+        return vec![];
+    }
+    log::warn!(
+        "issue description too large: {} > {}, split into notes",
+        issue.description.len(),
+        *ISSUE_MAX_LEN
+    );
+
+    let groups: Vec<Value> = match ctx.get("groups") {
+        Some(Value::Array(groups)) if !groups.is_empty() => groups.clone(),
+        _ => vec![json!({ "location": "", "findings": ctx["findings"] })],
+    };
+    let header = to_text(ctx.get("header"));
+    // leave room for the note title
+    let max = ISSUE_MAX_LEN.saturating_sub(1024);
+    let levels = ["critical", "high", "medium", "low"];
+
+    let mut summary: Vec<String> = vec![];
+    let mut notes: Vec<String> = vec![];
+    for group in groups.iter() {
+        let location = to_text(group.get("location"));
+        let findings = group["findings"].as_array().cloned().unwrap_or_default();
+
+        let mut values = vec![render::cell(location.as_str(), 200), findings.len().to_string()];
+        for level in levels {
+            let n = findings
+                .iter()
+                .filter(|f| to_text(f.get("severity")).to_lowercase() == level)
+                .count();
+            values.push(n.to_string());
+        }
+        let other = findings
+            .iter()
+            .filter(|f| !levels.contains(&to_text(f.get("severity")).to_lowercase().as_str()))
+            .count();
+        values.push(other.to_string());
+        summary.push(render::row(&values));
+
+        let rows: Vec<String> = findings
+            .iter()
+            .map(|f| format!("|{}{}", to_text(f.get("id")), to_text(f.get("record"))))
+            .collect();
+        let chunks = render::split_rows(header.as_str(), &rows, max);
+        let total = chunks.len();
+        for (idx, chunk) in chunks.into_iter().enumerate() {
+            let mut title = render::escape(location.as_str());
+            if total > 1 {
+                let (n, total) = ((idx + 1).to_string(), total.to_string());
+                title = format!(
+                    "{title}{}",
+                    i18n::tf("report.split.part", &[("n", n.as_str()), ("total", total.as_str())])
+                );
+            }
+            if title.is_empty() {
+                notes.push(chunk);
+            } else {
+                notes.push(format!("**{title}**\n\n{chunk}"));
+            }
+        }
+    }
+
+    let mut cols = vec![i18n::t("col.location"), i18n::t("col.findings")];
+    cols.extend(levels.iter().map(|level| i18n::severity(level)));
+    cols.push(i18n::t("col.other"));
+    issue.description = format!(
+        "{}\n\n{}\n{}\n{}",
+        i18n::tf("report.split", &[("url", REPORT_ARTIFACT_URL.as_str())]),
+        render::row(&cols),
+        render::row(&vec!["--".to_string(); cols.len()]),
+        summary.join("\n")
+    );
+
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_var_escaped_and_raw() {
+        let ctx = json!({ "name": "<b>@all</b> | x_y", "record": "|**a**|b|", "n": 3, "a": { "b": "deep" } });
+        assert_eq!(render("{{name}}", &ctx), "&lt;b&gt;&#64;all&lt;/b&gt; \\| x\\_y");
+        assert_eq!(render("{{{record}}}", &ctx), "|**a**|b|");
+        assert_eq!(render("{{{ record }}}!", &ctx), "|**a**|b|!");
+        assert_eq!(render("{{ a.b }} {{n}} [{{missing}}]", &ctx), "deep 3 []");
+    }
+
+    #[test]
+    fn test_each() {
+        let ctx = json!({ "sep": "-", "items": [{ "v": "a" }, { "v": "b" }], "tags": ["x", "y"], "empty": [] });
+        assert_eq!(render("{{#each items}}{{@index}}{{v}}{{sep}}{{/each}}", &ctx), "0a-1b-");
+        assert_eq!(render("{{#each tags}}[{{this}}]{{/each}}", &ctx), "[x][y]");
+        assert_eq!(render("{{#each tags}}{{{this}}}{{/each}}", &json!({ "tags": ["*a*"] })), "*a*");
+        assert_eq!(render("{{#each tags}}{{this}}{{/each}}", &json!({ "tags": ["*a*"] })), "\\*a\\*");
+        assert_eq!(render("{{#each empty}}{{this}}{{else}}none{{/each}}", &ctx), "none");
+        assert_eq!(render("{{#each tags}}{{this}}{{else}}none{{/each}}", &ctx), "xy");
+        assert_eq!(render("{{#each missing}}x{{/each}}", &ctx), "");
+    }
+
+    #[test]
+    fn test_if_else() {
+        let ctx = json!({ "yes": true, "no": false, "zero": 0, "text": "t", "blank": "", "list": [1], "obj": {} });
+        for path in ["yes", "text", "list"] {
+            assert_eq!(render(format!("{{{{#if {path}}}}}1{{{{else}}}}0{{{{/if}}}}").as_str(), &ctx), "1");
+        }
+        for path in ["no", "zero", "blank", "obj", "missing"] {
+            assert_eq!(render(format!("{{{{#if {path}}}}}1{{{{else}}}}0{{{{/if}}}}").as_str(), &ctx), "0");
+        }
+        assert_eq!(render("{{#if no}}1{{/if}}", &ctx), "");
+    }
+
+    #[test]
+    fn test_nesting() {
+        let ctx = json!({
+            "grouped": true,
+            "groups": [
+                { "location": "a", "findings": [{ "id": 1 }, { "id": 2 }] },
+                { "location": "b", "findings": [] },
+            ],
+        });
+        let tpl = "{{#if grouped}}{{#each groups}}{{location}}:{{#each findings}}{{#if id}}{{id}}{{/if}}{{else}}-{{/each}};{{/each}}{{else}}flat{{/if}}";
+        assert_eq!(render(tpl, &ctx), "a:12;b:-;");
+        assert_eq!(render(tpl, &json!({ "grouped": false })), "flat");
+    }
+
+    #[test]
+    fn test_malformed_tags() {
+        let ctx = json!({ "a": "x", "items": [1, 2] });
+        // unclosed tags are kept as text
+        assert_eq!(render("{{a}} {{a", &ctx), "x {{a");
+        assert_eq!(render("{{a}} {{{a}}", &ctx), "x {{{a}}");
+        // unclosed block renders to the end
+        assert_eq!(render("{{#each items}}{{this}}", &ctx), "12");
+        assert_eq!(render("{{#if a}}yes", &ctx), "yes");
+        // stray closing tag and else are dropped, the rest still renders
+        assert_eq!(render("{{/each}}{{a}}{{else}}{{a}}", &ctx), "xx");
+        // mismatched close ends the open block
+        assert_eq!(render("{{#each items}}{{this}}{{/if}}!", &ctx), "12!");
+        // unknown block is skipped with its body
+        assert_eq!(render("{{#with a}}{{a}}{{/with}}{{a}}", &ctx), "x");
+    }
+}
@@ -0,0 +1,130 @@
+//! exploit intelligence from local files refreshed by a separate job
+//!
+//! - epss scores csv (`EPSS_PATH`), as published by first.org: `cve,epss,percentile`,
+//!   optionally preceded by a `#model_version:...` comment line
+//! - cisa known exploited vulnerabilities catalog json (`KEV_PATH`)
+
+use std::{collections::HashMap, fs};
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Epss {
+    pub score: f64,
+    pub percentile: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct KevEntry {
+    pub cve: String,
+    pub name: String,
+    pub date_added: String,
+    pub due_date: String,
+    pub ransomware: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ThreatIntel {
+    epss: HashMap<String, Epss>,
+    kev: HashMap<String, KevEntry>,
+}
+
+pub fn parse_epss(content: &str) -> HashMap<String, Epss> {
+    let mut result: HashMap<String, Epss> = HashMap::new();
+    let mut columns: Vec<String> = vec![];
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let values: Vec<&str> = line.split(',').map(|v| v.trim()).collect();
+        if columns.is_empty() {
+            columns = values.iter().map(|v| v.to_lowercase()).collect();
+            continue;
+        }
+        let get = |name: &str| -> Option<&str> {
+            columns
+                .iter()
+                .position(|c| c == name)
+                .and_then(|idx| values.get(idx).copied())
+        };
+        let (Some(cve), Some(Ok(score))) = (get("cve"), get("epss").map(|v| v.parse::<f64>()))
+        else {
+            continue;
+        };
+        let percentile = get("percentile").and_then(|v| v.parse().ok()).unwrap_or(0.0);
+        result.insert(cve.to_uppercase(), Epss { score, percentile });
+    }
+
+    result
+}
+
+pub fn parse_kev(catalog: &Value) -> HashMap<String, KevEntry> {
+    let mut result: HashMap<String, KevEntry> = HashMap::new();
+    for item in catalog["vulnerabilities"].as_array().unwrap_or(&vec![]) {
+        let Some(cve) = item["cveID"].as_str() else {
+            continue;
+        };
+        result.insert(
+            cve.to_uppercase(),
+            KevEntry {
+                cve: cve.to_string(),
+                name: item["vulnerabilityName"].as_str().unwrap_or("").to_string(),
+                date_added: item["dateAdded"].as_str().unwrap_or("").to_string(),
+                due_date: item["dueDate"].as_str().unwrap_or("").to_string(),
+                ransomware: item["knownRansomwareCampaignUse"].as_str() == Some("Known"),
+            },
+        );
+    }
+
+    result
+}
+
+impl ThreatIntel {
+    /// load the configured files, a missing or empty path just leaves that source empty
+    pub fn load(epss_path: &str, kev_path: &str) -> Self {
+        let mut intel = ThreatIntel::default();
+        if !epss_path.is_empty() {
+            match fs::read_to_string(epss_path) {
+                Ok(cont) => intel.epss = parse_epss(cont.as_str()),
+                Err(e) => log::warn!("epss scores not available: {epss_path}, {e}"),
+            }
+        }
+        if !kev_path.is_empty() {
+            match fs::read_to_string(kev_path) {
+                Ok(cont) => match serde_json::from_str::<Value>(cont.as_str()) {
+                    Ok(catalog) => intel.kev = parse_kev(&catalog),
+                    Err(e) => log::warn!("invalid kev catalog: {kev_path}, {e}"),
+                },
+                Err(e) => log::warn!("kev catalog not available: {kev_path}, {e}"),
+            }
+        }
+        log::info!("threat intel loaded: {} epss, {} kev", intel.epss.len(), intel.kev.len());
+
+        intel
+    }
+
+    pub fn epss(self: &Self, cve: &str) -> Option<&Epss> {
+        self.epss.get(&cve.to_uppercase())
+    }
+
+    pub fn kev(self: &Self, cve: &str) -> Option<&KevEntry> {
+        self.kev.get(&cve.to_uppercase())
+    }
+}
+
+/// priority 0-100: cvss (or the severity when unscored) weighs 40, epss 40 and a kev listing 20
+pub fn priority(cvss: Option<f64>, severity: &str, epss: Option<f64>, kev: bool) -> f64 {
+    let cvss = cvss.unwrap_or(match severity.to_lowercase().as_str() {
+        "critical" => 9.0,
+        "high" => 7.0,
+        "medium" => 4.0,
+        "low" => 1.0,
+        _ => 0.0,
+    });
+    let score = cvss.clamp(0.0, 10.0) * 4.0
+        + epss.unwrap_or(0.0).clamp(0.0, 1.0) * 40.0
+        + if kev { 20.0 } else { 0.0 };
+
+    (score * 10.0).round() / 10.0
+}
@@ -0,0 +1,540 @@
+//! version schemes of package managers, used to find the minimal safe upgrade of a dependency
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scheme {
+    Maven,
+    Npm,
+    Pypi,
+    Go,
+    Gem,
+    Nuget,
+    Cargo,
+    #[default]
+    Generic,
+}
+
+impl Scheme {
+    /// from `dependency_files[].package_manager` of a gitlab report
+    pub fn from_package_manager(package_manager: &str) -> Self {
+        match package_manager.to_lowercase().as_str() {
+            "maven" | "gradle" | "sbt" => Scheme::Maven,
+            "npm" | "yarn" | "pnpm" | "bower" => Scheme::Npm,
+            "pip" | "pipenv" | "poetry" | "setuptools" | "conda" | "pypi" => Scheme::Pypi,
+            "go" | "golang" => Scheme::Go,
+            "bundler" | "gem" | "rubygems" => Scheme::Gem,
+            "nuget" => Scheme::Nuget,
+            "cargo" => Scheme::Cargo,
+            _ => Scheme::Generic,
+        }
+    }
+
+    /// from a manifest / lock file path
+    pub fn from_file(fpath: &str) -> Self {
+        let name = fpath.rsplit('/').next().unwrap_or(fpath).to_lowercase();
+        match name.as_str() {
+            "pom.xml" | "build.gradle" | "build.gradle.kts" | "build.sbt" | "gradle.lockfile" => {
+                Scheme::Maven
+            }
+            "package.json" | "package-lock.json" | "npm-shrinkwrap.json" | "yarn.lock"
+            | "pnpm-lock.yaml" => Scheme::Npm,
+            "pipfile" | "pipfile.lock" | "poetry.lock" | "setup.py" | "pyproject.toml" => {
+                Scheme::Pypi
+            }
+            "go.mod" | "go.sum" => Scheme::Go,
+            "gemfile" | "gemfile.lock" => Scheme::Gem,
+            "packages.lock.json" | "packages.config" => Scheme::Nuget,
+            "cargo.toml" | "cargo.lock" => Scheme::Cargo,
+            _ if name.starts_with("requirements") && name.ends_with(".txt") => Scheme::Pypi,
+            _ if name.ends_with(".gemspec") => Scheme::Gem,
+            _ if name.ends_with(".csproj") => Scheme::Nuget,
+            _ => Scheme::Generic,
+        }
+    }
+
+    /// from a package url, e.g. `pkg:maven/org.apache/commons@1.0`
+    pub fn from_purl(purl: &str) -> Self {
+        let kind = purl
+            .strip_prefix("pkg:")
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or("");
+        match kind {
+            "maven" => Scheme::Maven,
+            "npm" => Scheme::Npm,
+            "pypi" => Scheme::Pypi,
+            "golang" => Scheme::Go,
+            "gem" => Scheme::Gem,
+            "nuget" => Scheme::Nuget,
+            "cargo" => Scheme::Cargo,
+            _ => Scheme::Generic,
+        }
+    }
+
+    /// package url type, inverse of [`Scheme::from_purl`]
+    pub fn purl_type(self: &Self) -> &'static str {
+        match self {
+            Scheme::Maven => "maven",
+            Scheme::Npm => "npm",
+            Scheme::Pypi => "pypi",
+            Scheme::Go => "golang",
+            Scheme::Gem => "gem",
+            Scheme::Nuget => "nuget",
+            Scheme::Cargo => "cargo",
+            Scheme::Generic => "generic",
+        }
+    }
+}
+
+pub fn compare(scheme: Scheme, a: &str, b: &str) -> Ordering {
+    match scheme {
+        Scheme::Npm | Scheme::Go | Scheme::Nuget | Scheme::Cargo => cmp_semver(a, b),
+        Scheme::Pypi => cmp_pep440(a, b),
+        Scheme::Maven => cmp_maven(a, b),
+        Scheme::Gem => cmp_gem(a, b),
+        Scheme::Generic => cmp_generic(a, b),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Num(u64),
+    Str(String),
+}
+
+// split into digit / letter runs, any other char separates
+fn tokenize(version: &str) -> Vec<Item> {
+    let mut items: Vec<Item> = vec![];
+    let mut cur = String::new();
+    let flush = |cur: &mut String, items: &mut Vec<Item>| {
+        if cur.is_empty() {
+            return;
+        }
+        match cur.parse::<u64>() {
+            Ok(n) => items.push(Item::Num(n)),
+            Err(_) => items.push(Item::Str(cur.to_lowercase())),
+        }
+        cur.clear();
+    };
+    for c in version.chars() {
+        if !c.is_ascii_alphanumeric() {
+            flush(&mut cur, &mut items);
+            continue;
+        }
+        if let Some(last) = cur.chars().last() {
+            if last.is_ascii_digit() != c.is_ascii_digit() {
+                flush(&mut cur, &mut items);
+            }
+        }
+        cur.push(c);
+    }
+    flush(&mut cur, &mut items);
+
+    items
+}
+
+fn strip_prefix(version: &str) -> &str {
+    let version = version.trim().trim_start_matches(['=', ' ']);
+    match version.strip_prefix(['v', 'V']) {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_digit()) => rest,
+        _ => version,
+    }
+}
+
+// numbers compare numerically, numbers are newer than words, missing numbers are 0
+fn cmp_generic(a: &str, b: &str) -> Ordering {
+    let (a, b) = (tokenize(strip_prefix(a)), tokenize(strip_prefix(b)));
+    for i in 0..a.len().max(b.len()) {
+        let ord = match (a.get(i), b.get(i)) {
+            (Some(Item::Num(x)), Some(Item::Num(y))) => x.cmp(y),
+            (Some(Item::Str(x)), Some(Item::Str(y))) => x.cmp(y),
+            (Some(Item::Num(_)), Some(Item::Str(_))) => Ordering::Greater,
+            (Some(Item::Str(_)), Some(Item::Num(_))) => Ordering::Less,
+            (Some(Item::Num(x)), None) => x.cmp(&0),
+            (None, Some(Item::Num(y))) => 0.cmp(y),
+            (Some(Item::Str(_)), None) => Ordering::Less,
+            (None, Some(Item::Str(_))) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+
+    Ordering::Equal
+}
+
+// semver 2.0, also used by go modules (v prefix, +incompatible) and nuget (4 part, case insensitive)
+fn cmp_semver(a: &str, b: &str) -> Ordering {
+    let split = |v: &str| -> (Vec<u64>, Option<Vec<String>>) {
+        let v = strip_prefix(v);
+        let v = v.split('+').next().unwrap_or(v);
+        let (core, pre) = match v.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (v, None),
+        };
+        let core: Vec<u64> = core
+            .split('.')
+            .map(|n| n.trim().parse::<u64>().unwrap_or(0))
+            .collect();
+        let pre = pre.map(|pre| pre.split('.').map(|id| id.to_lowercase()).collect());
+        (core, pre)
+    };
+    let ((a_core, a_pre), (b_core, b_pre)) = (split(a), split(b));
+
+    for i in 0..a_core.len().max(b_core.len()).max(3) {
+        let ord = a_core.get(i).unwrap_or(&0).cmp(b_core.get(i).unwrap_or(&0));
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+
+    match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a_pre), Some(b_pre)) => {
+            for (x, y) in a_pre.iter().zip(b_pre.iter()) {
+                let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            a_pre.len().cmp(&b_pre.len())
+        }
+    }
+}
+
+// pep 440: epoch, release, pre (a < b < rc), post, dev
+fn pep440_key(version: &str) -> (u64, Vec<u64>, (u8, u64), i64, u64) {
+    let version = strip_prefix(&version.to_lowercase()).to_string();
+    let version = version.split('+').next().unwrap_or("").to_string();
+    let (epoch, rest) = match version.split_once('!') {
+        Some((epoch, rest)) => (epoch.parse::<u64>().unwrap_or(0), rest.to_string()),
+        None => (0, version),
+    };
+
+    let items = tokenize(rest.as_str());
+    let mut release: Vec<u64> = vec![];
+    let mut idx = 0;
+    while let Some(Item::Num(n)) = items.get(idx) {
+        release.push(*n);
+        idx += 1;
+    }
+    while release.len() > 1 && release.last() == Some(&0) {
+        release.pop();
+    }
+
+    let mut pre: Option<(u8, u64)> = None;
+    let mut post: Option<u64> = None;
+    let mut dev: Option<u64> = None;
+    while idx < items.len() {
+        let next_num = match items.get(idx + 1) {
+            Some(Item::Num(n)) => Some(*n),
+            _ => None,
+        };
+        match &items[idx] {
+            Item::Str(s) => {
+                match s.as_str() {
+                    "a" | "alpha" => pre = Some((1, next_num.unwrap_or(0))),
+                    "b" | "beta" => pre = Some((2, next_num.unwrap_or(0))),
+                    "c" | "rc" | "pre" | "preview" => pre = Some((3, next_num.unwrap_or(0))),
+                    "post" | "rev" | "r" => post = Some(next_num.unwrap_or(0)),
+                    "dev" => dev = Some(next_num.unwrap_or(0)),
+                    _ => {}
+                }
+                idx += if next_num.is_some() { 2 } else { 1 };
+            }
+            // implicit post release, e.g. 1.0-1
+            Item::Num(n) => {
+                post = Some(*n);
+                idx += 1;
+            }
+        }
+    }
+
+    let pre_key = match (pre, post, dev) {
+        (Some(pre), _, _) => pre,
+        // 1.0.dev1 sorts before 1.0a1
+        (None, None, Some(_)) => (0, 0),
+        _ => (4, 0),
+    };
+    (
+        epoch,
+        release,
+        pre_key,
+        post.map(|n| n as i64).unwrap_or(-1),
+        dev.unwrap_or(u64::MAX),
+    )
+}
+
+fn cmp_pep440(a: &str, b: &str) -> Ordering {
+    let (a, b) = (pep440_key(a), pep440_key(b));
+    let release = {
+        let mut ord = Ordering::Equal;
+        for i in 0..a.1.len().max(b.1.len()) {
+            ord = a.1.get(i).unwrap_or(&0).cmp(b.1.get(i).unwrap_or(&0));
+            if ord != Ordering::Equal {
+                break;
+            }
+        }
+        ord
+    };
+
+    a.0.cmp(&b.0)
+        .then(release)
+        .then(a.2.cmp(&b.2))
+        .then(a.3.cmp(&b.3))
+        .then(a.4.cmp(&b.4))
+}
+
+// maven ComparableVersion qualifier order, unknown qualifiers sort after sp
+fn maven_qualifier(qualifier: &str) -> (u8, String) {
+    let rank = match qualifier {
+        "alpha" | "a" => 0,
+        "beta" | "b" => 1,
+        "milestone" | "m" => 2,
+        "rc" | "cr" => 3,
+        "snapshot" => 4,
+        "" | "ga" | "final" | "release" => 5,
+        "sp" => 6,
+        _ => 7,
+    };
+    let name = if rank == 7 { qualifier.to_string() } else { String::new() };
+
+    (rank, name)
+}
+
+fn cmp_maven(a: &str, b: &str) -> Ordering {
+    let (a, b) = (tokenize(strip_prefix(a)), tokenize(strip_prefix(b)));
+    let null = Item::Str(String::new());
+    for i in 0..a.len().max(b.len()) {
+        let (x, y) = (a.get(i).unwrap_or(&null), b.get(i).unwrap_or(&null));
+        let ord = match (x, y) {
+            (Item::Num(x), Item::Num(y)) => x.cmp(y),
+            (Item::Str(x), Item::Str(y)) => maven_qualifier(x).cmp(&maven_qualifier(y)),
+            // padding: missing number is 0, a real number beats a qualifier
+            (Item::Num(x), Item::Str(y)) if y.is_empty() => x.cmp(&0),
+            (Item::Str(x), Item::Num(y)) if x.is_empty() => 0.cmp(y),
+            (Item::Num(_), Item::Str(_)) => Ordering::Greater,
+            (Item::Str(_), Item::Num(_)) => Ordering::Less,
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+
+    Ordering::Equal
+}
+
+// rubygems: any letter makes a prerelease, 1.0.a < 1.0
+fn cmp_gem(a: &str, b: &str) -> Ordering {
+    cmp_generic(a, b)
+}
+
+/// fixed versions named in a solution text, e.g. "Upgrade to versions 2.9.10.8, 2.10.5.1 or above."
+///
+/// a word is a version when it is dotted, or a bare number right after `to`, `version` or `>=`,
+/// so counts like "within 30 days" are skipped
+pub fn parse_fixed_versions(text: &str) -> Vec<String> {
+    let mut versions: Vec<String> = vec![];
+    let mut previous = String::new();
+    for word in text.split(|c: char| c.is_whitespace() || c == ',' || c == ';') {
+        if word.is_empty() {
+            continue;
+        }
+        let after = std::mem::replace(&mut previous, word.to_lowercase());
+        let word = word.trim_end_matches(['.', ':', ')', ']']).trim_start_matches(['(', '[']);
+        let (word, after) = match word.strip_prefix(">=") {
+            Some(rest) if !rest.is_empty() => (rest, String::from(">=")),
+            _ => (word, after),
+        };
+        let bare = strip_prefix(word);
+        if !bare.starts_with(|c: char| c.is_ascii_digit()) {
+            continue;
+        }
+        if !bare.contains('.') && !["to", "version", ">="].contains(&after.as_str()) {
+            continue;
+        }
+        if !bare
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ['.', '-', '+', '_'].contains(&c))
+        {
+            continue;
+        }
+        if !versions.contains(&word.to_string()) {
+            versions.push(word.to_string());
+        }
+    }
+
+    versions
+}
+
+/// first version of each `||` alternative of a patched range, e.g. `>=0.2.1 <1.0.0 || ^1.2.3` gives 0.2.1, 1.2.3
+pub fn range_lower_bounds(range: &str) -> Vec<String> {
+    let mut versions: Vec<String> = vec![];
+    for alternative in range.split("||") {
+        let lower = alternative
+            .split_whitespace()
+            .filter(|c| !c.starts_with('<'))
+            .map(|c| c.trim_start_matches(['>', '=', '^', '~']))
+            .find(|v| v.starts_with(|c: char| c.is_ascii_digit()));
+        if let Some(v) = lower.filter(|v| !versions.contains(&v.to_string())) {
+            versions.push(v.to_string());
+        }
+    }
+
+    versions
+}
+
+/// first version above each `||` alternative of a vulnerable range, e.g. `>=1.0.0 <1.2.6` gives 1.2.6
+pub fn range_upper_bounds(range: &str) -> Vec<String> {
+    let mut versions: Vec<String> = vec![];
+    for alternative in range.split("||") {
+        // `<=` names the last vulnerable version, the fix is unknown
+        let upper = alternative
+            .split_whitespace()
+            .filter(|c| c.starts_with('<') && !c.starts_with("<="))
+            .map(|c| c.trim_start_matches('<'))
+            .find(|v| v.starts_with(|c: char| c.is_ascii_digit()));
+        if let Some(v) = upper.filter(|v| !versions.contains(&v.to_string())) {
+            versions.push(v.to_string());
+        }
+    }
+
+    versions
+}
+
+// major.minor, a fixed version only covers its own release line
+fn release_line(version: &str) -> Vec<Item> {
+    tokenize(strip_prefix(version)).into_iter().take(2).collect()
+}
+
+// every vul lists one fixed version per release line, anything above the highest one is fixed too
+fn resolves(scheme: Scheme, candidate: &str, fixed: &[String]) -> bool {
+    let highest = fixed
+        .iter()
+        .max_by(|a, b| compare(scheme, a.as_str(), b.as_str()));
+    fixed.iter().any(|f| {
+        compare(scheme, candidate, f.as_str()) != Ordering::Less
+            && (Some(f) == highest || release_line(f) == release_line(candidate))
+    })
+}
+
+/// lowest version above `current` that fixes all vuls, `fixes` holds the fixed versions of each vul
+///
+/// returns None when a vul has no known fix
+pub fn minimal_safe_version(scheme: Scheme, current: &str, fixes: &[Vec<String>]) -> Option<String> {
+    if fixes.is_empty() || fixes.iter().any(|fixed| fixed.is_empty()) {
+        return None;
+    }
+
+    let mut candidates: Vec<&String> = fixes
+        .iter()
+        .flatten()
+        .filter(|v| compare(scheme, v.as_str(), current) == Ordering::Greater)
+        .collect();
+    candidates.sort_by(|a, b| compare(scheme, a.as_str(), b.as_str()));
+    candidates.dedup();
+
+    candidates
+        .into_iter()
+        .find(|candidate| {
+            fixes
+                .iter()
+                .all(|fixed| resolves(scheme, candidate.as_str(), fixed))
+        })
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ascending(scheme: Scheme, versions: &[&str]) {
+        for pair in versions.windows(2) {
+            assert_eq!(compare(scheme, pair[0], pair[1]), Ordering::Less, "{scheme:?} {} < {}", pair[0], pair[1]);
+            assert_eq!(compare(scheme, pair[1], pair[0]), Ordering::Greater, "{scheme:?} {} > {}", pair[1], pair[0]);
+        }
+    }
+
+    #[test]
+    fn test_compare_semver() {
+        ascending(
+            Scheme::Npm,
+            &["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-beta", "1.0.0-beta.2", "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0", "1.0.1", "1.10.0"],
+        );
+        assert_eq!(compare(Scheme::Go, "v1.2.3", "1.2.3"), Ordering::Equal);
+        assert_eq!(compare(Scheme::Go, "v2.0.0+incompatible", "v2.0.0"), Ordering::Equal);
+        assert_eq!(compare(Scheme::Nuget, "4.0.0.1", "4.0.0"), Ordering::Greater);
+        assert_eq!(compare(Scheme::Cargo, "1.0", "1.0.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_compare_pep440() {
+        ascending(
+            Scheme::Pypi,
+            &["1.0.dev1", "1.0a1", "1.0b2", "1.0rc1", "1.0", "1.0.post1", "1.0.post2", "1.1", "v2.0", "1!0.9"],
+        );
+        assert_eq!(compare(Scheme::Pypi, "1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare(Scheme::Pypi, "1.0+local", "1.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_compare_maven() {
+        ascending(
+            Scheme::Maven,
+            &["1.0-alpha-1", "1.0-beta", "1.0-M2", "1.0-RC1", "1.0-SNAPSHOT", "1.0", "1.0-sp1", "1.0.1", "2.13.4", "2.13.4.2"],
+        );
+        assert_eq!(compare(Scheme::Maven, "1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare(Scheme::Maven, "1.0.Final", "1.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_compare_gem_and_generic() {
+        ascending(Scheme::Gem, &["1.0.a", "1.0", "1.0.1"]);
+        ascending(Scheme::Generic, &["v2", "9.1", "10"]);
+    }
+
+    #[test]
+    fn test_minimal_safe_version() {
+        let fixes = |list: &[&[&str]]| -> Vec<Vec<String>> {
+            list.iter().map(|f| f.iter().map(|v| v.to_string()).collect()).collect()
+        };
+        let jackson = fixes(&[&["2.12.7.1", "2.13.4.2"], &["2.12.6"]]);
+        assert_eq!(minimal_safe_version(Scheme::Maven, "2.12.1", &jackson).as_deref(), Some("2.12.7.1"));
+        assert_eq!(minimal_safe_version(Scheme::Maven, "2.13.0", &jackson).as_deref(), Some("2.13.4.2"));
+        assert_eq!(
+            minimal_safe_version(Scheme::Maven, "1.0-SNAPSHOT", &fixes(&[&["1.0"]])).as_deref(),
+            Some("1.0")
+        );
+
+        let npm = fixes(&[&["v1.2.5"], &["1.3.0-beta.1", "1.2.4"]]);
+        assert_eq!(minimal_safe_version(Scheme::Npm, "1.2.3", &npm).as_deref(), Some("v1.2.5"));
+        assert_eq!(minimal_safe_version(Scheme::Npm, "1.2.6", &npm).as_deref(), Some("1.3.0-beta.1"));
+
+        assert_eq!(minimal_safe_version(Scheme::Pypi, "1.0", &fixes(&[&["1.0.post1"]])).as_deref(), Some("1.0.post1"));
+        assert_eq!(minimal_safe_version(Scheme::Pypi, "1.9", &fixes(&[&["2.0rc1"]])).as_deref(), Some("2.0rc1"));
+        assert_eq!(minimal_safe_version(Scheme::Pypi, "1!1.0", &fixes(&[&["2.0", "1!1.1"]])).as_deref(), Some("1!1.1"));
+
+        assert_eq!(minimal_safe_version(Scheme::Npm, "1.0.0", &fixes(&[&["1.0.1"], &[]])), None);
+        assert_eq!(minimal_safe_version(Scheme::Npm, "2.0.0", &fixes(&[&["1.0.1"]])), None);
+    }
+
+    #[test]
+    fn test_parse_fixed_versions() {
+        assert_eq!(
+            parse_fixed_versions("Upgrade to versions 2.12.7.1, 2.13.4.2 or later. Fixed in 2 releases"),
+            vec![String::from("2.12.7.1"), String::from("2.13.4.2")]
+        );
+        assert!(parse_fixed_versions("Patch within 30 days.").is_empty());
+        assert_eq!(parse_fixed_versions("Upgrade to 5 or v6.0.1."), vec![String::from("5"), String::from("v6.0.1")]);
+        assert_eq!(parse_fixed_versions("Requires (>=8) or >= 9"), vec![String::from("8"), String::from("9")]);
+        assert_eq!(parse_fixed_versions("upgrade to version v5"), vec![String::from("v5")]);
+    }
+}
@@ -0,0 +1,3 @@
+function render(el, html) {
+  el.innerHTML = html;
+}
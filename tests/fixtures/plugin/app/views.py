@@ -0,0 +1,15 @@
+import subprocess
+
+import yaml
+
+
+def run(cmd):
+    return subprocess.run(cmd, shell=True)
+
+
+def load(text):
+    return yaml.load(text)
+
+
+def calc(expr):
+    return eval(expr)
@@ -0,0 +1,2 @@
+def test_calc():
+    assert eval("1 + 1") == 2
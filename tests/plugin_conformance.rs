@@ -0,0 +1,251 @@
+//! conformance of the external command plugins, see `parser::plugin` for the protocol
+
+use std::{
+    env, process,
+    time::{Duration, Instant},
+};
+
+use sec_engine::parser::{
+    base::{BaseParser, Finding},
+    container::ContainerReport,
+    dast::DastReport,
+    detect::{Engine, Format},
+    iac::IacReport,
+    plugin::{self, Plugin, PROTOCOL},
+    sast::SASTReport,
+    sca::SCAReport,
+    secret::SecretReport,
+};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/plugin");
+const REFERENCE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/plugins/insecure_calls.py");
+
+fn shell(name: &str, script: &str, timeout: u64) -> Plugin {
+    Plugin {
+        name: name.to_string(),
+        engine: Engine::Sast,
+        command: String::from("sh"),
+        args: vec![String::from("-c"), script.to_string()],
+        env: vec![],
+        timeout: Duration::from_secs(timeout),
+    }
+}
+
+fn python3() -> bool {
+    process::Command::new("python3").arg("--version").output().is_ok()
+}
+
+#[tokio::test]
+async fn reference_plugin_conforms() {
+    if !python3() {
+        eprintln!("python3 not found, skip");
+        return;
+    }
+    let plugin = Plugin {
+        name: String::from("insecure-calls"),
+        engine: Engine::Sast,
+        command: String::from("python3"),
+        args: vec![REFERENCE.to_string(), String::from("{target}")],
+        env: vec![(String::from("INSECURE_CALLS_EXCLUDE"), String::from("tests"))],
+        timeout: Duration::from_secs(60),
+    };
+    let output = plugin.execute(FIXTURE).await.expect("reference plugin failed");
+    assert_eq!(output.stderr, vec![String::from("scanned 2 files, 4 findings")]);
+
+    let findings = plugin::parse_output(Engine::Sast, output.stdout.as_str()).expect("output breaks the protocol");
+    assert_eq!(findings.len(), 4);
+    assert!(findings.iter().all(|f| f.engine == "sast" && !f.fingerprint.is_empty()));
+    let eval = findings.iter().find(|f| f.identifiers.contains(&String::from("insecure-calls.eval"))).unwrap();
+    assert_eq!((eval.file.as_str(), eval.line, eval.severity.as_str()), ("app/views.py", 15, "High"));
+    assert!(findings.iter().all(|f| !f.file.starts_with("tests/")));
+
+    let input = plugin.run(FIXTURE).await.expect("no input of the reference plugin");
+    assert_eq!(input.format, Format::Command(String::from("insecure-calls"), Engine::Sast));
+    assert_eq!(input.format.engines(), vec![Engine::Sast]);
+}
+
+#[tokio::test]
+async fn environment_and_placeholders() {
+    let mut plugin = shell(
+        "env",
+        r#"echo "$1 $SEC_ENGINE_TARGET $SEC_ENGINE_ENGINE $SEC_ENGINE_PROTOCOL $EXTRA" >&2; echo '{"protocol":"sec-engine-plugin/1","findings":[]}'"#,
+        10,
+    );
+    plugin.args.push(String::from("sh"));
+    plugin.args.push(String::from("{target}"));
+    plugin.env.push((String::from("EXTRA"), String::from("extra")));
+    let output = plugin.execute("/src").await.unwrap();
+    assert_eq!(output.stderr, vec![format!("/src /src sast {PROTOCOL} extra")]);
+}
+
+#[tokio::test]
+async fn job_tokens_are_not_passed() {
+    env::set_var("GITLAB_TOKEN", "glpat-secret");
+    env::set_var("AI_TOKEN", "sk-secret");
+    let mut plugin = shell(
+        "tokens",
+        r#"echo "${GITLAB_TOKEN:-unset} ${AI_TOKEN:-unset} ${CI_JOB_TOKEN:-unset}" >&2; echo '{"protocol":"sec-engine-plugin/1","findings":[]}'"#,
+        10,
+    );
+    let output = plugin.execute(FIXTURE).await.unwrap();
+    assert_eq!(output.stderr, vec![String::from("unset unset unset")]);
+
+    // named in the config on purpose
+    plugin.env.push((String::from("CI_JOB_TOKEN"), String::from("job-token")));
+    let output = plugin.execute(FIXTURE).await.unwrap();
+    assert_eq!(output.stderr, vec![String::from("unset unset job-token")]);
+}
+
+#[tokio::test]
+async fn stderr_is_captured() {
+    let plugin = shell(
+        "noisy",
+        r#"echo "loading rules" >&2; echo "warning: slow" >&2; echo '{"protocol":"sec-engine-plugin/1","findings":[]}'"#,
+        10,
+    );
+    let output = plugin.execute(FIXTURE).await.unwrap();
+    assert_eq!(output.stderr, vec![String::from("loading rules"), String::from("warning: slow")]);
+    assert!(plugin::parse_output(Engine::Sast, output.stdout.as_str()).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn timeout_kills_the_plugin() {
+    let plugin = shell("slow", "echo started >&2; sleep 30", 1);
+    let start = Instant::now();
+    let err = plugin.execute(FIXTURE).await.unwrap_err();
+    assert!(err.contains("timed out"), "{err}");
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert!(plugin.run(FIXTURE).await.is_none());
+}
+
+#[tokio::test]
+async fn non_zero_exit_fails() {
+    let plugin = shell(
+        "broken",
+        r#"echo '{"protocol":"sec-engine-plugin/1","findings":[]}'; echo "license expired" >&2; exit 3"#,
+        10,
+    );
+    let err = plugin.execute(FIXTURE).await.unwrap_err();
+    assert!(err.contains("license expired"), "{err}");
+    assert!(plugin.run(FIXTURE).await.is_none());
+}
+
+#[tokio::test]
+async fn missing_executable_fails() {
+    let mut plugin = shell("missing", "", 10);
+    plugin.command = String::from("/nonexistent/sec-engine-plugin");
+    assert!(plugin.execute(FIXTURE).await.is_err());
+    assert!(plugin.run(FIXTURE).await.is_none());
+}
+
+#[tokio::test]
+async fn broken_output_is_dropped() {
+    for script in ["echo 'not json'", r#"echo '{"protocol":"sec-engine-plugin/2","findings":[]}'"#, r#"echo '{"findings":[]}'"#] {
+        assert!(shell("bad", script, 10).run(FIXTURE).await.is_none(), "{script}");
+    }
+}
+
+#[test]
+fn protocol_documents() {
+    assert!(plugin::parse_output(Engine::Sast, "").is_err());
+    assert!(plugin::parse_output(Engine::Sast, "[]").is_err());
+    assert!(plugin::parse_output(Engine::Sast, r#"{"protocol":"sec-engine-plugin/1"}"#).is_err());
+    assert!(plugin::parse_output(Engine::Sast, r#"{"protocol":"sec-engine-plugin/1","findings":{}}"#).is_err());
+    assert!(plugin::parse_output(Engine::Sast, r#"{"protocol":"other/1","findings":[]}"#).is_err());
+
+    let doc = r#"{
+        "protocol": "sec-engine-plugin/1",
+        "tool": { "name": "acme", "version": "2.1" },
+        "findings": [
+            { "name": "minimal" },
+            { "name": "full", "severity": "CRITICAL", "description": "text", "identifiers": ["CWE-1", 7],
+              "file": "./src/a.rs", "line": 3, "end_line": 5, "dependency": "serde:1.0.0", "fingerprint": "fp" },
+            { "name": "" },
+            { "severity": "high" },
+            { "name": "bad severity", "severity": "urgent" },
+            "not an object"
+        ]
+    }"#;
+    let findings = plugin::parse_output(Engine::Sca, doc).unwrap();
+    assert_eq!(findings.len(), 2);
+    assert_eq!(findings[0].severity, "Unknown");
+    assert_eq!(findings[0].engine, "sca");
+    let full = &findings[1];
+    assert_eq!(full.severity, "Critical");
+    assert_eq!(full.identifiers, vec![String::from("CWE-1")]);
+    assert_eq!((full.file.as_str(), full.line, full.end_line), ("src/a.rs", 3, 5));
+    assert_eq!(full.package(), (String::from("serde"), String::from("1.0.0")));
+    assert_eq!(full.fingerprint, "fp");
+}
+
+// the id of the plugin reaches code quality, exports and dedupe
+fn merged<T, R: BaseParser<T>>(mut report: R, findings: &[Finding]) -> Vec<String> {
+    report.merge(findings);
+    report.findings().into_iter().map(|f| f.fingerprint).collect()
+}
+
+#[test]
+fn fingerprint_survives_the_merge() {
+    let doc = r#"{
+        "protocol": "sec-engine-plugin/1",
+        "findings": [
+            { "name": "given", "severity": "high", "identifiers": ["CVE-2024-0001"], "file": "package-lock.json",
+              "line": 3, "dependency": "lodash:4.17.20", "fingerprint": "plugin-id-1" },
+            { "name": "computed", "severity": "low", "identifiers": ["CVE-2024-0002"], "file": "package-lock.json",
+              "line": 9, "dependency": "minimist:1.2.0" }
+        ]
+    }"#;
+    let findings = plugin::parse_output(Engine::Sast, doc).unwrap();
+    for fingerprints in [
+        merged(SASTReport::new(), &findings),
+        merged(SCAReport::new(), &findings),
+        merged(SecretReport::new(), &findings),
+        merged(IacReport::new(), &findings),
+        merged(ContainerReport::new(), &findings),
+        merged(DastReport::new(), &findings),
+    ] {
+        assert_eq!(fingerprints.len(), 2);
+        assert!(fingerprints.contains(&String::from("plugin-id-1")), "{fingerprints:?}");
+        assert!(fingerprints.iter().all(|f| !f.is_empty()));
+    }
+}
+
+#[test]
+fn config_entries() {
+    let plugins = plugin::config(
+        r#"{
+        "plugins": [
+            { "name": "a", "engine": "sast", "command": "a-scan", "args": ["--json", 1], "env": { "K": "V" }, "timeout": 5 },
+            { "name": "b", "engine": "secret", "command": "b-scan" },
+            { "name": "a", "engine": "sca", "command": "again" },
+            { "name": "c", "engine": "fuzz", "command": "c-scan" },
+            { "name": "d", "engine": "iac" },
+            { "engine": "iac", "command": "e-scan" }
+        ]
+    }"#,
+    );
+    assert_eq!(plugins.iter().map(|p| p.name.as_str()).collect::<Vec<&str>>(), vec!["a", "b"]);
+    assert_eq!(plugins[0].args, vec![String::from("--json")]);
+    assert_eq!(plugins[0].env, vec![(String::from("K"), String::from("V"))]);
+    assert_eq!(plugins[0].timeout, Duration::from_secs(5));
+    assert_eq!(plugins[1].engine, Engine::Secret);
+    assert!(plugins[1].timeout > Duration::from_secs(0));
+
+    assert!(plugin::config("not json").is_empty());
+    assert!(plugin::load("/nonexistent/sec-engine-plugins.json").is_empty());
+    assert!(plugin::load("").is_empty());
+}
+
+#[tokio::test]
+async fn only_plugins_of_the_running_engines() {
+    let mut secret = shell("secret", r#"echo '{"protocol":"sec-engine-plugin/1","findings":[{"name":"key"}]}'"#, 10);
+    secret.engine = Engine::Secret;
+    let sast = shell("sast", r#"echo '{"protocol":"sec-engine-plugin/1","findings":[]}'"#, 10);
+    let plugins = vec![secret, sast];
+
+    let inputs = plugin::inputs(&plugins, &[Engine::Secret], FIXTURE).await;
+    assert_eq!(inputs.len(), 1);
+    assert_eq!(inputs[0].path, "plugin:secret");
+    assert!(plugin::inputs(&plugins, &[Engine::Dast], FIXTURE).await.is_empty());
+    assert_eq!(plugin::inputs(&plugins, &Engine::ALL, FIXTURE).await.len(), 2);
+}